@@ -3,3 +3,6 @@
 #![allow(non_snake_case)]
 
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+/// Version of the bundled NGT library, which versions of this crate follow.
+pub const NGT_VERSION: &str = env!("CARGO_PKG_VERSION");
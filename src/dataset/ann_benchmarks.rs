@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use hdf5::types::VarLenUnicode;
+
+use super::recall;
+use crate::error::Result;
+use crate::ngt::{NgtDistance, NgtIndex, NgtProperties};
+use crate::VecId;
+
+/// A dataset in the [ann-benchmarks][] HDF5 format, made of `train` vectors to index,
+/// `test` query vectors and the `neighbors` of each query among the `train` vectors.
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), ngt::Error> {
+/// use ngt::dataset::AnnBenchmark;
+/// use ngt::NgtProperties;
+///
+/// let dataset = AnnBenchmark::open("sift-128-euclidean.hdf5")?;
+/// let prop = NgtProperties::dimension(dataset.dimension())?
+///     .distance_type(dataset.distance_type().unwrap())?;
+/// let index = dataset.build_index("target/path/to/bench_index/dir", prop, 4)?;
+/// let recall = dataset.evaluate(&index, 10, 0.1)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [ann-benchmarks]: https://github.com/erikbern/ann-benchmarks
+#[derive(Debug, Clone)]
+pub struct AnnBenchmark {
+    /// The vectors to index.
+    pub train: Vec<Vec<f32>>,
+    /// The query vectors.
+    pub test: Vec<Vec<f32>>,
+    /// The positions in `train` of the nearest neighbors of each query.
+    pub neighbors: Vec<Vec<usize>>,
+    /// The name of the distance function, such as `euclidean` or `angular`.
+    pub distance: Option<String>,
+}
+
+impl AnnBenchmark {
+    /// Load the dataset from the `.hdf5` file at the specified path.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = hdf5::File::open(path)?;
+
+        let read = |name: &str| -> Result<Vec<Vec<f32>>> {
+            let dataset = file.dataset(name)?;
+            let dim = dataset.shape().get(1).copied().unwrap_or_default();
+            let data = dataset.read_raw::<f32>()?;
+            Ok(data.chunks(dim.max(1)).map(<[f32]>::to_vec).collect())
+        };
+        let train = read("train")?;
+        let test = read("test")?;
+
+        let dataset = file.dataset("neighbors")?;
+        let k = dataset.shape().get(1).copied().unwrap_or_default();
+        let neighbors = dataset
+            .read_raw::<i64>()?
+            .chunks(k.max(1))
+            .map(|ids| ids.iter().map(|&id| usize::try_from(id)).collect())
+            .collect::<std::result::Result<_, _>>()?;
+
+        let distance = file
+            .attr("distance")
+            .and_then(|attr| attr.read_scalar::<VarLenUnicode>())
+            .map(|distance| distance.as_str().to_string())
+            .ok();
+
+        Ok(Self {
+            train,
+            test,
+            neighbors,
+            distance,
+        })
+    }
+
+    /// The dimension of the vectors.
+    pub fn dimension(&self) -> usize {
+        self.train.first().map(Vec::len).unwrap_or_default()
+    }
+
+    /// The NGT distance function corresponding to the dataset distance, if any.
+    ///
+    /// The `hamming` and `jaccard` datasets have none, NGT only supports these
+    /// distances for `u8` vectors while the datasets are loaded as `f32` vectors.
+    pub fn distance_type(&self) -> Option<NgtDistance> {
+        match self.distance.as_deref()? {
+            "euclidean" => Some(NgtDistance::L2),
+            "angular" => Some(NgtDistance::Angle),
+            _ => None,
+        }
+    }
+
+    /// Create an index at the specified path, insert the `train` vectors and build it
+    /// with `num_threads` threads.
+    pub fn build_index<P: AsRef<Path>>(
+        &self,
+        path: P,
+        prop: NgtProperties<f32>,
+        num_threads: usize,
+    ) -> Result<NgtIndex<f32>> {
+        let mut index = NgtIndex::create(path, prop)?;
+        let data = self.train.concat();
+        index.insert_batch_flat(&data, self.train.len())?;
+        index.build(num_threads)?;
+        Ok(index)
+    }
+
+    /// The mean recall of the `res_size` nearest vectors found for each `test` query,
+    /// against their `res_size` true `neighbors`, see [`recall`][].
+    ///
+    /// The index must have been created by [`build_index`](AnnBenchmark::build_index),
+    /// so that the vector at position `i` of `train` has the id `i + 1`.
+    pub fn evaluate(&self, index: &NgtIndex<f32>, res_size: usize, epsilon: f32) -> Result<f64> {
+        let mut total = 0.;
+        for (query, neighbors) in self.test.iter().zip(&self.neighbors) {
+            let found = index
+                .search(query, res_size, epsilon)?
+                .into_iter()
+                .map(|r| r.id)
+                .collect::<Vec<_>>();
+            let truth = neighbors
+                .iter()
+                .take(res_size)
+                .map(|&i| VecId::try_from(i + 1))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            total += recall(&found, &truth);
+        }
+        Ok(total / self.test.len().max(1) as f64)
+    }
+}
@@ -0,0 +1,343 @@
+//! Readers for the standard ANN benchmark formats, and bulk ingestion of their vectors
+//! into an [`NgtIndex`][].
+//!
+//! The `.fvecs`, `.bvecs` and `.ivecs` formats (as used by the SIFT and GIST datasets)
+//! store each vector as its dimension, a little-endian `i32`, followed by its elements
+//! as little-endian `f32`, `u8` or `i32` respectively.
+//!
+//! ```rust,no_run
+//! # fn main() -> Result<(), ngt::Error> {
+//! use ngt::dataset;
+//! use ngt::{NgtIndex, NgtProperties};
+//!
+//! let prop = NgtProperties::<f32>::dimension(128)?;
+//! let mut index = NgtIndex::create("target/path/to/sift_index/dir", prop)?;
+//!
+//! let vecs = dataset::read_fvecs("sift/sift_base.fvecs")?;
+//! dataset::ingest(&mut index, vecs, 10_000, |n| println!("{n} vectors inserted"))?;
+//! index.build(4)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! With the `hdf5` feature, datasets in the [ann-benchmarks][] format can be loaded
+//! and evaluated with `AnnBenchmark`. With the `parquet` feature, embeddings exported
+//! to Parquet files can be ingested with `ParquetEmbeddings`.
+//!
+//! [ann-benchmarks]: https://github.com/erikbern/ann-benchmarks
+
+#[cfg(feature = "hdf5")]
+mod ann_benchmarks;
+#[cfg(feature = "parquet")]
+mod parquet;
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind as IoErrorKind, Read};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::ngt::{NgtIndex, NgtObjectType};
+use crate::VecId;
+
+#[cfg(feature = "hdf5")]
+pub use self::ann_benchmarks::AnnBenchmark;
+#[cfg(feature = "parquet")]
+pub use self::parquet::ParquetEmbeddings;
+
+/// Number of elements allocated upfront at most for a batch of [`ingest`][], larger
+/// batches grow as their vectors are read.
+const MAX_BATCH_PREALLOC: usize = 1 << 20;
+
+/// Streams the vectors of a `.fvecs`, `.bvecs` or `.ivecs` file, see
+/// [`read_fvecs`][], [`read_bvecs`][] and [`read_ivecs`][].
+#[derive(Debug)]
+pub struct VecsReader<R, T> {
+    reader: R,
+    dimension: Option<usize>,
+    _marker: PhantomData<T>,
+}
+
+impl<R, T> VecsReader<R, T>
+where
+    R: Read,
+    T: VecsElement,
+{
+    /// Read vectors from the specified reader.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            dimension: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The dimension of the vectors read so far.
+    pub fn dimension(&self) -> Option<usize> {
+        self.dimension
+    }
+
+    fn read_vec(&mut self) -> Result<Option<Vec<T>>> {
+        // Only a clean end of file ends the stream, a partial header is a truncation
+        let mut dim = [0; 4];
+        let mut read = 0;
+        while read < dim.len() {
+            match self.reader.read(&mut dim[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == IoErrorKind::Interrupted => (),
+                Err(e) => Err(e)?,
+            }
+        }
+        match read {
+            0 => return Ok(None),
+            4 => (),
+            _ => Err(format!("Truncated vector header of {read} bytes"))?,
+        }
+        let dim = usize::try_from(i32::from_le_bytes(dim))?;
+        match self.dimension {
+            Some(expected) if expected != dim => Err(format!(
+                "Inconsistent vector dimension, expected: {expected} got: {dim}"
+            ))?,
+            _ => self.dimension = Some(dim),
+        }
+
+        // The dimension isn't trusted to allocate, only the bytes actually read are
+        let len = dim
+            .checked_mul(T::SIZE)
+            .ok_or_else(|| format!("Invalid vector dimension {dim}"))?;
+        let mut bytes = Vec::new();
+        (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() != len {
+            Err(format!(
+                "Truncated vector, expected: {len} bytes got: {}",
+                bytes.len()
+            ))?
+        }
+        Ok(Some(bytes.chunks_exact(T::SIZE).map(T::from_le).collect()))
+    }
+}
+
+impl<R, T> Iterator for VecsReader<R, T>
+where
+    R: Read,
+    T: VecsElement,
+{
+    type Item = Result<Vec<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_vec().transpose()
+    }
+}
+
+/// Elements of the vectors stored in `.fvecs`, `.bvecs` and `.ivecs` files.
+pub trait VecsElement: private::Sealed + Sized {
+    #[doc(hidden)]
+    const SIZE: usize;
+
+    #[doc(hidden)]
+    fn from_le(bytes: &[u8]) -> Self;
+}
+
+impl VecsElement for f32 {
+    const SIZE: usize = 4;
+
+    fn from_le(bytes: &[u8]) -> Self {
+        f32::from_le_bytes(bytes.try_into().unwrap())
+    }
+}
+
+impl VecsElement for u8 {
+    const SIZE: usize = 1;
+
+    fn from_le(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
+}
+
+impl VecsElement for i32 {
+    const SIZE: usize = 4;
+
+    fn from_le(bytes: &[u8]) -> Self {
+        i32::from_le_bytes(bytes.try_into().unwrap())
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for f32 {}
+    impl Sealed for u8 {}
+    impl Sealed for i32 {}
+}
+
+/// Stream the `f32` vectors of the `.fvecs` file at the specified path.
+pub fn read_fvecs<P: AsRef<Path>>(path: P) -> Result<VecsReader<BufReader<File>, f32>> {
+    Ok(VecsReader::new(BufReader::new(File::open(path)?)))
+}
+
+/// Stream the `u8` vectors of the `.bvecs` file at the specified path.
+pub fn read_bvecs<P: AsRef<Path>>(path: P) -> Result<VecsReader<BufReader<File>, u8>> {
+    Ok(VecsReader::new(BufReader::new(File::open(path)?)))
+}
+
+/// Stream the `i32` vectors of the `.ivecs` file at the specified path, typically the
+/// ids of the ground truth neighbors of queries.
+pub fn read_ivecs<P: AsRef<Path>>(path: P) -> Result<VecsReader<BufReader<File>, i32>> {
+    Ok(VecsReader::new(BufReader::new(File::open(path)?)))
+}
+
+/// Insert all the vectors into the index by batches of `batch_size`, calling
+/// `progress` with the number of vectors inserted so far after each batch. Returns
+/// the number of inserted vectors.
+///
+/// A `batch_size` of [`usize::MAX`] inserts all the vectors in a single batch.
+///
+/// **The method [`build`](NgtIndex::build) must be called after inserting vectors**.
+pub fn ingest<T, I, F>(
+    index: &mut NgtIndex<T>,
+    vecs: I,
+    batch_size: usize,
+    mut progress: F,
+) -> Result<usize>
+where
+    T: NgtObjectType,
+    I: IntoIterator<Item = Result<Vec<T>>>,
+    F: FnMut(usize),
+{
+    let batch_size = batch_size.max(1);
+    let dim = index.dimension();
+    // Batches too large to be addressed are only inserted once all vectors are read
+    let batch_len = batch_size.saturating_mul(dim);
+    let mut batch = Vec::with_capacity(batch_len.min(MAX_BATCH_PREALLOC));
+    let mut inserted = 0;
+
+    for vec in vecs {
+        let vec = vec?;
+        if vec.len() != dim {
+            Err(format!(
+                "Inconsistent vector dimension, expected: {dim} got: {}",
+                vec.len()
+            ))?
+        }
+        batch.extend(vec);
+        if batch.len() == batch_len {
+            index.insert_batch_flat(&batch, batch_size)?;
+            inserted += batch_size;
+            batch.clear();
+            progress(inserted);
+        }
+    }
+    if !batch.is_empty() {
+        let rows = batch.len() / dim;
+        index.insert_batch_flat(&batch, rows)?;
+        inserted += rows;
+        progress(inserted);
+    }
+
+    Ok(inserted)
+}
+
+/// The fraction of the `truth` ids that are `found`, 1 when `truth` is empty.
+pub fn recall(found: &[VecId], truth: &[VecId]) -> f64 {
+    if truth.is_empty() {
+        return 1.;
+    }
+    let found = found.iter().collect::<HashSet<_>>();
+    truth.iter().filter(|id| found.contains(id)).count() as f64 / truth.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::result::Result as StdResult;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::NgtProperties;
+
+    fn fvecs(vecs: &[&[f32]]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for vec in vecs {
+            bytes.extend((vec.len() as i32).to_le_bytes());
+            vec.iter().for_each(|v| bytes.extend(v.to_le_bytes()));
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_read_vecs() -> StdResult<(), Box<dyn StdError>> {
+        let bytes = fvecs(&[&[1.0, 2.0], &[3.0, 4.0]]);
+        let vecs = VecsReader::<_, f32>::new(bytes.as_slice()).collect::<Result<Vec<_>>>()?;
+        assert_eq!(vecs, vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+
+        let bytes = [2, 0, 0, 0, 7, 8];
+        let vecs = VecsReader::<_, u8>::new(&bytes[..]).collect::<Result<Vec<_>>>()?;
+        assert_eq!(vecs, vec![vec![7, 8]]);
+
+        // Inconsistent dimensions and truncated files are rejected
+        let bytes = fvecs(&[&[1.0, 2.0], &[3.0]]);
+        assert!(VecsReader::<_, f32>::new(bytes.as_slice())
+            .collect::<Result<Vec<_>>>()
+            .is_err());
+        let bytes = fvecs(&[&[1.0, 2.0]]);
+        assert!(VecsReader::<_, f32>::new(&bytes[..bytes.len() - 1])
+            .collect::<Result<Vec<_>>>()
+            .is_err());
+        assert!(VecsReader::<_, f32>::new(&bytes[..2])
+            .collect::<Result<Vec<_>>>()
+            .is_err());
+
+        // Huge dimensions aren't allocated upfront
+        let bytes = [0xff, 0xff, 0xff, 0x7f, 1, 2];
+        assert!(VecsReader::<_, u8>::new(&bytes[..])
+            .collect::<Result<Vec<_>>>()
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recall() {
+        assert_eq!(recall(&[1, 2, 3], &[3, 2, 1]), 1.);
+        assert_eq!(recall(&[1, 2, 5, 6], &[1, 2, 3, 4]), 0.5);
+        assert_eq!(recall(&[], &[]), 1.);
+    }
+
+    #[test]
+    fn test_ingest() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the dataset and the index
+        let dir = tempdir()?;
+        let path = dir.path().join("base.fvecs");
+        let vecs = (0..5).map(|i| vec![i as f32; 3]).collect::<Vec<_>>();
+        std::fs::write(
+            &path,
+            fvecs(&vecs.iter().map(Vec::as_slice).collect::<Vec<_>>()),
+        )?;
+
+        // Ingest the dataset by batches of 2 vectors
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path().join("index"), prop)?;
+        let mut reported = Vec::new();
+        let inserted = ingest(&mut index, read_fvecs(&path)?, 2, |n| reported.push(n))?;
+        assert_eq!(inserted, 5);
+        assert_eq!(reported, vec![2, 4, 5]);
+        index.build(1)?;
+        let res = index.search(&[4.0; 3], 1, 0.1)?;
+        assert_eq!(index.get_vec(res[0].id)?, vec![4.0; 3]);
+
+        // A single batch can be requested without allocating it upfront
+        let mut reported = Vec::new();
+        let inserted = ingest(&mut index, read_fvecs(&path)?, usize::MAX, |n| {
+            reported.push(n)
+        })?;
+        assert_eq!(inserted, 5);
+        assert_eq!(reported, vec![5]);
+
+        dir.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float64Type, Int64Type};
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_schema::DataType;
+
+use crate::error::Result;
+use crate::ngt::{NgtIndex, NgtObjectType};
+use crate::VecId;
+
+/// Streams embeddings stored in a Parquet file into an [`NgtIndex`][].
+///
+/// Each row holds an embedding as a list of floats, along with an optional integer id
+/// column identifying it outside of the index.
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), ngt::Error> {
+/// use ngt::dataset::ParquetEmbeddings;
+/// use ngt::{NgtIndex, NgtProperties};
+///
+/// let prop = NgtProperties::<f32>::dimension(384)?;
+/// let mut index = NgtIndex::create("target/path/to/parquet_index/dir", prop)?;
+///
+/// let ids = ParquetEmbeddings::new("embeddings.parquet")
+///     .embedding_column("embedding")
+///     .id_column("doc_id")
+///     .ingest(&mut index, |n| println!("{n} vectors inserted"))?;
+/// index.build(4)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParquetEmbeddings {
+    path: PathBuf,
+    embedding_column: String,
+    id_column: Option<String>,
+    batch_size: usize,
+}
+
+impl ParquetEmbeddings {
+    /// Read the Parquet file at the specified path, with embeddings in the `embedding`
+    /// column and without id column by default.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            embedding_column: "embedding".to_string(),
+            id_column: None,
+            batch_size: 8192,
+        }
+    }
+
+    /// The column of the embeddings, a list or a fixed size list of floats.
+    pub fn embedding_column<S: Into<String>>(mut self, column: S) -> Self {
+        self.embedding_column = column.into();
+        self
+    }
+
+    /// The column of the external ids of the embeddings, an integer column. Without id
+    /// column, the row numbers are used as external ids.
+    pub fn id_column<S: Into<String>>(mut self, column: S) -> Self {
+        self.id_column = Some(column.into());
+        self
+    }
+
+    /// The number of rows read and inserted at once.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Insert all the embeddings into the index, calling `progress` with the number of
+    /// vectors inserted so far after each batch. Returns the pairs of external ids and
+    /// vector ids.
+    ///
+    /// **The method [`build`](NgtIndex::build) must be called after inserting vectors**.
+    pub fn ingest<T, F>(
+        &self,
+        index: &mut NgtIndex<T>,
+        mut progress: F,
+    ) -> Result<Vec<(i64, VecId)>>
+    where
+        T: NgtObjectType,
+        F: FnMut(usize),
+    {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&self.path)?)?
+            .with_batch_size(self.batch_size)
+            .build()?;
+
+        let dim = index.dimension();
+        let mut ids = Vec::new();
+        for batch in reader {
+            let batch = batch?;
+            let data = self.embeddings(&batch, dim)?;
+            let external_ids = match &self.id_column {
+                Some(name) => {
+                    let column = cast(column(&batch, name)?, &DataType::Int64)?;
+                    let column = column.as_primitive::<Int64Type>();
+                    if column.null_count() > 0 {
+                        Err(format!("Null ids in column {name:?}"))?
+                    }
+                    column.values().to_vec()
+                }
+                None => {
+                    let first = i64::try_from(ids.len())?;
+                    (first..first + i64::try_from(batch.num_rows())?).collect()
+                }
+            };
+
+            let first_id = index.next_batch_id();
+            index.insert_batch_flat(&data, batch.num_rows())?;
+            ids.extend(external_ids.into_iter().zip(first_id..));
+            progress(ids.len());
+        }
+
+        Ok(ids)
+    }
+
+    /// The embeddings of the batch rows, flattened in row-major order.
+    fn embeddings<T: NgtObjectType>(&self, batch: &RecordBatch, dim: usize) -> Result<Vec<T>> {
+        let name = &self.embedding_column;
+        let column = column(batch, name)?;
+        if column.null_count() > 0 {
+            Err(format!("Null embeddings in column {name:?}"))?
+        }
+
+        let (values, offsets) = if let Some(list) = column.as_list_opt::<i32>() {
+            let offsets = list.value_offsets().iter().map(|&o| o as usize);
+            (list.values(), offsets.collect::<Vec<_>>())
+        } else if let Some(list) = column.as_list_opt::<i64>() {
+            let offsets = list.value_offsets().iter().map(|&o| o as usize);
+            (list.values(), offsets.collect::<Vec<_>>())
+        } else if let Some(list) = column.as_fixed_size_list_opt() {
+            let len = list.value_length() as usize;
+            let offsets = (0..=list.len()).map(|i| i * len);
+            (list.values(), offsets.collect::<Vec<_>>())
+        } else {
+            Err(format!(
+                "Column {name:?} of type {} isn't a list of floats",
+                column.data_type()
+            ))?
+        };
+
+        let values = cast(values, &DataType::Float64)?;
+        let values = values.as_primitive::<Float64Type>();
+        let mut data = Vec::with_capacity(batch.num_rows() * dim);
+        for range in offsets.windows(2) {
+            if range[1] - range[0] != dim {
+                Err(format!(
+                    "Inconsistent embedding dim, expected: {dim} got: {}",
+                    range[1] - range[0]
+                ))?
+            }
+            if (range[0]..range[1]).any(|i| values.is_null(i)) {
+                Err(format!("Null embedding elements in column {name:?}"))?
+            }
+            data.extend(
+                values.values()[range[0]..range[1]]
+                    .iter()
+                    .map(|&v| T::from_f64(v)),
+            );
+        }
+
+        Ok(data)
+    }
+}
+
+fn column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a ArrayRef> {
+    Ok(batch
+        .column_by_name(name)
+        .ok_or_else(|| format!("Missing column {name:?}"))?)
+}
+
+fn cast(array: &ArrayRef, to: &DataType) -> Result<ArrayRef> {
+    Ok(arrow_cast::cast(array, to)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::result::Result as StdResult;
+    use std::sync::Arc;
+
+    use ::parquet::arrow::ArrowWriter;
+    use arrow_array::builder::{Float32Builder, ListBuilder};
+    use arrow_array::Int64Array;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::NgtProperties;
+
+    #[test]
+    fn test_parquet_embeddings() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the file and the index
+        let dir = tempdir()?;
+        let path = dir.path().join("embeddings.parquet");
+
+        // Write 5 embeddings along with their ids
+        let mut embeddings = ListBuilder::new(Float32Builder::new());
+        for i in 0..5 {
+            embeddings.values().append_slice(&[i as f32; 3]);
+            embeddings.append(true);
+        }
+        let batch = RecordBatch::try_from_iter([
+            (
+                "doc_id",
+                Arc::new(Int64Array::from_iter_values(10..15)) as ArrayRef,
+            ),
+            ("vec", Arc::new(embeddings.finish()) as ArrayRef),
+        ])?;
+        let mut writer = ArrowWriter::try_new(File::create(&path)?, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        // Ingest them by batches of 2 rows
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path().join("index"), prop)?;
+        let mut reported = Vec::new();
+        let ids = ParquetEmbeddings::new(&path)
+            .embedding_column("vec")
+            .id_column("doc_id")
+            .batch_size(2)
+            .ingest(&mut index, |n| reported.push(n))?;
+        assert_eq!(reported, vec![2, 4, 5]);
+        assert_eq!(ids.len(), 5);
+        index.build(1)?;
+        let res = index.search(&[3.0; 3], 1, 0.1)?;
+        assert_eq!(ids.iter().find(|&&(_, id)| id == res[0].id).unwrap().0, 13);
+
+        // Missing columns are rejected
+        let mut index = NgtIndex::create(dir.path().join("missing"), NgtProperties::dimension(3)?)?;
+        let res = ParquetEmbeddings::new(&path).ingest::<f32, _>(&mut index, |_| ());
+        assert!(res.is_err());
+
+        // Null embedding elements are rejected
+        let mut embeddings = ListBuilder::new(Float32Builder::new());
+        embeddings.values().append_slice(&[1.0, 2.0]);
+        embeddings.values().append_null();
+        embeddings.append(true);
+        let batch =
+            RecordBatch::try_from_iter([("embedding", Arc::new(embeddings.finish()) as ArrayRef)])?;
+        let nulls = dir.path().join("nulls.parquet");
+        let mut writer = ArrowWriter::try_new(File::create(&nulls)?, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        let mut index = NgtIndex::create(dir.path().join("nulls"), NgtProperties::dimension(3)?)?;
+        let res = ParquetEmbeddings::new(&nulls).ingest::<f32, _>(&mut index, |_| ());
+        assert!(res.is_err());
+
+        dir.close()?;
+        Ok(())
+    }
+}
@@ -6,11 +6,44 @@ use ngt_sys as sys;
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
-pub struct Error(pub(crate) String);
+pub struct Error {
+    kind: ErrorKind,
+    msg: String,
+}
+
+/// The kind of an [`Error`][].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The index can't hold more vectors, as their ids would overflow [`VecId`][].
+    ///
+    /// [`VecId`]: crate::VecId
+    CapacityExceeded,
+    /// The requested vector doesn't exist, or has been removed.
+    NotFound,
+    /// The index was written by an incompatible NGT version, or holds vectors of
+    /// another object type.
+    IncompatibleIndex,
+    /// Any other error, reported either by NGT or by the Rust wrappers.
+    Other,
+}
+
+impl Error {
+    pub(crate) fn new<S: Into<String>>(kind: ErrorKind, msg: S) -> Self {
+        Self {
+            kind,
+            msg: msg.into(),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.msg)
     }
 }
 
@@ -18,77 +51,112 @@ impl std::error::Error for Error {}
 
 pub(crate) fn make_err(err: sys::NGTError) -> Error {
     let err_str = unsafe { CStr::from_ptr(sys::ngt_get_error_string(err)) };
-    let err_msg = err_str.to_string_lossy().into();
+    let err_msg = err_str.to_string_lossy().into_owned();
     unsafe { sys::ngt_clear_error_string(err) };
-    Error(err_msg)
+    Error::new(ErrorKind::Other, err_msg)
 }
 
 impl From<String> for Error {
     fn from(err: String) -> Self {
-        Self(err)
+        Self::new(ErrorKind::Other, err)
     }
 }
 
 impl From<std::io::Error> for Error {
     fn from(source: std::io::Error) -> Self {
-        Self(source.to_string())
+        Self::new(ErrorKind::Other, source.to_string())
+    }
+}
+
+#[cfg(feature = "metadata")]
+impl From<serde_json::Error> for Error {
+    fn from(source: serde_json::Error) -> Self {
+        Self::new(ErrorKind::Other, source.to_string())
+    }
+}
+
+#[cfg(feature = "hdf5")]
+impl From<hdf5::Error> for Error {
+    fn from(source: hdf5::Error) -> Self {
+        Self::new(ErrorKind::Other, source.to_string())
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(source: parquet::errors::ParquetError) -> Self {
+        Self::new(ErrorKind::Other, source.to_string())
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<arrow_schema::ArrowError> for Error {
+    fn from(source: arrow_schema::ArrowError) -> Self {
+        Self::new(ErrorKind::Other, source.to_string())
     }
 }
 
 impl From<std::num::TryFromIntError> for Error {
     fn from(source: std::num::TryFromIntError) -> Self {
-        Self(source.to_string())
+        Self::new(ErrorKind::Other, source.to_string())
     }
 }
 
 impl From<std::ffi::NulError> for Error {
     fn from(source: std::ffi::NulError) -> Self {
-        Self(source.to_string())
+        Self::new(ErrorKind::Other, source.to_string())
     }
 }
 
 impl From<std::ffi::IntoStringError> for Error {
     fn from(source: std::ffi::IntoStringError) -> Self {
-        Self(source.to_string())
+        Self::new(ErrorKind::Other, source.to_string())
     }
 }
 
 impl From<num_enum::TryFromPrimitiveError<crate::NgtObject>> for Error {
     fn from(source: num_enum::TryFromPrimitiveError<crate::NgtObject>) -> Self {
-        Self(source.to_string())
+        Self::new(ErrorKind::Other, source.to_string())
     }
 }
 
 impl From<num_enum::TryFromPrimitiveError<crate::NgtDistance>> for Error {
     fn from(source: num_enum::TryFromPrimitiveError<crate::NgtDistance>) -> Self {
-        Self(source.to_string())
+        Self::new(ErrorKind::Other, source.to_string())
     }
 }
 
 #[cfg(feature = "quantized")]
 impl From<num_enum::TryFromPrimitiveError<crate::qg::QgObject>> for Error {
     fn from(source: num_enum::TryFromPrimitiveError<crate::qg::QgObject>) -> Self {
-        Self(source.to_string())
+        Self::new(ErrorKind::Other, source.to_string())
     }
 }
 
 #[cfg(feature = "quantized")]
 impl From<num_enum::TryFromPrimitiveError<crate::qg::QgDistance>> for Error {
     fn from(source: num_enum::TryFromPrimitiveError<crate::qg::QgDistance>) -> Self {
-        Self(source.to_string())
+        Self::new(ErrorKind::Other, source.to_string())
     }
 }
 
 #[cfg(feature = "quantized")]
 impl From<num_enum::TryFromPrimitiveError<crate::qbg::QbgObject>> for Error {
     fn from(source: num_enum::TryFromPrimitiveError<crate::qbg::QbgObject>) -> Self {
-        Self(source.to_string())
+        Self::new(ErrorKind::Other, source.to_string())
     }
 }
 
 #[cfg(feature = "quantized")]
 impl From<num_enum::TryFromPrimitiveError<crate::qbg::QbgDistance>> for Error {
     fn from(source: num_enum::TryFromPrimitiveError<crate::qbg::QbgDistance>) -> Self {
-        Self(source.to_string())
+        Self::new(ErrorKind::Other, source.to_string())
+    }
+}
+
+#[cfg(feature = "quantized")]
+impl From<num_enum::TryFromPrimitiveError<crate::qbg::QbgClusteringInitMode>> for Error {
+    fn from(source: num_enum::TryFromPrimitiveError<crate::qbg::QbgClusteringInitMode>) -> Self {
+        Self::new(ErrorKind::Other, source.to_string())
     }
 }
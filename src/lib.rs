@@ -48,7 +48,7 @@
 //! index.build(2)?;
 //!
 //! // Perform a vector search (with 1 result)
-//! let res = index.search(&vec![1.1, 2.1, 3.1], 1, ngt::EPSILON)?;
+//! let res = index.search(&vec![1.1, 2.1, 3.1], 1, index.default_epsilon())?;
 //! assert_eq!(res[0].id, id1);
 //! assert_eq!(index.get_vec(id1)?, vec![1.0, 2.0, 3.0]);
 //!
@@ -74,24 +74,132 @@
 #[cfg(all(feature = "quantized", feature = "shared_mem"))]
 compile_error!(r#"only one of ["quantized", "shared_mem"] can be enabled"#);
 
+pub mod dataset;
 mod error;
+#[cfg(feature = "metadata")]
+pub mod metadata;
 mod ngt;
 #[cfg(feature = "quantized")]
 pub mod qbg;
 #[cfg(feature = "quantized")]
 pub mod qg;
+mod query;
 
 pub type VecId = u32;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SearchResult {
     pub id: VecId,
     pub distance: f32,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchResultWithVec<T> {
+    pub id: VecId,
+    pub distance: f32,
+    pub vec: Vec<T>,
+}
+
+/// A search result along with an optional external key identifying the vector outside
+/// of the index, and an optional copy of the stored vector.
+///
+/// It can be built from a [`SearchResult`][] or a [`SearchResultWithVec`][], which
+/// stay the cheaper results returned by searches. With the `serde` feature, missing
+/// keys and vectors are omitted when serialized, so that services can return results
+/// directly as JSON.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DetailedSearchResult<K, T> {
+    pub id: VecId,
+    pub distance: f32,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub key: Option<K>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub vec: Option<Vec<T>>,
+}
+
+impl<K, T> DetailedSearchResult<K, T> {
+    /// Attach the external key of the vector.
+    pub fn with_key(mut self, key: K) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Attach the stored vector.
+    pub fn with_vec(mut self, vec: Vec<T>) -> Self {
+        self.vec = Some(vec);
+        self
+    }
+}
+
+impl<K, T> From<SearchResult> for DetailedSearchResult<K, T> {
+    fn from(res: SearchResult) -> Self {
+        Self {
+            id: res.id,
+            distance: res.distance,
+            key: None,
+            vec: None,
+        }
+    }
+}
+
+impl<K, T> From<SearchResultWithVec<T>> for DetailedSearchResult<K, T> {
+    fn from(res: SearchResultWithVec<T>) -> Self {
+        Self {
+            id: res.id,
+            distance: res.distance,
+            key: None,
+            vec: Some(res.vec),
+        }
+    }
+}
+
+#[deprecated(note = "use `NgtProperties::default_epsilon` and `NgtIndex::default_epsilon` instead")]
 pub const EPSILON: f32 = 0.1;
 
-pub use crate::error::{Error, Result};
-pub use crate::ngt::{optim, NgtDistance, NgtIndex, NgtObject, NgtProperties, NgtQuery};
+pub use crate::error::{Error, ErrorKind, Result};
+#[cfg(feature = "metadata")]
+pub use crate::metadata::MetadataStore;
+pub use crate::ngt::{
+    bench, distance, maintenance, migration, optim, ClosedIndex, Fusion, GraphFormat, IndexMode,
+    ModeRead, ModeWrite, NamespacedIndex, NgtDistance, NgtGraphType, NgtIndex, NgtIndexType,
+    NgtObject, NgtProperties, NgtQuery, NgtQueryResult, NgtResults, NgtSeedType, RoundRobin,
+    ShardedIndex, ShardingPolicy, TunedParams, VerifyReport,
+};
 
 pub use half;
+#[cfg(feature = "ndarray")]
+pub use ndarray;
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detailed_search_result_serde() -> serde_json::Result<()> {
+        let res = SearchResult {
+            id: 1,
+            distance: 0.5,
+        };
+        let res = DetailedSearchResult::<String, f32>::from(res);
+        assert_eq!(serde_json::to_string(&res)?, r#"{"id":1,"distance":0.5}"#);
+
+        let res = res.with_key("doc-1".to_string()).with_vec(vec![1.0]);
+        let json = serde_json::to_string(&res)?;
+        assert_eq!(json, r#"{"id":1,"distance":0.5,"key":"doc-1","vec":[1.0]}"#);
+        assert_eq!(
+            serde_json::from_str::<DetailedSearchResult<_, _>>(&json)?,
+            res
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,333 @@
+//! Payloads attached to the vectors of an index, see [`MetadataStore`][].
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::ngt::migration::IdMap;
+use crate::ngt::{IndexMode, ModeWrite, NgtIndex, NgtObjectType};
+use crate::VecId;
+
+const METADATA_FILE: &str = "metadata.json";
+
+/// Arbitrary payloads attached to the vectors of an index, persisted as JSON in the
+/// index directory, so that search results can be hydrated without a separate
+/// database.
+///
+/// ```rust
+/// # fn main() -> Result<(), ngt::Error> {
+/// use ngt::{MetadataStore, NgtIndex, NgtProperties};
+///
+/// let prop = NgtProperties::dimension(3)?;
+/// let mut index: NgtIndex<f32> = NgtIndex::create("target/path/to/meta_index/dir", prop)?;
+/// let mut metadata = MetadataStore::open(index.path())?;
+///
+/// let id = metadata.insert_vec(&mut index, vec![1.0, 2.0, 3.0], "first".to_string())?;
+///
+/// // Persist both the index and the metadata
+/// metadata.persist_with(&mut index)?;
+///
+/// assert_eq!(metadata.get(id).map(String::as_str), Some("first"));
+/// # std::fs::remove_dir_all("target/path/to/meta_index/dir").unwrap();
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Payloads are kept along with the time they were attached, so that
+/// [`sync`](MetadataStore::sync) drops them once their id is assigned to a vector
+/// inserted later on. This requires the index to
+/// [`record timestamps`](crate::NgtProperties::record_timestamps), otherwise only
+/// removals are followed. Vectors getting new ids are followed by
+/// [`remap`](MetadataStore::remap) and [`remap_to`](MetadataStore::remap_to).
+#[derive(Debug, Clone)]
+pub struct MetadataStore<V> {
+    path: PathBuf,
+    entries: BTreeMap<VecId, Entry<V>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry<V> {
+    #[serde(with = "time")]
+    attached: SystemTime,
+    value: V,
+}
+
+impl<V> Entry<V> {
+    fn new(value: V) -> Self {
+        Self {
+            attached: SystemTime::now(),
+            value,
+        }
+    }
+}
+
+/// Times are encoded like in the other sidecar files of the index directory.
+mod time {
+    use std::time::SystemTime;
+
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::ngt::{decode_time, encode_time};
+
+    pub(super) fn serialize<S: Serializer>(time: &SystemTime, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&encode_time(*time))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<SystemTime, D::Error> {
+        let time = String::deserialize(d)?;
+        decode_time(&time).ok_or_else(|| D::Error::custom(format!("Invalid time: {time:?}")))
+    }
+}
+
+impl<V> MetadataStore<V>
+where
+    V: Serialize + DeserializeOwned,
+{
+    /// Open the metadata of the index at the specified directory, it is empty if
+    /// none were persisted yet.
+    ///
+    /// [`In-memory`](NgtIndex::create_in_memory) indexes have no directory, so they
+    /// can't have metadata.
+    pub fn open<P: AsRef<Path>>(index_dir: P) -> Result<Self> {
+        if index_dir.as_ref().as_os_str().is_empty() {
+            Err("In-memory indexes can't have metadata".to_string())?
+        }
+        let path = index_dir.as_ref().join(METADATA_FILE);
+        let entries = match fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => Err(e)?,
+        };
+        Ok(Self { path, entries })
+    }
+
+    /// Get the payload of the specified vector.
+    pub fn get(&self, id: VecId) -> Option<&V> {
+        self.entries.get(&id).map(|entry| &entry.value)
+    }
+
+    /// Attach a payload to the specified vector, returning the previous one.
+    pub fn insert(&mut self, id: VecId, value: V) -> Option<V> {
+        self.entries
+            .insert(id, Entry::new(value))
+            .map(|entry| entry.value)
+    }
+
+    /// Remove the payload of the specified vector.
+    pub fn remove(&mut self, id: VecId) -> Option<V> {
+        self.entries.remove(&id).map(|entry| entry.value)
+    }
+
+    /// Insert the specified vector into the index and attach a payload to it.
+    pub fn insert_vec<T>(&mut self, index: &mut NgtIndex<T>, vec: Vec<T>, value: V) -> Result<VecId>
+    where
+        T: NgtObjectType,
+    {
+        let id = index.insert(vec)?;
+        self.entries.insert(id, Entry::new(value));
+        Ok(id)
+    }
+
+    /// Remove the specified vector from the index along with its payload.
+    pub fn remove_vec<T>(&mut self, index: &mut NgtIndex<T>, id: VecId) -> Result<Option<V>>
+    where
+        T: NgtObjectType,
+    {
+        index.remove(id)?;
+        Ok(self.remove(id))
+    }
+
+    /// Remove the payloads of the vectors that aren't stored in the index anymore,
+    /// including the [`soft removed`](NgtIndex::soft_remove) ones, and of the ids
+    /// assigned to a vector inserted after the payload was attached.
+    pub fn sync<T, M>(&mut self, index: &NgtIndex<T, M>)
+    where
+        T: NgtObjectType,
+        M: IndexMode,
+    {
+        let live = index.ids().collect::<BTreeSet<_>>();
+        self.entries.retain(|&id, entry| {
+            live.contains(&id)
+                && index
+                    .inserted_at(id)
+                    .map_or(true, |inserted| inserted <= entry.attached)
+        });
+    }
+
+    /// Move the payloads of the vectors that got new ids, following the pairs of old
+    /// and new ids returned by [`optim::cleanup`](crate::optim::cleanup). The other
+    /// payloads are kept.
+    pub fn remap(&mut self, ids: &IdMap) {
+        let moved = ids
+            .iter()
+            .filter_map(|&(old, new)| Some((new, self.entries.remove(&old)?.value)))
+            .collect::<Vec<_>>();
+        for (id, value) in moved {
+            self.entries.insert(id, Entry::new(value));
+        }
+    }
+
+    /// The payloads of the index at `index_dir` rebuilt from this one, following the
+    /// pairs of old and new ids returned by [`compact_to`](NgtIndex::compact_to) or
+    /// [`rebuild_with`](NgtIndex::rebuild_with). The payloads of the vectors that
+    /// weren't copied are dropped.
+    pub fn remap_to<P: AsRef<Path>>(mut self, index_dir: P, ids: &IdMap) -> Result<Self> {
+        let mut remapped = Self::open(index_dir)?;
+        remapped.entries = ids
+            .iter()
+            .filter_map(|&(old, new)| Some((new, Entry::new(self.entries.remove(&old)?.value))))
+            .collect();
+        Ok(remapped)
+    }
+
+    /// [`Persist`](NgtIndex::persist) the index, then [`sync`](MetadataStore::sync)
+    /// and [`persist`](MetadataStore::persist) the payloads.
+    pub fn persist_with<T>(&mut self, index: &mut NgtIndex<T, ModeWrite>) -> Result<()>
+    where
+        T: NgtObjectType,
+    {
+        index.persist()?;
+        self.sync(index);
+        self.persist()
+    }
+
+    /// Iterate over the payloads along with the ids of their vectors.
+    pub fn iter(&self) -> impl Iterator<Item = (VecId, &V)> {
+        self.entries.iter().map(|(&id, entry)| (id, &entry.value))
+    }
+
+    /// The number of payloads.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there is no payload.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Persist the payloads to the index directory.
+    ///
+    /// The file is replaced atomically, so that a crash while persisting doesn't
+    /// corrupt the previously persisted payloads.
+    pub fn persist(&self) -> Result<()> {
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(&self.entries)?)?;
+        fs::rename(tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::result::Result as StdResult;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::NgtProperties;
+
+    #[test]
+    fn test_metadata() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index along with its metadata
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let mut metadata = MetadataStore::open(dir.path())?;
+
+        // Insert three vectors with their payloads
+        let id1 = metadata.insert_vec(&mut index, vec![1.0, 2.0, 3.0], vec!["a".to_string()])?;
+        let payload2 = vec!["b".to_string(), "c".to_string()];
+        let id2 = metadata.insert_vec(&mut index, vec![4.0, 5.0, 6.0], payload2)?;
+        let id3 = index.insert(vec![7.0, 8.0, 9.0])?;
+        metadata.insert(id3, vec!["d".to_string()]);
+
+        // Remove a vector along with its payload
+        assert_eq!(
+            metadata.remove_vec(&mut index, id1)?,
+            Some(vec!["a".to_string()])
+        );
+        assert_eq!(metadata.get(id1), None);
+        assert_eq!(metadata.len(), 2);
+
+        // Soft removed vectors lose their payload once synced
+        index.soft_remove(id3)?;
+        metadata.sync(&index);
+        assert_eq!(metadata.get(id3), None);
+
+        // Verify that the payloads are persisted
+        metadata.persist_with(&mut index)?;
+        let metadata = MetadataStore::<Vec<String>>::open(dir.path())?;
+        assert_eq!(
+            metadata.iter().collect::<Vec<_>>(),
+            vec![(id2, &vec!["b".to_string(), "c".to_string()])]
+        );
+
+        // In-memory indexes have no directory to store metadata in
+        assert!(MetadataStore::<String>::open("").is_err());
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_ids() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index recording timestamps along with its metadata
+        let prop = NgtProperties::<f32>::dimension(3)?.record_timestamps(true)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let mut metadata = MetadataStore::open(dir.path())?;
+        let id1 = metadata.insert_vec(&mut index, vec![1.0, 2.0, 3.0], "a".to_string())?;
+        let id2 = metadata.insert_vec(&mut index, vec![4.0, 5.0, 6.0], "b".to_string())?;
+
+        // The payload of a recycled id is dropped once synced
+        index.remove(id1)?;
+        let id3 = index.insert(vec![7.0, 8.0, 9.0])?;
+        assert_eq!(id3, id1);
+        metadata.sync(&index);
+        assert_eq!(metadata.get(id3), None);
+        assert_eq!(metadata.get(id2).map(String::as_str), Some("b"));
+
+        // Payloads follow the vectors getting new ids
+        metadata.insert(id3, "c".to_string());
+        metadata.remap(&vec![(id2, id3), (id3, id2)]);
+        metadata.sync(&index);
+        assert_eq!(metadata.get(id2).map(String::as_str), Some("c"));
+        assert_eq!(metadata.get(id3).map(String::as_str), Some("b"));
+
+        // And the vectors copied into a compacted index
+        let dst = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dst.path())?;
+        }
+        index.soft_remove(id2)?;
+        let (_, ids) = index.compact_to(dst.path(), 1)?;
+        let metadata = metadata.remap_to(dst.path(), &ids)?;
+        assert_eq!(
+            metadata.iter().map(|(_, v)| v.as_str()).collect::<Vec<_>>(),
+            vec!["b"]
+        );
+
+        dst.close()?;
+        dir.close()?;
+        Ok(())
+    }
+}
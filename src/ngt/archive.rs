@@ -0,0 +1,114 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use super::manifest::Manifest;
+use crate::error::Result;
+
+/// Writes the files of the directory `dir` as a tar archive.
+///
+/// Files are streamed in the order of their relative path, the manifest of the index
+/// is part of them and allows [`read`] to check the archive.
+pub(crate) fn write<W: Write>(dir: &Path, writer: W) -> Result<()> {
+    let mut files = Vec::new();
+    list_files(dir, Path::new(""), &mut files)?;
+    files.sort();
+
+    let mut builder = tar::Builder::new(writer);
+    for file in files {
+        builder.append_path_with_name(dir.join(&file), &file)?;
+    }
+    builder.into_inner()?.flush()?;
+
+    Ok(())
+}
+
+/// Extracts an archive produced by [`write`] into the directory `dir`, which must not
+/// exist.
+pub(crate) fn read<R: Read>(reader: R, dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir.parent().unwrap_or(Path::new("")))?;
+    fs::create_dir(dir)?;
+
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.into_owned();
+        if !name.components().all(|c| matches!(c, Component::Normal(_)))
+            || !entry.header().entry_type().is_file()
+        {
+            Err(format!("Invalid entry {name:?} in index archive"))?
+        }
+
+        let path = dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = fs::File::create(path)?;
+        std::io::copy(&mut entry, &mut file)?;
+    }
+
+    if Manifest::load(dir)?.is_none() {
+        Err("Not an index archive, missing manifest".to_string())?
+    }
+
+    Ok(())
+}
+
+fn list_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(root.join(dir))? {
+        let entry = entry?;
+        let path = dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            list_files(root, &path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::result::Result as StdResult;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::ngt::{NgtDistance, NgtObject};
+
+    #[test]
+    fn test_archive() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory with nested files and a manifest
+        let dir = tempdir()?;
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("qg"))?;
+        fs::write(src.join("grp"), "grp")?;
+        fs::write(src.join("qg").join("obj"), "obj")?;
+        fs::write(src.join("empty"), "")?;
+        Manifest::new(NgtObject::Float, 3, NgtDistance::L2).save(&src)?;
+
+        // Archive the directory and extract it
+        let mut archive = Vec::new();
+        write(&src, &mut archive)?;
+        let dst = dir.path().join("dst");
+        read(archive.as_slice(), &dst)?;
+        assert_eq!(fs::read_to_string(dst.join("grp"))?, "grp");
+        assert_eq!(fs::read_to_string(dst.join("qg").join("obj"))?, "obj");
+        assert_eq!(fs::read_to_string(dst.join("empty"))?, "");
+        assert!(Manifest::load(&dst)?.is_some());
+
+        // Invalid archives are rejected
+        assert!(read(&b"NOTANARC"[..], &dir.path().join("invalid")).is_err());
+        assert!(read(&archive[..1024], &dir.path().join("truncated")).is_err());
+
+        // Archives without manifest are rejected
+        let mut builder = tar::Builder::new(Vec::new());
+        builder.append_path_with_name(src.join("qg").join("obj"), "obj")?;
+        let archive = builder.into_inner()?;
+        assert!(read(archive.as_slice(), &dir.path().join("no_manifest")).is_err());
+
+        dir.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use std::borrow::Cow;
+
+use ndarray::{Array1, ArrayView1, ArrayView2};
+
+use super::{IndexMode, ModeWrite, NgtIndex, NgtObjectType};
+use crate::error::Result;
+use crate::{SearchResult, VecId};
+
+impl<T> NgtIndex<T, ModeWrite>
+where
+    T: NgtObjectType,
+{
+    /// Insert the specified vector, see [`insert`](NgtIndex::insert).
+    pub fn insert_array(&mut self, vec: ArrayView1<T>) -> Result<VecId> {
+        self.insert(vec.to_vec())
+    }
+
+    /// Insert the rows of the specified matrix, see
+    /// [`insert_batch`](NgtIndex::insert_batch).
+    ///
+    /// Matrices in standard layout are inserted without being copied.
+    pub fn insert_batch_array(&mut self, batch: ArrayView2<T>) -> Result<()> {
+        if batch.ncols() != self.dimension() {
+            Err(format!(
+                "Inconsistent batch dim, expected: {} got: {}",
+                self.dimension(),
+                batch.ncols()
+            ))?
+        }
+        match batch.as_slice() {
+            Some(data) => self.insert_batch_flat(data, batch.nrows()),
+            None => self.insert_batch(batch.rows().into_iter().map(|r| r.to_vec()).collect()),
+        }
+    }
+}
+
+impl<T, M> NgtIndex<T, M>
+where
+    T: NgtObjectType,
+    M: IndexMode,
+{
+    /// Search the nearest vectors to the specified query vector, see
+    /// [`search`](NgtIndex::search).
+    ///
+    /// Contiguous views are searched without being copied.
+    pub fn search_array(
+        &self,
+        vec: ArrayView1<T>,
+        res_size: usize,
+        epsilon: f32,
+    ) -> Result<Vec<SearchResult>> {
+        self.search(&as_slice(&vec), res_size, epsilon)
+    }
+
+    /// Search the nearest vectors to each row of the specified matrix, see
+    /// [`search`](NgtIndex::search).
+    pub fn search_batch_array(
+        &self,
+        batch: ArrayView2<T>,
+        res_size: usize,
+        epsilon: f32,
+    ) -> Result<Vec<Vec<SearchResult>>> {
+        batch
+            .rows()
+            .into_iter()
+            .map(|row| self.search(&as_slice(&row), res_size, epsilon))
+            .collect()
+    }
+
+    /// Get the specified vector, see [`get_vec`](NgtIndex::get_vec).
+    pub fn get_vec_array(&self, id: VecId) -> Result<Array1<T>> {
+        Ok(Array1::from_vec(self.get_vec(id)?))
+    }
+}
+
+fn as_slice<'a, T: Clone>(vec: &'a ArrayView1<T>) -> Cow<'a, [T]> {
+    match vec.as_slice() {
+        Some(slice) => Cow::Borrowed(slice),
+        None => Cow::Owned(vec.to_vec()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::result::Result as StdResult;
+
+    use ndarray::array;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::NgtProperties;
+
+    #[test]
+    fn test_ngt_array() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Insert vectors from arrays
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let id1 = index.insert_array(array![1.0, 2.0, 3.0].view())?;
+        index.insert_batch_array(array![[4.0, 5.0, 6.0], [7.0, 8.0, 9.0]].view())?;
+        index.build(1)?;
+        assert_eq!(index.get_vec_array(id1)?, array![1.0, 2.0, 3.0]);
+
+        // Search contiguous and strided views
+        let res = index.search_array(array![1.1, 2.1, 3.1].view(), 1, 0.1)?;
+        assert_eq!(res[0].id, id1);
+        let columns = array![[1.1, 7.1], [2.1, 8.1], [3.1, 9.1]];
+        let res = index.search_array(columns.column(0), 1, 0.1)?;
+        assert_eq!(res[0].id, id1);
+        let queries = columns.t();
+        let res = index.search_batch_array(queries.view(), 1, 0.1)?;
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0][0].id, id1);
+        assert_eq!(index.get_vec(res[1][0].id)?, vec![7.0, 8.0, 9.0]);
+
+        dir.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+//! Functions aimed at benchmarking the searches of an [`NgtIndex`](NgtIndex)
+
+use std::panic;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ngt_sys as sys;
+use scopeguard::defer;
+
+use super::{IndexMode, NgtIndex, NgtObjectType, NgtQuery};
+use crate::error::Result;
+
+/// The parameters of the searches run by [`run`][].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchParams {
+    /// The number of results of each search.
+    pub size: usize,
+    /// The epsilon of the searches, the index default epsilon when `None`.
+    pub epsilon: Option<f32>,
+    /// The edge size of the searches, the index search edge size when `None`.
+    pub edge_size: Option<usize>,
+}
+
+impl Default for BenchParams {
+    fn default() -> Self {
+        Self {
+            size: 10,
+            epsilon: None,
+            edge_size: None,
+        }
+    }
+}
+
+impl BenchParams {
+    fn query<'a, T: NgtObjectType>(&self, vec: &'a [T], default_epsilon: f32) -> NgtQuery<'a, T> {
+        let mut query = NgtQuery::new(vec)
+            .size(self.size)
+            .epsilon(self.epsilon.unwrap_or(default_epsilon));
+        if let Some(edge_size) = self.edge_size {
+            query = query.edge_size(edge_size);
+        }
+        query
+    }
+}
+
+/// The throughput and latencies measured by [`run`][].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchReport {
+    /// The number of queries per second.
+    pub qps: f64,
+    /// The wall-clock duration of the whole benchmark.
+    pub elapsed: Duration,
+    /// The mean latency of the queries.
+    pub mean: Duration,
+    latencies: Vec<Duration>,
+}
+
+impl BenchReport {
+    /// The latency under which are `p` percents of the queries, for `p` between `0`
+    /// and `100`.
+    pub fn percentile(&self, p: f64) -> Duration {
+        if self.latencies.is_empty() {
+            return Duration::ZERO;
+        }
+        let rank = (p.clamp(0., 100.) / 100. * self.latencies.len() as f64).ceil() as usize;
+        self.latencies[rank.clamp(1, self.latencies.len()) - 1]
+    }
+
+    /// The median latency.
+    pub fn p50(&self) -> Duration {
+        self.percentile(50.)
+    }
+
+    /// The 90th percentile latency.
+    pub fn p90(&self) -> Duration {
+        self.percentile(90.)
+    }
+
+    /// The 99th percentile latency.
+    pub fn p99(&self) -> Duration {
+        self.percentile(99.)
+    }
+
+    /// The maximum latency.
+    pub fn max(&self) -> Duration {
+        self.percentile(100.)
+    }
+
+    /// The number of queries.
+    pub fn nb_queries(&self) -> usize {
+        self.latencies.len()
+    }
+}
+
+/// Search each of the queries with the specified parameters, from `concurrency`
+/// threads, and report the throughput and latencies of the searches.
+///
+/// The searches go through the same path as
+/// [`search_query`](NgtIndex::search_query), so the measures include the overhead of
+/// the Rust wrappers.
+///
+/// **The index must have been [`built`](NgtIndex::build) beforehand**.
+pub fn run<T, M>(
+    index: &NgtIndex<T, M>,
+    queries: &[&[T]],
+    params: BenchParams,
+    concurrency: usize,
+) -> Result<BenchReport>
+where
+    T: NgtObjectType + Sync,
+    M: IndexMode,
+{
+    if queries.is_empty() {
+        Err("Benchmarking requires queries".to_string())?
+    }
+    let chunk_size = queries.len().div_ceil(concurrency.max(1));
+
+    let start = Instant::now();
+    let latencies = thread::scope(|s| {
+        let handles = queries
+            .chunks(chunk_size)
+            .map(|chunk| {
+                s.spawn(move || unsafe {
+                    // Each thread reports its errors in its own error object
+                    let ebuf = sys::ngt_create_error_object();
+                    defer! { sys::ngt_destroy_error_object(ebuf); }
+
+                    chunk
+                        .iter()
+                        .map(|vec| {
+                            let query = params.query(vec, index.default_epsilon());
+                            let start = Instant::now();
+                            index.search_query_in(&query, ebuf, |_| Ok(()))?;
+                            Ok(start.elapsed())
+                        })
+                        .collect::<Result<Vec<_>>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect::<Result<Vec<_>>>()
+    })?;
+    let elapsed = start.elapsed();
+
+    let mut latencies = latencies.into_iter().flatten().collect::<Vec<_>>();
+    latencies.sort();
+    Ok(BenchReport {
+        qps: latencies.len() as f64 / elapsed.as_secs_f64(),
+        elapsed,
+        mean: latencies.iter().sum::<Duration>() / latencies.len() as u32,
+        latencies,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::result::Result as StdResult;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::NgtProperties;
+
+    #[test]
+    fn test_bench() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index with a few vectors
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        index.insert_batch((0..100).map(|i| vec![i as f32; 3]).collect())?;
+        index.build(2)?;
+
+        // Benchmark the searches from 2 threads
+        let queries = (0..10).map(|i| vec![i as f32 + 0.5; 3]).collect::<Vec<_>>();
+        let queries = queries.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        let report = run(&index, &queries, BenchParams::default(), 2)?;
+        assert_eq!(report.nb_queries(), 10);
+        assert!(report.qps > 0.);
+        assert!(report.p50() <= report.p90());
+        assert!(report.p99() <= report.max());
+        assert!(run(&index, &[], BenchParams::default(), 2).is_err());
+
+        dir.close()?;
+        Ok(())
+    }
+}
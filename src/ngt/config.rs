@@ -0,0 +1,75 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+const CONFIG_FILE: &str = "config";
+
+/// Settings of an index that NGT doesn't know about, persisted in a sidecar file of
+/// the index directory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Config {
+    pub(crate) linear_search_threshold: usize,
+    pub(crate) epsilon: f32,
+    pub(crate) reuse_removed_ids: bool,
+    pub(crate) record_timestamps: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            linear_search_threshold: 0,
+            epsilon: 0.1,
+            reuse_removed_ids: true,
+            record_timestamps: false,
+        }
+    }
+}
+
+impl Config {
+    /// Load the config from the index directory, indexes created without config get
+    /// the default one.
+    pub(crate) fn load(dir: &Path) -> Result<Self> {
+        let content = match fs::read_to_string(dir.join(CONFIG_FILE)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => Err(e)?,
+        };
+
+        let mut config = Self::default();
+        for line in content.lines() {
+            let parse_err = || Error::from(format!("Invalid config line: {line:?}"));
+            let (key, value) = line.split_once(' ').ok_or_else(parse_err)?;
+            match key {
+                "linear_search_threshold" => {
+                    config.linear_search_threshold = value.parse().map_err(|_| parse_err())?
+                }
+                "epsilon" => config.epsilon = value.parse().map_err(|_| parse_err())?,
+                "reuse_removed_ids" => {
+                    config.reuse_removed_ids = value.parse().map_err(|_| parse_err())?
+                }
+                "record_timestamps" => {
+                    config.record_timestamps = value.parse().map_err(|_| parse_err())?
+                }
+                _ => Err(parse_err())?,
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Save the config in the index directory.
+    pub(crate) fn save(&self, dir: &Path) -> Result<()> {
+        let content = format!(
+            "linear_search_threshold {}\nepsilon {}\nreuse_removed_ids {}\n\
+             record_timestamps {}\n",
+            self.linear_search_threshold,
+            self.epsilon,
+            self.reuse_removed_ids,
+            self.record_timestamps
+        );
+        fs::write(dir.join(CONFIG_FILE), content)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+//! Distance functions computed the same way NGT does, to re-rank or check search
+//! results outside of an index.
+//!
+//! Vectors of normalized distances (such as [`NgtDistance::NormalizedL2`][]) are
+//! normalized by NGT when inserted, so they must be normalized beforehand to get the
+//! same distances as the index.
+
+use super::{NgtDistance, NgtObject, NgtObjectType};
+use crate::error::Result;
+
+/// Compute the distance between two vectors the same way NGT does for the
+/// specified distance function.
+///
+/// Fails if the vectors have different dimensions, or if the distance function isn't
+/// supported for the vectors object type.
+///
+/// The [`InnerProduct`](NgtDistance::InnerProduct) distance is the negated inner
+/// product of the vectors, so that the most similar vectors are the nearest ones.
+pub fn compute<T: NgtObjectType>(distance: NgtDistance, a: &[T], b: &[T]) -> Result<f32> {
+    if a.len() != b.len() {
+        Err(format!("Dimension mismatch: {} != {}", a.len(), b.len()))?
+    }
+
+    let a = a.iter().map(|x| x.to_f64()).collect::<Vec<_>>();
+    let b = b.iter().map(|x| x.to_f64()).collect::<Vec<_>>();
+
+    let d = match distance {
+        NgtDistance::L1 => a.iter().zip(&b).map(|(x, y)| (x - y).abs()).sum(),
+        NgtDistance::L2 | NgtDistance::NormalizedL2 => euclidean(&a, &b),
+        NgtDistance::Angle | NgtDistance::NormalizedAngle => {
+            similarity(&a, &b).clamp(-1., 1.).acos()
+        }
+        NgtDistance::Cosine | NgtDistance::NormalizedCosine => 1. - similarity(&a, &b),
+        NgtDistance::Hamming if T::as_obj() == NgtObject::Uint8 => {
+            bits(&a, &b).map(|(x, y)| (x ^ y).count_ones()).sum::<u32>() as f64
+        }
+        NgtDistance::Jaccard if T::as_obj() == NgtObject::Uint8 => {
+            let (inter, union) = bits(&a, &b).fold((0, 0), |(inter, union), (x, y)| {
+                (inter + (x & y).count_ones(), union + (x | y).count_ones())
+            });
+            if union == 0 {
+                0.
+            } else {
+                1. - inter as f64 / union as f64
+            }
+        }
+        NgtDistance::Poincare => {
+            let norm_a = dot(&a, &a);
+            let norm_b = dot(&b, &b);
+            let diff = euclidean(&a, &b).powi(2);
+            (1. + 2. * diff / ((1. - norm_a) * (1. - norm_b))).acosh()
+        }
+        NgtDistance::InnerProduct => -dot(&a, &b),
+        NgtDistance::Lorentz if a.is_empty() => {
+            Err("The Lorentz distance requires non-empty vectors".to_string())?
+        }
+        NgtDistance::Lorentz => {
+            let prod = a[0] * b[0] - dot(&a[1..], &b[1..]);
+            prod.max(1.).acosh()
+        }
+        _ => Err(format!(
+            "Distance {distance:?} isn't supported for {:?}",
+            T::as_obj()
+        ))?,
+    };
+
+    Ok(d as f32)
+}
+
+/// The L1 (Manhattan) distance between two vectors.
+pub fn l1<T: NgtObjectType>(a: &[T], b: &[T]) -> Result<f32> {
+    compute(NgtDistance::L1, a, b)
+}
+
+/// The L2 (Euclidean) distance between two vectors.
+pub fn l2<T: NgtObjectType>(a: &[T], b: &[T]) -> Result<f32> {
+    compute(NgtDistance::L2, a, b)
+}
+
+/// The cosine distance between two vectors, that is one minus their cosine similarity.
+pub fn cosine<T: NgtObjectType>(a: &[T], b: &[T]) -> Result<f32> {
+    compute(NgtDistance::Cosine, a, b)
+}
+
+/// The angle in radians between two vectors.
+pub fn angle<T: NgtObjectType>(a: &[T], b: &[T]) -> Result<f32> {
+    compute(NgtDistance::Angle, a, b)
+}
+
+/// The Hamming distance between two `u8` vectors, counted in bits.
+pub fn hamming<T: NgtObjectType>(a: &[T], b: &[T]) -> Result<f32> {
+    compute(NgtDistance::Hamming, a, b)
+}
+
+/// The Jaccard distance between two `u8` vectors seen as bit sets.
+pub fn jaccard<T: NgtObjectType>(a: &[T], b: &[T]) -> Result<f32> {
+    compute(NgtDistance::Jaccard, a, b)
+}
+
+/// The negated inner product of two vectors.
+pub fn inner_product<T: NgtObjectType>(a: &[T], b: &[T]) -> Result<f32> {
+    compute(NgtDistance::InnerProduct, a, b)
+}
+
+/// The Poincaré distance between two vectors of the Poincaré ball.
+pub fn poincare<T: NgtObjectType>(a: &[T], b: &[T]) -> Result<f32> {
+    compute(NgtDistance::Poincare, a, b)
+}
+
+/// The Lorentz distance between two vectors of the hyperboloid model.
+pub fn lorentz<T: NgtObjectType>(a: &[T], b: &[T]) -> Result<f32> {
+    compute(NgtDistance::Lorentz, a, b)
+}
+
+/// Normalize a vector to a unit L2 norm, as NGT does with the vectors of normalized
+/// distances. Zero vectors are left unchanged.
+///
+/// Only float vectors can be meaningfully normalized, the components of `u8` vectors
+/// would be rounded.
+pub fn normalize<T: NgtObjectType>(vec: &[T]) -> Vec<T> {
+    let vec = vec.iter().map(|x| x.to_f64()).collect::<Vec<_>>();
+    let norm = dot(&vec, &vec).sqrt();
+    let norm = if norm == 0. { 1. } else { norm };
+    vec.into_iter().map(|x| T::from_f64(x / norm)).collect()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn euclidean(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn similarity(a: &[f64], b: &[f64]) -> f64 {
+    dot(a, b) / (dot(a, a).sqrt() * dot(b, b).sqrt())
+}
+
+fn bits<'a>(a: &'a [f64], b: &'a [f64]) -> impl Iterator<Item = (u8, u8)> + 'a {
+    a.iter().zip(b).map(|(&x, &y)| (x as u8, y as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute() -> Result<()> {
+        let (a, b) = ([1.0f32, 0.0], [0.0f32, 2.0]);
+        assert_eq!(compute(NgtDistance::L1, &a, &b)?, 3.0);
+        assert_eq!(compute(NgtDistance::Cosine, &a, &b)?, 1.0);
+        assert_eq!(
+            compute(NgtDistance::Angle, &a, &b)?,
+            std::f32::consts::FRAC_PI_2
+        );
+
+        let (a, b) = ([0b1100u8, 0b0001], [0b1010u8, 0b0001]);
+        assert_eq!(compute(NgtDistance::Hamming, &a, &b)?, 2.0);
+        assert_eq!(compute(NgtDistance::Jaccard, &a, &b)?, 0.5);
+
+        assert!(compute(NgtDistance::L2, &[1.0f32], &[1.0, 2.0]).is_err());
+        assert!(compute(NgtDistance::SparseJaccard, &a, &b).is_err());
+
+        assert_eq!(l2(&[0.0f32, 3.0], &[4.0, 0.0])?, 5.0);
+        assert!(hamming(&[1.0f32, 2.0], &[1.0, 3.0]).is_err());
+        assert!(jaccard(&[1.0f32], &[1.0]).is_err());
+        assert_eq!(inner_product(&[1.0f32, 2.0], &[3.0, 4.0])?, -11.0);
+        assert!(lorentz::<f32>(&[], &[]).is_err());
+
+        assert_eq!(normalize(&[3.0f32, 4.0]), vec![0.6, 0.8]);
+        assert_eq!(normalize(&[0.0f32, 0.0]), vec![0.0, 0.0]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::SystemTime;
+
+use super::timestamps::{decode_time, encode_time};
+use crate::error::{Error, Result};
+use crate::VecId;
+
+const EXPIRATIONS_FILE: &str = "expirations";
+
+/// Deadlines of the vectors of an index inserted with a time to live, persisted in a
+/// sidecar file of the index directory.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Expirations {
+    deadlines: BTreeMap<VecId, SystemTime>,
+}
+
+impl Expirations {
+    /// Load the expirations from the index directory, indexes created without
+    /// expirations get empty ones.
+    pub(crate) fn load(dir: &Path) -> Result<Self> {
+        let content = match fs::read_to_string(dir.join(EXPIRATIONS_FILE)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => Err(e)?,
+        };
+
+        let deadlines = content
+            .lines()
+            .map(|line| {
+                let parse_err = || Error::from(format!("Invalid expirations line: {line:?}"));
+                let (id, deadline) = line.split_once(' ').ok_or_else(parse_err)?;
+                let id = id.parse().map_err(|_| parse_err())?;
+                let deadline = decode_time(deadline).ok_or_else(parse_err)?;
+                Ok((id, deadline))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { deadlines })
+    }
+
+    /// Save the expirations in the index directory.
+    pub(crate) fn save(&self, dir: &Path) -> Result<()> {
+        let content = self
+            .deadlines
+            .iter()
+            .map(|(id, &deadline)| format!("{id} {}\n", encode_time(deadline)))
+            .collect::<String>();
+        fs::write(dir.join(EXPIRATIONS_FILE), content)?;
+        Ok(())
+    }
+
+    pub(crate) fn insert(&mut self, id: VecId, deadline: SystemTime) {
+        self.deadlines.insert(id, deadline);
+    }
+
+    pub(crate) fn remove(&mut self, id: VecId) {
+        self.deadlines.remove(&id);
+    }
+
+    pub(crate) fn get(&self, id: VecId) -> Option<SystemTime> {
+        self.deadlines.get(&id).copied()
+    }
+
+    /// The ids whose deadline is not later than `now`.
+    pub(crate) fn expired(&self, now: SystemTime) -> Vec<VecId> {
+        self.deadlines
+            .iter()
+            .filter(|(_, &deadline)| deadline <= now)
+            .map(|(&id, _)| id)
+            .collect()
+    }
+}
@@ -0,0 +1,114 @@
+use std::io::Write;
+
+use crate::error::Result;
+use crate::VecId;
+
+/// The format of the adjacency data written by
+/// [`NgtIndex::export_graph`](crate::NgtIndex::export_graph).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// One `source,target,distance` line per edge, after a header line.
+    Csv,
+    /// A directed [GraphML](http://graphml.graphdrawing.org) graph, with the
+    /// distance as a `distance` edge attribute.
+    GraphMl,
+    /// For each node, its id and number of edges as little-endian `u32`, followed by
+    /// the edges as a little-endian `u32` target and `f32` distance.
+    Binary,
+}
+
+/// The outgoing edges of a node, as the ids of its neighbors along with their
+/// distance.
+pub(crate) type Adjacency = (VecId, Vec<(VecId, f32)>);
+
+/// Writes the adjacency lists of the graph in the specified format.
+pub(crate) fn write<W, I>(mut writer: W, format: GraphFormat, nodes: I) -> Result<()>
+where
+    W: Write,
+    I: Iterator<Item = Result<Adjacency>>,
+{
+    match format {
+        GraphFormat::Csv => {
+            writeln!(writer, "source,target,distance")?;
+            for node in nodes {
+                let (id, edges) = node?;
+                for (target, distance) in edges {
+                    writeln!(writer, "{id},{target},{distance}")?;
+                }
+            }
+        }
+        GraphFormat::GraphMl => {
+            writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+            writeln!(
+                writer,
+                r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+            )?;
+            writeln!(
+                writer,
+                r#"  <key id="distance" for="edge" attr.name="distance" attr.type="float"/>"#
+            )?;
+            writeln!(writer, r#"  <graph edgedefault="directed">"#)?;
+            let mut edges = Vec::new();
+            for node in nodes {
+                let (id, node_edges) = node?;
+                writeln!(writer, r#"    <node id="{id}"/>"#)?;
+                edges.extend(node_edges.into_iter().map(|(t, d)| (id, t, d)));
+            }
+            for (source, target, distance) in edges {
+                writeln!(
+                    writer,
+                    r#"    <edge source="{source}" target="{target}"><data key="distance">{distance}</data></edge>"#
+                )?;
+            }
+            writeln!(writer, "  </graph>")?;
+            writeln!(writer, "</graphml>")?;
+        }
+        GraphFormat::Binary => {
+            for node in nodes {
+                let (id, edges) = node?;
+                writer.write_all(&id.to_le_bytes())?;
+                writer.write_all(&u32::try_from(edges.len())?.to_le_bytes())?;
+                for (target, distance) in edges {
+                    writer.write_all(&target.to_le_bytes())?;
+                    writer.write_all(&distance.to_le_bytes())?;
+                }
+            }
+        }
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::result::Result as StdResult;
+
+    use super::*;
+
+    #[test]
+    fn test_write_graph() -> StdResult<(), Box<dyn StdError>> {
+        let nodes = || vec![Ok((1, vec![(2, 0.5)])), Ok((2, vec![(1, 0.5)]))].into_iter();
+
+        let mut csv = Vec::new();
+        write(&mut csv, GraphFormat::Csv, nodes())?;
+        assert_eq!(
+            String::from_utf8(csv)?,
+            "source,target,distance\n1,2,0.5\n2,1,0.5\n"
+        );
+
+        let mut graphml = Vec::new();
+        write(&mut graphml, GraphFormat::GraphMl, nodes())?;
+        let graphml = String::from_utf8(graphml)?;
+        assert!(graphml.contains(r#"<node id="2"/>"#));
+        assert!(graphml.contains(r#"<edge source="1" target="2">"#));
+
+        let mut binary = Vec::new();
+        write(&mut binary, GraphFormat::Binary, nodes())?;
+        assert_eq!(binary.len(), 2 * (4 + 4 + 4 + 4));
+        assert_eq!(&binary[..8], &[1, 0, 0, 0, 1, 0, 0, 0]);
+
+        Ok(())
+    }
+}
@@ -1,38 +1,71 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
-use std::ffi::CString;
+use std::ffi::{CString, OsStr};
+use std::fmt;
 use std::fs;
+use std::io::{Read, Write};
+use std::marker::PhantomData;
 use std::mem;
 use std::os::unix::ffi::OsStrExt;
-use std::path::Path;
+use std::panic;
+use std::path::{Path, PathBuf};
 use std::ptr;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
 use ngt_sys as sys;
-use scopeguard::defer;
-
-use super::{NgtObject, NgtObjectType, NgtProperties};
-use crate::error::{make_err, Error, Result};
-use crate::{SearchResult, VecId};
+use scopeguard::{defer, guard, ScopeGuard};
+
+use super::archive;
+use super::config::Config;
+use super::distance;
+use super::expirations::Expirations;
+use super::graph::{self, GraphFormat};
+#[cfg(not(feature = "shared_mem"))]
+use super::maintenance::swap_in;
+use super::maintenance::{copy_dir, parent_dir, staging_dir};
+use super::manifest::Manifest;
+use super::migration::{self, IdMap};
+use super::properties::{AccuracyTable, Tuning};
+use super::timestamps::Timestamps;
+use super::tombstones::Tombstones;
+use super::{NgtDistance, NgtGraphType, NgtIndexType, NgtObject, NgtObjectType, NgtProperties};
+use crate::error::{make_err, Error, ErrorKind, Result};
+use crate::query::QueryVec;
+use crate::{SearchResult, SearchResultWithVec, VecId};
 
 #[derive(Debug)]
-pub struct NgtIndex<T> {
+pub struct NgtIndex<T, M = ModeWrite> {
     pub(crate) path: CString,
     pub(crate) prop: NgtProperties<T>,
     pub(crate) index: sys::NGTIndex,
+    _mode: M,
     ospace: sys::NGTObjectSpace,
     ebuf: sys::NGTError,
+    timestamps: Timestamps,
+    tombstones: Tombstones,
+    expirations: Expirations,
+    accuracy_table: Option<AccuracyTable>,
+    /// Results object reused by [`search_into`](NgtIndex::search_into), lazily created.
+    results: Mutex<sys::NGTObjectDistances>,
 }
 
-unsafe impl<T> Send for NgtIndex<T> {}
-unsafe impl<T> Sync for NgtIndex<T> {}
+unsafe impl<T, M> Send for NgtIndex<T, M> {}
+unsafe impl<T, M> Sync for NgtIndex<T, M> {}
 
-impl<T> NgtIndex<T>
+impl<T> NgtIndex<T, ModeWrite>
 where
     T: NgtObjectType,
 {
     /// Creates an empty ANNG index with the given [`NgtProperties`][].
+    ///
+    /// The index is made of a graph and a VP-tree, unless a graph-only
+    /// [`index_type`](NgtProperties::index_type) is specified.
     pub fn create<P: AsRef<Path>>(path: P, prop: NgtProperties<T>) -> Result<Self> {
+        prop.validate()?;
         if cfg!(feature = "shared_mem") && path.as_ref().exists() {
-            Err(Error(format!("Path {:?} already exists", path.as_ref())))?
+            Err(format!("Path {:?} already exists", path.as_ref()))?
         }
 
         if let Some(path) = path.as_ref().parent() {
@@ -45,207 +78,113 @@ where
 
             let path = CString::new(path.as_ref().as_os_str().as_bytes())?;
 
-            let index = sys::ngt_create_graph_and_tree(path.as_ptr(), prop.raw_prop, ebuf);
+            let index = match prop.index_type {
+                NgtIndexType::GraphAndTree => {
+                    sys::ngt_create_graph_and_tree(path.as_ptr(), prop.raw_prop, ebuf)
+                }
+                NgtIndexType::Graph => sys::ngt_create_graph(path.as_ptr(), prop.raw_prop, ebuf),
+            };
             if index.is_null() {
                 Err(make_err(ebuf))?
             }
             sys::ngt_close_index(index);
 
+            let dir = Path::new(OsStr::from_bytes(path.as_bytes()));
+            prop.tuning.write(dir)?;
+            prop.config.save(dir)?;
+            Manifest::new(
+                prop.object_type,
+                prop.dimension as usize,
+                prop.distance_type,
+            )
+            .save(dir)?;
+
             let index = sys::ngt_open_index(path.as_ptr(), ebuf);
             if index.is_null() {
                 Err(make_err(ebuf))?
             }
+            let index = guard(index, |index| sys::ngt_close_index(index));
 
-            let ospace = sys::ngt_get_object_space(index, ebuf);
+            let ospace = sys::ngt_get_object_space(*index, ebuf);
             if ospace.is_null() {
                 Err(make_err(ebuf))?
             }
 
             Ok(NgtIndex {
+                timestamps: Timestamps::new(prop.config.record_timestamps),
                 path,
                 prop,
-                index,
+                index: ScopeGuard::into_inner(index),
+                _mode: ModeWrite,
                 ospace,
                 ebuf: sys::ngt_create_error_object(),
+                tombstones: Tombstones::default(),
+                expirations: Expirations::default(),
+                accuracy_table: None,
+                results: Mutex::new(ptr::null_mut()),
             })
         }
     }
 
-    /// Open the already existing index at the specified path.
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
-        if !path.as_ref().exists() {
-            Err(Error(format!("Path {:?} does not exist", path.as_ref())))?
+    /// Creates an empty ANNG index with the given [`NgtProperties`][], held in memory
+    /// only.
+    ///
+    /// Such an index has no directory, it can't be [`persisted`](NgtIndex::persist)
+    /// and only supports the default [`index_type`](NgtProperties::index_type).
+    #[cfg(not(feature = "shared_mem"))]
+    pub fn create_in_memory(prop: NgtProperties<T>) -> Result<Self> {
+        prop.validate()?;
+        if prop.index_type != NgtIndexType::GraphAndTree || prop.tuning != Tuning::default() {
+            Err("In-memory indexes only support the default index type and tuning".to_string())?
         }
 
         unsafe {
             let ebuf = sys::ngt_create_error_object();
             defer! { sys::ngt_destroy_error_object(ebuf); }
 
-            let path = CString::new(path.as_ref().as_os_str().as_bytes())?;
-
-            let index = sys::ngt_open_index(path.as_ptr(), ebuf);
+            let index = sys::ngt_create_graph_and_tree_in_memory(prop.raw_prop, ebuf);
             if index.is_null() {
                 Err(make_err(ebuf))?
             }
 
             let ospace = sys::ngt_get_object_space(index, ebuf);
             if ospace.is_null() {
+                sys::ngt_close_index(index);
                 Err(make_err(ebuf))?
             }
 
-            let prop = NgtProperties::from(index)?;
-
             Ok(NgtIndex {
-                path,
+                timestamps: Timestamps::new(prop.config.record_timestamps),
+                path: CString::default(),
                 prop,
                 index,
+                _mode: ModeWrite,
                 ospace,
                 ebuf: sys::ngt_create_error_object(),
+                tombstones: Tombstones::default(),
+                expirations: Expirations::default(),
+                accuracy_table: None,
+                results: Mutex::new(ptr::null_mut()),
             })
         }
     }
 
-    /// Search the nearest vectors to the specified query vector.
-    ///
-    /// **The index must have been [`built`](NgtIndex::build) beforehand**.
-    pub fn search(&self, vec: &[T], res_size: usize, epsilon: f32) -> Result<Vec<SearchResult>> {
-        unsafe {
-            let results = sys::ngt_create_empty_results(self.ebuf);
-            if results.is_null() {
-                Err(make_err(self.ebuf))?
-            }
-            defer! { sys::ngt_destroy_results(results); }
-
-            match T::as_obj() {
-                NgtObject::Float => {
-                    if !sys::ngt_search_index_as_float(
-                        self.index,
-                        vec.as_ptr() as *mut f32,
-                        self.prop.dimension,
-                        res_size,
-                        epsilon,
-                        -1.0,
-                        results,
-                        self.ebuf,
-                    ) {
-                        Err(make_err(self.ebuf))?
-                    }
-                }
-                NgtObject::Uint8 => {
-                    if !sys::ngt_search_index_as_uint8(
-                        self.index,
-                        vec.as_ptr() as *mut u8,
-                        self.prop.dimension,
-                        res_size,
-                        epsilon,
-                        -1.0,
-                        results,
-                        self.ebuf,
-                    ) {
-                        Err(make_err(self.ebuf))?
-                    }
-                }
-                NgtObject::Float16 => {
-                    if !sys::ngt_search_index_as_float16(
-                        self.index,
-                        vec.as_ptr() as *mut _,
-                        self.prop.dimension,
-                        res_size,
-                        epsilon,
-                        -1.0,
-                        results,
-                        self.ebuf,
-                    ) {
-                        Err(make_err(self.ebuf))?
-                    }
-                }
-            }
-
-            let rsize = sys::ngt_get_result_size(results, self.ebuf);
-            let mut ret = Vec::with_capacity(rsize as usize);
-
-            for i in 0..rsize {
-                let d = sys::ngt_get_result(results, i, self.ebuf);
-                if d.id == 0 && d.distance == 0.0 {
-                    Err(make_err(self.ebuf))?
-                } else {
-                    ret.push(SearchResult {
-                        id: d.id,
-                        distance: d.distance,
-                    });
-                }
-            }
-
-            Ok(ret)
-        }
-    }
-
-    /// Search the nearest vectors to the specified [`NgtQuery`][].
-    ///
-    /// **The index must have been [`built`](NgtIndex::build) beforehand**.
-    pub fn search_query(&self, query: NgtQuery<T>) -> Result<Vec<SearchResult>> {
-        unsafe {
-            let results = sys::ngt_create_empty_results(self.ebuf);
-            if results.is_null() {
-                Err(make_err(self.ebuf))?
-            }
-            defer! { sys::ngt_destroy_results(results); }
-
-            match T::as_obj() {
-                NgtObject::Float => {
-                    let q = sys::NGTQueryFloat {
-                        query: query.query.as_ptr() as *mut f32,
-                        params: query.params(),
-                    };
-                    if !sys::ngt_search_index_with_query_float(self.index, q, results, self.ebuf) {
-                        Err(make_err(self.ebuf))?
-                    }
-                }
-                NgtObject::Uint8 => {
-                    let q = sys::NGTQueryUint8 {
-                        query: query.query.as_ptr() as *mut u8,
-                        params: query.params(),
-                    };
-                    if !sys::ngt_search_index_with_query_uint8(self.index, q, results, self.ebuf) {
-                        Err(make_err(self.ebuf))?
-                    }
-                }
-                NgtObject::Float16 => {
-                    let q = sys::NGTQueryFloat16 {
-                        query: query.query.as_ptr() as *mut _,
-                        params: query.params(),
-                    };
-                    if !sys::ngt_search_index_with_query_float16(self.index, q, results, self.ebuf)
-                    {
-                        Err(make_err(self.ebuf))?
-                    }
-                }
-            }
-
-            let rsize = sys::ngt_get_result_size(results, self.ebuf);
-            let mut ret = Vec::with_capacity(rsize as usize);
-
-            for i in 0..rsize {
-                let d = sys::ngt_get_result(results, i, self.ebuf);
-                if d.id == 0 && d.distance == 0.0 {
-                    Err(make_err(self.ebuf))?
-                } else {
-                    ret.push(SearchResult {
-                        id: d.id,
-                        distance: d.distance,
-                    });
-                }
-            }
-
-            Ok(ret)
-        }
+    /// Open the already existing index at the specified path.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with(path, ModeWrite)
     }
 
     /// Insert the specified vector into the index. However note that it is not
     /// discoverable yet.
     ///
+    /// The id of the vector is assigned by NGT, whose C API doesn't allow choosing it.
+    /// Ids of removed vectors may be assigned again, otherwise ids follow the
+    /// insertion order.
+    ///
     /// **The method [`build`](NgtIndex::build) must be called after inserting vectors**.
     pub fn insert(&mut self, mut vec: Vec<T>) -> Result<VecId> {
+        self.check_capacity(1)?;
+
         unsafe {
             let id = match self.prop.object_type {
                 NgtObject::Float => sys::ngt_insert_index_as_float(
@@ -270,14 +209,36 @@ where
             if id == 0 {
                 Err(make_err(self.ebuf))?
             } else {
+                self.timestamps.insert(id);
                 Ok(id)
             }
         }
     }
 
+    /// Insert the specified vector into the index like [`insert`](NgtIndex::insert),
+    /// it then expires once `ttl` has elapsed.
+    ///
+    /// Expired vectors are only removed by [`expire_now`](NgtIndex::expire_now), which
+    /// is up to the caller to run periodically.
+    pub fn insert_with_ttl(&mut self, vec: Vec<T>, ttl: Duration) -> Result<VecId> {
+        let Some(deadline) = SystemTime::now().checked_add(ttl) else {
+            Err(format!("The time to live {ttl:?} is out of range"))?
+        };
+        let id = self.insert(vec)?;
+        self.expirations.insert(id, deadline);
+        Ok(id)
+    }
+
     /// Insert the multiple vectors into the index. However note that they are not
     /// discoverable yet.
     ///
+    /// NGT's object repository isn't thread-safe, so appending vectors can't be done
+    /// concurrently, even for distinct vectors: appends grow a shared repository and
+    /// may reallocate it. For high ingest throughput, insert all the vectors first,
+    /// preferably through [`insert_batch_flat`](NgtIndex::insert_batch_flat) which
+    /// avoids copying them, and then let [`build`](NgtIndex::build) index them with
+    /// multiple threads.
+    ///
     /// **The method [`build`](NgtIndex::build) must be called after inserting vectors**.
     pub fn insert_batch(&mut self, batch: Vec<Vec<T>>) -> Result<()> {
         let batch_size = u32::try_from(batch.len())?;
@@ -285,7 +246,7 @@ where
         if batch_size > 0 {
             let dim = batch[0].len();
             if dim != self.prop.dimension as usize {
-                Err(Error(format!(
+                Err(Error::from(format!(
                     "Inconsistent batch dim, expected: {} got: {}",
                     self.prop.dimension, dim
                 )))?;
@@ -294,13 +255,40 @@ where
             return Ok(());
         }
 
+        let rows = batch.len();
+        let batch = batch.into_iter().flatten().collect::<Vec<T>>();
+        self.insert_batch_flat(&batch, rows)
+    }
+
+    /// Insert the `rows` vectors stored contiguously in `data`, in row-major order.
+    /// However note that they are not discoverable yet.
+    ///
+    /// Unlike [`insert_batch`](NgtIndex::insert_batch), the vectors are passed to NGT
+    /// without being copied, which matters for large ingests.
+    ///
+    /// **The method [`build`](NgtIndex::build) must be called after inserting vectors**.
+    pub fn insert_batch_flat(&mut self, data: &[T], rows: usize) -> Result<()> {
+        let batch_size = u32::try_from(rows)?;
+        let dim = self.prop.dimension as usize;
+        if rows.checked_mul(dim) != Some(data.len()) {
+            Err(format!(
+                "Inconsistent batch length, expected {rows} rows of dim {dim} got: {}",
+                data.len()
+            ))?
+        }
+        if batch_size == 0 {
+            return Ok(());
+        }
+
+        self.check_capacity(batch_size)?;
+
         unsafe {
-            let mut batch = batch.into_iter().flatten().collect::<Vec<T>>();
+            let first_id = self.next_batch_id();
             match self.prop.object_type {
                 NgtObject::Float => {
                     if !sys::ngt_batch_append_index(
                         self.index,
-                        batch.as_mut_ptr() as *mut f32,
+                        data.as_ptr() as *mut f32,
                         batch_size,
                         self.ebuf,
                     ) {
@@ -310,7 +298,7 @@ where
                 NgtObject::Uint8 => {
                     if !sys::ngt_batch_append_index_as_uint8(
                         self.index,
-                        batch.as_mut_ptr() as *mut u8,
+                        data.as_ptr() as *mut u8,
                         batch_size,
                         self.ebuf,
                     ) {
@@ -320,7 +308,7 @@ where
                 NgtObject::Float16 => {
                     if !sys::ngt_batch_append_index_as_float16(
                         self.index,
-                        batch.as_mut_ptr() as *mut _,
+                        data.as_ptr() as *mut _,
                         batch_size,
                         self.ebuf,
                     ) {
@@ -328,189 +316,2812 @@ where
                     }
                 }
             }
+            (first_id..first_id + batch_size).for_each(|id| self.timestamps.insert(id));
             Ok(())
         }
     }
 
+    /// The id of the first vector of the next batch, batches being appended after the
+    /// stored vectors with consecutive ids.
+    pub(crate) fn next_batch_id(&self) -> VecId {
+        unsafe { sys::ngt_get_object_repository_size(self.index, self.ebuf).max(1) }
+    }
+
+    /// Check that `nb_vecs` more vectors can be appended without overflowing their ids.
+    fn check_capacity(&self, nb_vecs: u32) -> Result<()> {
+        let repo_size = unsafe { sys::ngt_get_object_repository_size(self.index, self.ebuf) };
+        if repo_size.max(1).checked_add(nb_vecs).is_none() {
+            Err(Error::new(
+                ErrorKind::CapacityExceeded,
+                format!(
+                    "Index capacity exceeded: cannot insert {nb_vecs} more vectors, \
+                     ids are limited to {}. Consider splitting the vectors across \
+                     multiple indexes",
+                    VecId::MAX
+                ),
+            ))?
+        }
+        Ok(())
+    }
+
     /// Build the index for the vectors that have been inserted so far.
+    ///
+    /// NGT can't interrupt a build once started, so builds can't be cancelled. Nor does
+    /// it report its progress, its counters can't be read while it builds the index. To
+    /// be able to stop or follow a long build, insert the vectors in batches and build
+    /// after each of them, only the vectors inserted since the last build are added to
+    /// the graph.
     pub fn build(&mut self, num_threads: usize) -> Result<()> {
         unsafe {
             if !sys::ngt_create_index(self.index, num_threads as u32, self.ebuf) {
                 Err(make_err(self.ebuf))?
             }
+            self.timestamps.build();
             Ok(())
         }
     }
 
+    /// Build the index only for the vectors inserted since the last build, doing
+    /// nothing if there are none.
+    ///
+    /// NGT only adds the vectors missing from the graph when building, so already
+    /// indexed vectors aren't touched again, this mostly saves a build when nothing
+    /// has been inserted.
+    pub fn build_incremental(&mut self, num_threads: usize) -> Result<()> {
+        if self.nb_indexed() >= self.nb_inserted() {
+            return Ok(());
+        }
+        self.build(num_threads)
+    }
+
+    /// Repair what can be repaired from the [`verify`](NgtIndex::verify) report, and
+    /// return the report of the repaired index.
+    ///
+    /// Vectors missing from the graph are indexed by building the index. Dangling
+    /// edges and orphan nodes can't be repaired through NGT's C API, the `ngt repair`
+    /// command of NGT must be used for them.
+    pub fn repair(&mut self, num_threads: usize) -> Result<VerifyReport> {
+        if !self.verify()?.missing_nodes.is_empty() {
+            self.build(num_threads)?;
+        }
+        self.verify()
+    }
+
     /// Persist the index to the disk.
     pub fn persist(&mut self) -> Result<()> {
+        if self.is_in_memory() {
+            Err("In-memory indexes can't be persisted".to_string())?
+        }
+
         unsafe {
             if !sys::ngt_save_index(self.index, self.path.as_ptr(), self.ebuf) {
                 Err(make_err(self.ebuf))?
             }
-            Ok(())
         }
+        self.save_sidecars(self.path())
     }
 
-    /// Remove the specified vector.
-    pub fn remove(&mut self, id: VecId) -> Result<()> {
+    /// Copy the index to the specified path like [`snapshot_to`](NgtIndex::snapshot_to),
+    /// and open the copy.
+    ///
+    /// The clone is independent of this index, it can be used as a read replica or to
+    /// prepare a new version of the index while this one is still serving searches.
+    pub fn clone_to<P: AsRef<Path>>(&mut self, path: P) -> Result<Self> {
+        self.snapshot_to(path.as_ref())?;
+        Self::open(path)
+    }
+
+    /// Persist the index to the disk, such that a crash can't leave a partially
+    /// written index behind.
+    ///
+    /// The index is saved to a fresh directory next to the index one, that is then
+    /// atomically exchanged with the index directory, so that either version of the
+    /// index is always found at its path.
+    #[cfg(not(feature = "shared_mem"))]
+    pub fn persist_atomic(&mut self) -> Result<()> {
+        if self.is_in_memory() {
+            Err("In-memory indexes can't be persisted".to_string())?
+        }
+
+        let path = self.path().to_path_buf();
+        let (_staging, tmp) = staging_dir(&path, "persist")?;
+
+        fs::create_dir(&tmp)?;
         unsafe {
-            if !sys::ngt_remove_index(self.index, id, self.ebuf) {
+            let tmp = CString::new(tmp.as_os_str().as_bytes())?;
+            if !sys::ngt_save_index(self.index, tmp.as_ptr(), self.ebuf) {
                 Err(make_err(self.ebuf))?
             }
-            Ok(())
         }
+        self.save_sidecars(&tmp)?;
+
+        // The previous version ends up in the staging directory, removed when dropped
+        swap_in(&tmp, &path, |_| true)
     }
 
-    /// Get the specified vector.
-    pub fn get_vec(&self, id: VecId) -> Result<Vec<T>> {
-        unsafe {
-            match self.prop.object_type {
-                NgtObject::Float => {
-                    let results = sys::ngt_get_object_as_float(self.ospace, id, self.ebuf);
-                    if results.is_null() {
-                        Err(make_err(self.ebuf))?
-                    }
+    fn save_sidecars(&self, dir: &Path) -> Result<()> {
+        let prop = &self.prop;
+        Manifest::new(
+            prop.object_type,
+            prop.dimension as usize,
+            prop.distance_type,
+        )
+        .save(dir)?;
+        self.prop.config.save(dir)?;
+        self.timestamps.save(dir)?;
+        self.tombstones.save(dir)?;
+        self.expirations.save(dir)?;
+        Ok(())
+    }
 
-                    let results = Vec::from_raw_parts(
-                        results,
-                        self.prop.dimension as usize,
-                        self.prop.dimension as usize,
-                    );
-                    let results = mem::ManuallyDrop::new(results);
+    /// [`Persist`](NgtIndex::persist) the index and copy its directory to the
+    /// specified path, which must not exist.
+    ///
+    /// The copy is written to a fresh staging directory next to the path, and renamed
+    /// once complete and synced to the disk, so the path never holds a partial
+    /// snapshot. The returned [`ClosedIndex`][] can be archived or uploaded while this
+    /// index keeps being modified.
+    pub fn snapshot_to<P: AsRef<Path>>(&mut self, path: P) -> Result<ClosedIndex<T, ModeRead>> {
+        let path = path.as_ref();
+        if path.exists() {
+            Err(format!("Path {path:?} already exists"))?
+        }
+        self.persist()?;
+
+        let (_staging, tmp) = staging_dir(path, "snapshot")?;
+        copy_dir(self.path(), &tmp)?;
+        fs::rename(&tmp, path)?;
+        fs::File::open(parent_dir(path))?.sync_all()?;
+
+        Ok(ClosedIndex {
+            path: path.to_path_buf(),
+            mode: ModeRead,
+            _marker: PhantomData,
+        })
+    }
 
-                    let results = results.iter().copied().collect::<Vec<_>>();
-                    Ok(mem::transmute::<_, Vec<T>>(results))
-                }
-                NgtObject::Float16 => {
-                    let results = sys::ngt_get_object(self.ospace, id, self.ebuf);
-                    if results.is_null() {
-                        Err(make_err(self.ebuf))?
-                    }
+    /// [`Persist`](NgtIndex::persist) the index and write its directory as a single
+    /// file archive, which can be read back by [`import`](NgtIndex::import).
+    ///
+    /// The archive is an uncompressed tar file holding all the files of the index
+    /// directory, including the manifest checked on import.
+    pub fn export<W: Write>(&mut self, writer: W) -> Result<()> {
+        self.persist()?;
+        archive::write(self.path(), writer)
+    }
 
-                    let results = Vec::from_raw_parts(
-                        results as *mut half::f16,
-                        self.prop.dimension as usize,
-                        self.prop.dimension as usize,
-                    );
-                    let results = mem::ManuallyDrop::new(results);
+    /// Extract an archive written by [`export`](NgtIndex::export) to the specified
+    /// path, which must not exist, and open the index.
+    pub fn import<R: Read, P: AsRef<Path>>(reader: R, path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            Err(format!("Path {path:?} already exists"))?
+        }
 
-                    let results = results.iter().copied().collect::<Vec<_>>();
-                    Ok(mem::transmute::<_, Vec<T>>(results))
-                }
-                NgtObject::Uint8 => {
-                    let results = sys::ngt_get_object_as_integer(self.ospace, id, self.ebuf);
-                    if results.is_null() {
-                        Err(make_err(self.ebuf))?
-                    }
+        if let Err(e) = archive::read(reader, path) {
+            if path.exists() {
+                fs::remove_dir_all(path)?;
+            }
+            return Err(e);
+        }
+        Self::open(path)
+    }
 
-                    let results = Vec::from_raw_parts(
-                        results,
-                        self.prop.dimension as usize,
-                        self.prop.dimension as usize,
-                    );
-                    let results = mem::ManuallyDrop::new(results);
+    /// Remove the specified vector.
+    ///
+    /// Returns an error of kind [`NotFound`](ErrorKind::NotFound) if the vector
+    /// doesn't exist.
+    ///
+    /// NGT may assign the id to a vector inserted later on, unless disabled by
+    /// [`reuse_removed_ids`](NgtProperties::reuse_removed_ids).
+    pub fn remove(&mut self, id: VecId) -> Result<()> {
+        if !self.prop.config.reuse_removed_ids {
+            return self.soft_remove(id);
+        }
 
-                    let results = results.iter().copied().collect::<Vec<_>>();
-                    Ok(mem::transmute::<_, Vec<T>>(results))
-                }
+        self.check_exists(id)?;
+        unsafe {
+            if !sys::ngt_remove_index(self.index, id, self.ebuf) {
+                Err(make_err(self.ebuf))?
             }
+            self.timestamps.remove(id);
+            self.tombstones.remove(id);
+            self.expirations.remove(id);
+            Ok(())
         }
     }
 
-    /// The number of vectors inserted (but not necessarily indexed).
-    pub fn nb_inserted(&self) -> usize {
-        unsafe { sys::ngt_get_number_of_objects(self.index, self.ebuf) as usize }
+    /// Mark the specified vector as removed without touching the graph, it is then
+    /// excluded from search results until it is physically removed by
+    /// [`compact`](NgtIndex::compact).
+    ///
+    /// Soft removals are cheap, while a [`remove`](NgtIndex::remove) repairs the graph
+    /// around the removed node.
+    pub fn soft_remove(&mut self, id: VecId) -> Result<()> {
+        self.check_exists(id)?;
+        self.tombstones.insert(id);
+        Ok(())
     }
 
-    /// The number of indexed vectors, available after [`build`](NgtIndex::build).
-    pub fn nb_indexed(&self) -> usize {
-        unsafe { sys::ngt_get_number_of_indexed_objects(self.index, self.ebuf) as usize }
+    /// Remove the vectors whose time to live has elapsed, and return their ids.
+    ///
+    /// Expired vectors are [`soft removed`](NgtIndex::soft_remove), set `compact` to
+    /// also [`compact`](NgtIndex::compact) the index. Indexes that don't
+    /// [`reuse_removed_ids`](NgtProperties::reuse_removed_ids) can't be compacted in
+    /// place, see [`compact_to`](NgtIndex::compact_to).
+    pub fn expire_now(&mut self, compact: bool) -> Result<Vec<VecId>> {
+        if compact && !self.prop.config.reuse_removed_ids {
+            Err("Indexes not reusing removed ids can't be compacted in place".to_string())?
+        }
+
+        let expired = self.expirations.expired(SystemTime::now());
+        for &id in &expired {
+            if self.exists(id) {
+                self.soft_remove(id)?;
+            }
+            self.expirations.remove(id);
+        }
+        if compact {
+            self.compact()?;
+        }
+        Ok(expired)
+    }
+
+    /// Physically remove the [`soft removed`](NgtIndex::soft_remove) vectors, and
+    /// return their number.
+    ///
+    /// Fails if [`reuse_removed_ids`](NgtProperties::reuse_removed_ids) is disabled,
+    /// as NGT would recycle the ids of the removed vectors, such indexes are compacted
+    /// into a new index by [`compact_to`](NgtIndex::compact_to).
+    ///
+    /// The VP-tree of the index degrades after many removals, but NGT's C API can't
+    /// rebuild it without the graph. To restore the performance of a long-lived index,
+    /// rebuild it entirely with [`rebuild_with`](NgtIndex::rebuild_with).
+    pub fn compact(&mut self) -> Result<usize> {
+        if !self.prop.config.reuse_removed_ids {
+            Err("Indexes not reusing removed ids can't be compacted".to_string())?
+        }
+
+        let ids = self.tombstones.take();
+        for &id in &ids {
+            if let Err(e) = self.remove(id) {
+                // Keep the tombstones of the vectors not removed yet
+                ids.range(id..).for_each(|&id| self.tombstones.insert(id));
+                return Err(e);
+            }
+        }
+        Ok(ids.len())
     }
 }
 
-impl<T> Drop for NgtIndex<T> {
-    fn drop(&mut self) {
-        if !self.index.is_null() {
+impl<T> NgtIndex<T, ModeRead>
+where
+    T: NgtObjectType,
+{
+    /// Open the already existing index at the specified path in read-only mode.
+    ///
+    /// Read-only indexes can only be searched, which lets NGT use a leaner search path
+    /// and multiple processes open the same index safely.
+    pub fn open_readonly<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with(path, ModeRead)
+    }
+}
+
+impl<T, M> NgtIndex<T, M>
+where
+    T: NgtObjectType,
+    M: IndexMode,
+{
+    /// Close the index to release its memory, it can be reopened later on with the
+    /// returned [`ClosedIndex`][].
+    ///
+    /// The changes that haven't been [`persisted`](NgtIndex::persist) are lost, and
+    /// [`in-memory`](NgtIndex::create_in_memory) indexes can't be closed.
+    pub fn close(self) -> Result<ClosedIndex<T, M>> {
+        if self.is_in_memory() {
+            Err("In-memory indexes can't be closed".to_string())?
+        }
+
+        Ok(ClosedIndex {
+            path: self.path().to_path_buf(),
+            mode: self._mode,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Pipe the vectors of the index through `transform` into a new index created at
+    /// `dst_path` with the given [`NgtProperties`][], which is then built.
+    ///
+    /// The transform gets the id and the vector, and returns the new vector or `None`
+    /// to skip it. Soft removed vectors are skipped. The new index assigns its own ids,
+    /// so the pairs of source and destination ids are returned along with the new
+    /// index, and written in a manifest that can be read by
+    /// [`read_id_map`](migration::read_id_map).
+    pub fn rebuild_with<U, P, F>(
+        &self,
+        dst_path: P,
+        prop: NgtProperties<U>,
+        num_threads: usize,
+        mut transform: F,
+    ) -> Result<(NgtIndex<U>, IdMap)>
+    where
+        U: NgtObjectType,
+        P: AsRef<Path>,
+        F: FnMut(VecId, Vec<T>) -> Option<Vec<U>>,
+    {
+        let mut dst = NgtIndex::create(dst_path, prop)?;
+        let mut ids = Vec::new();
+        for id in self.ids() {
+            if let Some(vec) = transform(id, self.get_vec(id)?) {
+                ids.push((id, dst.insert(vec)?));
+            }
+        }
+        dst.build(num_threads)?;
+        migration::write_id_map(dst.path(), &ids)?;
+
+        Ok((dst, ids))
+    }
+
+    /// Rebuild the vectors of the index, except the
+    /// [`soft removed`](NgtIndex::soft_remove) ones, into a new index created at
+    /// `dst_path` with the same properties, which is then built.
+    ///
+    /// This reclaims the space of the removed vectors of indexes that don't
+    /// [`reuse_removed_ids`](NgtProperties::reuse_removed_ids), which can't be
+    /// [`compacted`](NgtIndex::compact) in place. The new index assigns its own ids,
+    /// the pairs of source and destination ids are returned like by
+    /// [`rebuild_with`](NgtIndex::rebuild_with), and the expirations are carried over
+    /// to the new ids.
+    pub fn compact_to<P: AsRef<Path>>(
+        &self,
+        dst_path: P,
+        num_threads: usize,
+    ) -> Result<(NgtIndex<T>, IdMap)> {
+        let prop = self.prop.try_clone()?;
+        let (mut dst, ids) = self.rebuild_with(dst_path, prop, num_threads, |_, vec| Some(vec))?;
+        for &(src_id, dst_id) in &ids {
+            if let Some(deadline) = self.expirations.get(src_id) {
+                dst.expirations.insert(dst_id, deadline);
+            }
+        }
+        dst.persist()?;
+        Ok((dst, ids))
+    }
+
+    fn open_with<P: AsRef<Path>>(path: P, mode: M) -> Result<Self> {
+        if !path.as_ref().exists() {
+            Err(format!("Path {:?} does not exist", path.as_ref()))?
+        }
+        let manifest = Manifest::load(path.as_ref())?;
+        if let Some(manifest) = &manifest {
+            manifest.check(T::as_obj())?;
+        }
+
+        unsafe {
+            let ebuf = sys::ngt_create_error_object();
+            defer! { sys::ngt_destroy_error_object(ebuf); }
+
+            let path = CString::new(path.as_ref().as_os_str().as_bytes())?;
+
+            let index = if M::READ_ONLY {
+                sys::ngt_open_index_as_read_only(path.as_ptr(), ebuf)
+            } else {
+                sys::ngt_open_index(path.as_ptr(), ebuf)
+            };
+            if index.is_null() {
+                Err(make_err(ebuf))?
+            }
+            // Close the index if its properties or sidecar files can't be loaded
+            let index = guard(index, |index| sys::ngt_close_index(index));
+
+            let ospace = sys::ngt_get_object_space(*index, ebuf);
+            if ospace.is_null() {
+                Err(make_err(ebuf))?
+            }
+
+            let dir = Path::new(OsStr::from_bytes(path.as_bytes()));
+            let mut prop = NgtProperties::from(*index)?;
+            if let Some(manifest) = &manifest {
+                manifest.check_properties(prop.dimension as usize, prop.distance_type)?;
+            }
+            prop.index_type = NgtIndexType::read(dir)?;
+            prop.graph_type = NgtGraphType::read(dir)?;
+            prop.tuning = Tuning::read(dir)?;
+            prop.config = Config::load(dir)?;
+            let timestamps = Timestamps::load(dir, prop.config.record_timestamps)?;
+            let tombstones = Tombstones::load(dir)?;
+            let expirations = Expirations::load(dir)?;
+            let accuracy_table = AccuracyTable::read(dir)?;
+
+            Ok(NgtIndex {
+                path,
+                prop,
+                index: ScopeGuard::into_inner(index),
+                _mode: mode,
+                ospace,
+                ebuf: sys::ngt_create_error_object(),
+                timestamps,
+                tombstones,
+                expirations,
+                accuracy_table,
+                results: Mutex::new(ptr::null_mut()),
+            })
+        }
+    }
+
+    /// Search the nearest vectors to the specified query vector.
+    ///
+    /// **The index must have been [`built`](NgtIndex::build) beforehand**, unless it
+    /// holds less vectors than its
+    /// [`linear_search_threshold`](NgtProperties::linear_search_threshold).
+    pub fn search(&self, vec: &[T], res_size: usize, epsilon: f32) -> Result<Vec<SearchResult>> {
+        if self.tombstones.is_empty() {
+            self.search_all(vec, res_size, epsilon)
+        } else {
+            self.search_filtered(vec, res_size, epsilon, |_| true)
+        }
+    }
+
+    /// Search including the [`soft removed`](NgtIndex::soft_remove) vectors.
+    fn search_all(&self, vec: &[T], res_size: usize, epsilon: f32) -> Result<Vec<SearchResult>> {
+        unsafe {
+            let results = sys::ngt_create_empty_results(self.ebuf);
+            if results.is_null() {
+                Err(make_err(self.ebuf))?
+            }
+            defer! { sys::ngt_destroy_results(results); }
+
+            if self.use_linear_search() {
+                self.linear_search(vec, res_size, results, self.ebuf)?;
+                return self.collect_results(results, self.ebuf, |d| {
+                    Ok(SearchResult {
+                        id: d.id,
+                        distance: d.distance,
+                    })
+                });
+            }
+
+            match T::as_obj() {
+                NgtObject::Float => {
+                    if !sys::ngt_search_index_as_float(
+                        self.index,
+                        vec.as_ptr() as *mut f32,
+                        self.prop.dimension,
+                        res_size,
+                        epsilon,
+                        -1.0,
+                        results,
+                        self.ebuf,
+                    ) {
+                        Err(make_err(self.ebuf))?
+                    }
+                }
+                NgtObject::Uint8 => {
+                    if !sys::ngt_search_index_as_uint8(
+                        self.index,
+                        vec.as_ptr() as *mut u8,
+                        self.prop.dimension,
+                        res_size,
+                        epsilon,
+                        -1.0,
+                        results,
+                        self.ebuf,
+                    ) {
+                        Err(make_err(self.ebuf))?
+                    }
+                }
+                NgtObject::Float16 => {
+                    if !sys::ngt_search_index_as_float16(
+                        self.index,
+                        vec.as_ptr() as *mut _,
+                        self.prop.dimension,
+                        res_size,
+                        epsilon,
+                        -1.0,
+                        results,
+                        self.ebuf,
+                    ) {
+                        Err(make_err(self.ebuf))?
+                    }
+                }
+            }
+
+            self.collect_results(results, self.ebuf, |d| {
+                Ok(SearchResult {
+                    id: d.id,
+                    distance: d.distance,
+                })
+            })
+        }
+    }
+
+    /// Search the nearest vectors to the specified query vector among those whose id
+    /// satisfies `filter`, for instance to restrict results to a tenant.
+    ///
+    /// Results are over-fetched until `res_size` of them satisfy `filter`, or until
+    /// all the vectors of the index have been fetched, so a very selective filter
+    /// can be as slow as a linear scan.
+    ///
+    /// **The index must have been [`built`](NgtIndex::build) beforehand**.
+    pub fn search_filtered<F>(
+        &self,
+        vec: &[T],
+        res_size: usize,
+        epsilon: f32,
+        mut filter: F,
+    ) -> Result<Vec<SearchResult>>
+    where
+        F: FnMut(VecId) -> bool,
+    {
+        if res_size == 0 {
+            return Ok(Vec::new());
+        }
+
+        let nb_vecs = self.nb_inserted();
+        let mut fetch_size = res_size.saturating_mul(2);
+        loop {
+            let res = self.search_all(vec, fetch_size, epsilon)?;
+            let exhausted = res.len() < fetch_size || fetch_size >= nb_vecs;
+            let res = res
+                .into_iter()
+                .filter(|r| !self.tombstones.contains(r.id) && filter(r.id))
+                .take(res_size)
+                .collect::<Vec<_>>();
+            if res.len() == res_size || exhausted {
+                return Ok(res);
+            }
+            fetch_size = fetch_size.saturating_mul(2);
+        }
+    }
+
+    /// Search all the vectors within `radius` of the specified query vector.
+    ///
+    /// NGT is asked for as many results as there are vectors in the index, so the
+    /// results aren't capped by a size as in [`search`](NgtIndex::search).
+    ///
+    /// NGT computes all the results upfront, they are then converted one by one while
+    /// iterating over the returned [`NgtResults`][], which saves allocating them all
+    /// at once.
+    ///
+    /// **The index must have been [`built`](NgtIndex::build) beforehand**.
+    pub fn range_search(&self, vec: &[T], radius: f32, epsilon: f32) -> Result<NgtResults<'_>> {
+        let size = self.nb_inserted().max(1);
+        unsafe {
+            let results = sys::ngt_create_empty_results(self.ebuf);
+            if results.is_null() {
+                Err(make_err(self.ebuf))?
+            }
+            let mut results = NgtResults {
+                results,
+                pos: 0,
+                size: 0,
+                nb_skipped: 0,
+                tombstones: &self.tombstones,
+                ebuf: self.ebuf,
+                to_result: Box::new(|id, distance| Ok(SearchResult { id, distance })),
+            };
+
+            match T::as_obj() {
+                NgtObject::Float => {
+                    if !sys::ngt_search_index_as_float(
+                        self.index,
+                        vec.as_ptr() as *mut f32,
+                        self.prop.dimension,
+                        size,
+                        epsilon,
+                        radius,
+                        results.results,
+                        self.ebuf,
+                    ) {
+                        Err(make_err(self.ebuf))?
+                    }
+                }
+                NgtObject::Uint8 => {
+                    if !sys::ngt_search_index_as_uint8(
+                        self.index,
+                        vec.as_ptr() as *mut u8,
+                        self.prop.dimension,
+                        size,
+                        epsilon,
+                        radius,
+                        results.results,
+                        self.ebuf,
+                    ) {
+                        Err(make_err(self.ebuf))?
+                    }
+                }
+                NgtObject::Float16 => {
+                    if !sys::ngt_search_index_as_float16(
+                        self.index,
+                        vec.as_ptr() as *mut _,
+                        self.prop.dimension,
+                        size,
+                        epsilon,
+                        radius,
+                        results.results,
+                        self.ebuf,
+                    ) {
+                        Err(make_err(self.ebuf))?
+                    }
+                }
+            }
+
+            results.count_results();
+            Ok(results)
+        }
+    }
+
+    /// Search the nearest vectors to each of the specified query vectors, and fuse
+    /// their results into the `res_size` best ones according to [`Fusion`][].
+    ///
+    /// This is useful when an item is represented by several vectors, such as the
+    /// crops of an image or the sentences of a text.
+    ///
+    /// **The index must have been [`built`](NgtIndex::build) beforehand**.
+    pub fn search_multi(
+        &self,
+        vecs: &[&[T]],
+        res_size: usize,
+        epsilon: f32,
+        fusion: Fusion,
+    ) -> Result<Vec<SearchResult>> {
+        let results = vecs
+            .iter()
+            .map(|vec| self.search(vec, res_size, epsilon))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut fused = BTreeMap::<VecId, f32>::new();
+        match fusion {
+            Fusion::MinDistance => {
+                for r in results.iter().flatten() {
+                    let d = fused.entry(r.id).or_insert(f32::INFINITY);
+                    *d = d.min(r.distance);
+                }
+            }
+            Fusion::Mean => {
+                let ids = results
+                    .iter()
+                    .flatten()
+                    .map(|r| r.id)
+                    .collect::<BTreeSet<_>>();
+                for id in ids {
+                    let mut sum = 0.;
+                    for (vec, res) in vecs.iter().zip(&results) {
+                        sum += match res.iter().find(|r| r.id == id) {
+                            Some(r) => r.distance,
+                            None => self.distance_to(vec, id)?,
+                        };
+                    }
+                    fused.insert(id, sum / vecs.len() as f32);
+                }
+            }
+            Fusion::ReciprocalRank => {
+                for res in &results {
+                    for (rank, r) in res.iter().enumerate() {
+                        *fused.entry(r.id).or_default() -= 1. / (RRF_K + rank as f32 + 1.);
+                    }
+                }
+            }
+        }
+
+        let mut fused = fused
+            .into_iter()
+            .map(|(id, distance)| SearchResult { id, distance })
+            .collect::<Vec<_>>();
+        fused.sort_by(|a, b| a.distance.total_cmp(&b.distance).then(a.id.cmp(&b.id)));
+        fused.truncate(res_size);
+        Ok(fused)
+    }
+
+    /// Search the nearest vectors to the specified [`NgtQuery`][], the results are
+    /// extracted lazily from NGT while iterating over the returned [`NgtResults`][].
+    ///
+    /// NGT still computes all the results at once, but callers stopping after the
+    /// first acceptable results don't pay for copying the others, nor for fetching
+    /// their vectors with [`with_vectors`](NgtQuery::with_vectors). Soft removed
+    /// vectors are skipped, so there may be fewer results than the query size.
+    ///
+    /// **The index must have been [`built`](NgtIndex::build) beforehand**.
+    pub fn search_iter<R>(&self, query: NgtQuery<T, R>) -> Result<NgtResults<'_, R>>
+    where
+        R: NgtQueryResult<T>,
+    {
+        unsafe {
+            let results = sys::ngt_create_empty_results(self.ebuf);
+            if results.is_null() {
+                Err(make_err(self.ebuf))?
+            }
+            let mut results = NgtResults {
+                results,
+                pos: 0,
+                size: 0,
+                nb_skipped: 0,
+                tombstones: &self.tombstones,
+                ebuf: self.ebuf,
+                to_result: Box::new(|id, distance| R::from_search(self, id, distance)),
+            };
+
+            self.search_query_into(&query, results.results, self.ebuf)?;
+            results.count_results();
+            Ok(results)
+        }
+    }
+
+    /// Search the nearest vectors to the specified [`NgtQuery`][], along with their
+    /// stored vectors for queries [`with_vectors`](NgtQuery::with_vectors).
+    ///
+    /// **The index must have been [`built`](NgtIndex::build) beforehand**, unless it
+    /// holds less vectors than its
+    /// [`linear_search_threshold`](NgtProperties::linear_search_threshold).
+    pub fn search_query<R>(&self, query: NgtQuery<T, R>) -> Result<Vec<R>>
+    where
+        R: NgtQueryResult<T>,
+    {
+        unsafe {
+            self.search_query_in(&query, self.ebuf, |d| {
+                R::from_search(self, d.id, d.distance)
+            })
+        }
+    }
+
+    /// Search the nearest vectors to each of the specified [`NgtQuery`][], which can
+    /// have different parameters, using `num_threads` threads.
+    ///
+    /// The results are in the same order as the queries.
+    ///
+    /// **The index must have been [`built`](NgtIndex::build) beforehand**.
+    pub fn search_queries<R>(
+        &self,
+        queries: &[NgtQuery<T, R>],
+        num_threads: usize,
+    ) -> Vec<Result<Vec<R>>>
+    where
+        T: Sync,
+        R: NgtQueryResult<T> + Send,
+    {
+        if queries.is_empty() {
+            return Vec::new();
+        }
+        let chunk_size = queries.len().div_ceil(num_threads.max(1));
+
+        thread::scope(|s| {
+            let handles = queries
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    s.spawn(move || unsafe {
+                        // Each thread reports its errors in its own error object
+                        let ebuf = sys::ngt_create_error_object();
+                        defer! { sys::ngt_destroy_error_object(ebuf); }
+
+                        chunk
+                            .iter()
+                            .map(|query| {
+                                self.search_query_in(query, ebuf, |d| {
+                                    R::from_search(self, d.id, d.distance)
+                                })
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+                .collect()
+        })
+    }
+
+    /// Search the nearest vectors to the specified [`NgtQuery`][], clearing and filling
+    /// the `out` buffer with the results.
+    ///
+    /// Reusing the same buffer, along with a NGT results object kept by the index,
+    /// avoids allocating on each query in tight serving loops. Concurrent searches and
+    /// searches of indexes with [`soft removed`](NgtIndex::soft_remove) vectors still
+    /// allocate.
+    ///
+    /// **The index must have been [`built`](NgtIndex::build) beforehand**.
+    pub fn search_into<R>(&self, query: NgtQuery<T, R>, out: &mut Vec<R>) -> Result<()>
+    where
+        R: NgtQueryResult<T>,
+    {
+        out.clear();
+        if !self.tombstones.is_empty() {
+            out.extend(self.search_query(query)?);
+            return Ok(());
+        }
+
+        let to_result = |d: sys::NGTObjectDistance| R::from_search(self, d.id, d.distance);
+        unsafe {
+            let Ok(mut results) = self.results.try_lock() else {
+                // Another thread is using the reusable results object
+                out.extend(self.search_query_all(&query, self.ebuf, to_result)?);
+                return Ok(());
+            };
+            if results.is_null() {
+                *results = sys::ngt_create_empty_results(self.ebuf);
+                if results.is_null() {
+                    Err(make_err(self.ebuf))?
+                }
+            }
+
+            self.search_query_into(&query, *results, self.ebuf)?;
+            self.collect_results_into(*results, self.ebuf, out, to_result)
+        }
+    }
+
+    /// Search the specified [`NgtQuery`][] reporting errors in `ebuf`, which allows
+    /// searching from multiple threads with one error object per thread.
+    pub(crate) unsafe fn search_query_in<Q, R, F>(
+        &self,
+        query: &NgtQuery<T, Q>,
+        ebuf: sys::NGTError,
+        f: F,
+    ) -> Result<Vec<R>>
+    where
+        F: FnMut(sys::NGTObjectDistance) -> Result<R>,
+    {
+        if self.tombstones.is_empty() {
+            return self.search_query_all(query, ebuf, f);
+        }
+
+        // Over-fetch results to make up for the soft removed vectors
+        let nb_vecs = self.nb_inserted();
+        let mut fetch = NgtQuery {
+            query: QueryVec::Borrowed(&query.query),
+            size: query.size.saturating_mul(2),
+            ..*query
+        };
+        loop {
+            let res = self.search_query_all(&fetch, ebuf, Ok)?;
+            let exhausted = fetch.size == 0 || res.len() < fetch.size || fetch.size >= nb_vecs;
+            let res = res
+                .into_iter()
+                .filter(|d| !self.tombstones.contains(d.id))
+                .take(if query.size == 0 {
+                    usize::MAX
+                } else {
+                    query.size
+                })
+                .collect::<Vec<_>>();
+            if res.len() == query.size || exhausted {
+                return res.into_iter().map(f).collect();
+            }
+            fetch.size = fetch.size.saturating_mul(2);
+        }
+    }
+
+    /// Search including the [`soft removed`](NgtIndex::soft_remove) vectors.
+    unsafe fn search_query_all<Q, R, F>(
+        &self,
+        query: &NgtQuery<T, Q>,
+        ebuf: sys::NGTError,
+        f: F,
+    ) -> Result<Vec<R>>
+    where
+        F: FnMut(sys::NGTObjectDistance) -> Result<R>,
+    {
+        let results = sys::ngt_create_empty_results(ebuf);
+        if results.is_null() {
+            Err(make_err(ebuf))?
+        }
+        defer! { sys::ngt_destroy_results(results); }
+
+        self.search_query_into(query, results, ebuf)?;
+        self.collect_results(results, ebuf, f)
+    }
+
+    /// Search the specified [`NgtQuery`][] and store the results in `results`.
+    unsafe fn search_query_into<Q>(
+        &self,
+        query: &NgtQuery<T, Q>,
+        results: sys::NGTObjectDistances,
+        ebuf: sys::NGTError,
+    ) -> Result<()> {
+        // Linear scans don't support range searches
+        if self.use_linear_search() && query.radius < 0. {
+            return self.linear_search(&query.query, query.size, results, ebuf);
+        }
+
+        let epsilon = match (query.expected_accuracy, &self.accuracy_table) {
+            (Some(accuracy), Some(table)) => table.epsilon(accuracy),
+            (Some(_), None) => Err(Error::new(
+                ErrorKind::NotFound,
+                "Searching by expected accuracy requires an accuracy table".to_string(),
+            ))?,
+            (None, _) => query.epsilon,
+        };
+
+        match T::as_obj() {
+            NgtObject::Float => {
+                let q = sys::NGTQueryFloat {
+                    query: query.query.as_ptr() as *mut f32,
+                    params: query.params(epsilon),
+                };
+                if !sys::ngt_search_index_with_query_float(self.index, q, results, ebuf) {
+                    Err(make_err(ebuf))?
+                }
+            }
+            NgtObject::Uint8 => {
+                let q = sys::NGTQueryUint8 {
+                    query: query.query.as_ptr() as *mut u8,
+                    params: query.params(epsilon),
+                };
+                if !sys::ngt_search_index_with_query_uint8(self.index, q, results, ebuf) {
+                    Err(make_err(ebuf))?
+                }
+            }
+            NgtObject::Float16 => {
+                let q = sys::NGTQueryFloat16 {
+                    query: query.query.as_ptr() as *mut _,
+                    params: query.params(epsilon),
+                };
+                if !sys::ngt_search_index_with_query_float16(self.index, q, results, ebuf) {
+                    Err(make_err(ebuf))?
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The exact `res_size` nearest vectors, found by a linear scan of all the vectors.
+    pub(crate) fn search_exact(&self, vec: &[T], res_size: usize) -> Result<Vec<SearchResult>> {
+        unsafe {
+            let results = sys::ngt_create_empty_results(self.ebuf);
+            if results.is_null() {
+                Err(make_err(self.ebuf))?
+            }
+            defer! { sys::ngt_destroy_results(results); }
+
+            let fetch = res_size.saturating_add(self.tombstones.len());
+            self.linear_search(vec, fetch, results, self.ebuf)?;
+            let mut res = self.collect_results(results, self.ebuf, |d| {
+                Ok(SearchResult {
+                    id: d.id,
+                    distance: d.distance,
+                })
+            })?;
+            res.retain(|r| !self.tombstones.contains(r.id));
+            res.truncate(res_size);
+            Ok(res)
+        }
+    }
+
+    fn use_linear_search(&self) -> bool {
+        let nb_live = self.nb_inserted().saturating_sub(self.tombstones.len());
+        nb_live < self.prop.config.linear_search_threshold
+    }
+
+    unsafe fn linear_search(
+        &self,
+        vec: &[T],
+        res_size: usize,
+        results: sys::NGTObjectDistances,
+        ebuf: sys::NGTError,
+    ) -> Result<()> {
+        match T::as_obj() {
+            NgtObject::Float => {
+                if !sys::ngt_linear_search_index_as_float(
+                    self.index,
+                    vec.as_ptr() as *mut f32,
+                    self.prop.dimension,
+                    res_size,
+                    results,
+                    ebuf,
+                ) {
+                    Err(make_err(ebuf))?
+                }
+            }
+            NgtObject::Uint8 => {
+                if !sys::ngt_linear_search_index_as_uint8(
+                    self.index,
+                    vec.as_ptr() as *mut u8,
+                    self.prop.dimension,
+                    res_size,
+                    results,
+                    ebuf,
+                ) {
+                    Err(make_err(ebuf))?
+                }
+            }
+            NgtObject::Float16 => {
+                if !sys::ngt_linear_search_index_as_float16(
+                    self.index,
+                    vec.as_ptr() as *mut _,
+                    self.prop.dimension,
+                    res_size,
+                    results,
+                    ebuf,
+                ) {
+                    Err(make_err(ebuf))?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Extract the results one by one, NGT's C API has no bulk accessor and the
+    /// results are a C++ vector whose layout isn't part of the API. Each call is a
+    /// bounds-checked read, cheap next to the search itself.
+    unsafe fn collect_results<R, F>(
+        &self,
+        results: sys::NGTObjectDistances,
+        ebuf: sys::NGTError,
+        f: F,
+    ) -> Result<Vec<R>>
+    where
+        F: FnMut(sys::NGTObjectDistance) -> Result<R>,
+    {
+        let mut ret = Vec::new();
+        self.collect_results_into(results, ebuf, &mut ret, f)?;
+        Ok(ret)
+    }
+
+    /// Extract the results into `out`, see
+    /// [`collect_results`](NgtIndex::collect_results).
+    unsafe fn collect_results_into<R, F>(
+        &self,
+        results: sys::NGTObjectDistances,
+        ebuf: sys::NGTError,
+        out: &mut Vec<R>,
+        mut f: F,
+    ) -> Result<()>
+    where
+        F: FnMut(sys::NGTObjectDistance) -> Result<R>,
+    {
+        let rsize = sys::ngt_get_result_size(results, ebuf);
+        out.reserve(rsize as usize);
+
+        for i in 0..rsize {
+            let d = sys::ngt_get_result(results, i, ebuf);
+            if d.id == 0 && d.distance == 0.0 {
+                Err(make_err(ebuf))?
+            } else {
+                out.push(f(d)?);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the specified vector.
+    ///
+    /// Returns an error of kind [`NotFound`](ErrorKind::NotFound) if the vector
+    /// doesn't exist.
+    pub fn get_vec(&self, id: VecId) -> Result<Vec<T>> {
+        self.check_exists(id)?;
+        unsafe {
+            match self.prop.object_type {
+                NgtObject::Float => {
+                    let results = sys::ngt_get_object_as_float(self.ospace, id, self.ebuf);
+                    if results.is_null() {
+                        Err(make_err(self.ebuf))?
+                    }
+
+                    let results = Vec::from_raw_parts(
+                        results,
+                        self.prop.dimension as usize,
+                        self.prop.dimension as usize,
+                    );
+                    let results = mem::ManuallyDrop::new(results);
+
+                    let results = results.iter().copied().collect::<Vec<_>>();
+                    Ok(mem::transmute::<_, Vec<T>>(results))
+                }
+                NgtObject::Float16 => {
+                    let results = sys::ngt_get_object(self.ospace, id, self.ebuf);
+                    if results.is_null() {
+                        Err(make_err(self.ebuf))?
+                    }
+
+                    let results = Vec::from_raw_parts(
+                        results as *mut half::f16,
+                        self.prop.dimension as usize,
+                        self.prop.dimension as usize,
+                    );
+                    let results = mem::ManuallyDrop::new(results);
+
+                    let results = results.iter().copied().collect::<Vec<_>>();
+                    Ok(mem::transmute::<_, Vec<T>>(results))
+                }
+                NgtObject::Uint8 => {
+                    let results = sys::ngt_get_object_as_integer(self.ospace, id, self.ebuf);
+                    if results.is_null() {
+                        Err(make_err(self.ebuf))?
+                    }
+
+                    let results = Vec::from_raw_parts(
+                        results,
+                        self.prop.dimension as usize,
+                        self.prop.dimension as usize,
+                    );
+                    let results = mem::ManuallyDrop::new(results);
+
+                    let results = results.iter().copied().collect::<Vec<_>>();
+                    Ok(mem::transmute::<_, Vec<T>>(results))
+                }
+            }
+        }
+    }
+
+    /// The distance between the two specified vectors, computed with the distance
+    /// function of the index.
+    pub fn distance_between(&self, a: VecId, b: VecId) -> Result<f32> {
+        distance::compute(
+            self.prop.distance_type,
+            &self.get_vec(a)?,
+            &self.get_vec(b)?,
+        )
+    }
+
+    /// The distance between the specified query vector and stored vector, computed
+    /// with the distance function of the index. Useful to re-rank search results.
+    ///
+    /// Vectors stored in normalized indexes are normalized, unlike the query.
+    pub fn distance_to(&self, query: &[T], id: VecId) -> Result<f32> {
+        distance::compute(self.prop.distance_type, query, &self.get_vec(id)?)
+    }
+
+    /// The outgoing edges of the specified vector in the graph, as the ids of its
+    /// neighbors along with their distance.
+    ///
+    /// **The index must have been [`built`](NgtIndex::build) beforehand**.
+    pub fn edges(&self, id: VecId) -> Result<Vec<(VecId, f32)>> {
+        unsafe {
+            let edges = sys::ngt_create_empty_results(self.ebuf);
+            if edges.is_null() {
+                Err(make_err(self.ebuf))?
+            }
+            defer! { sys::ngt_destroy_results(edges); }
+
+            if !sys::ngt_get_edges(self.index, id, edges, self.ebuf) {
+                Err(make_err(self.ebuf))?
+            }
+
+            self.collect_results(edges, self.ebuf, |d| Ok((d.id, d.distance)))
+        }
+    }
+
+    /// Write the adjacency lists of the whole graph in the specified format, see
+    /// [`GraphFormat`][]. Nodes are written in the order of their ids.
+    ///
+    /// Note that the exported graph can't be imported back, nor can a graph computed
+    /// externally be installed in place of [`build`](NgtIndex::build): NGT C API
+    /// doesn't allow to set the edges of a node. To speed up the construction of large
+    /// indexes, use more threads when building or tune
+    /// [`batch_size_for_creation`](NgtProperties::batch_size_for_creation) instead.
+    ///
+    /// **The index must have been [`built`](NgtIndex::build) beforehand**.
+    pub fn export_graph<W: Write>(&self, writer: W, format: GraphFormat) -> Result<()> {
+        let nodes = self.stored_ids().map(|id| Ok((id, self.edges(id)?)));
+        graph::write(writer, format, nodes)
+    }
+
+    /// Check the consistency of the graph with the stored vectors, see
+    /// [`VerifyReport`][].
+    pub fn verify(&self) -> Result<VerifyReport> {
+        let ids = self.stored_ids().collect::<BTreeSet<_>>();
+        let mut report = VerifyReport::default();
+        let mut targets = BTreeSet::new();
+
+        for &id in &ids {
+            let Ok(edges) = self.edges(id) else {
+                report.missing_nodes.push(id);
+                continue;
+            };
+            for (target, _) in edges {
+                if ids.contains(&target) {
+                    targets.insert(target);
+                } else {
+                    report.dangling_edges.push((id, target));
+                }
+            }
+        }
+
+        // Nodes of a graph with a single node can't have incoming edges
+        if ids.len() > 1 {
+            report.orphan_nodes = ids
+                .iter()
+                .filter(|id| !targets.contains(id) && !report.missing_nodes.contains(id))
+                .copied()
+                .collect();
+        }
+
+        Ok(report)
+    }
+
+    /// Iterate over the ids of all the vectors currently stored in the index, removed
+    /// vectors are skipped, including the [`soft removed`](NgtIndex::soft_remove) ones.
+    pub fn ids(&self) -> impl Iterator<Item = VecId> + '_ {
+        self.stored_ids()
+            .filter(|&id| !self.tombstones.contains(id))
+    }
+
+    /// The ids of all the stored vectors, including the soft removed ones which are
+    /// still nodes of the graph.
+    pub(crate) fn stored_ids(&self) -> impl Iterator<Item = VecId> + '_ {
+        let repo_size = unsafe { sys::ngt_get_object_repository_size(self.index, self.ebuf) };
+        (1..repo_size).filter(move |&id| self.exists(id))
+    }
+
+    fn exists(&self, id: VecId) -> bool {
+        unsafe {
+            let obj = sys::ngt_get_object(self.ospace, id, self.ebuf);
+            if obj.is_null() {
+                sys::ngt_clear_error_string(self.ebuf);
+                false
+            } else {
+                true
+            }
+        }
+    }
+
+    fn check_exists(&self, id: VecId) -> Result<()> {
+        if !self.exists(id) {
+            Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Vector {id} not found"),
+            ))?
+        }
+        Ok(())
+    }
+
+    /// Iterate over all the vectors currently stored in the index along with their
+    /// ids, removed vectors are skipped as in [`ids`](NgtIndex::ids). Failures to read a
+    /// vector are yielded.
+    pub fn iter(&self) -> impl Iterator<Item = Result<(VecId, Vec<T>)>> + '_ {
+        self.ids().map(move |id| Ok((id, self.get_vec(id)?)))
+    }
+
+    /// The time at which the specified vector was inserted, if known, that is if the
+    /// index [`records timestamps`](NgtProperties::record_timestamps).
+    pub fn inserted_at(&self, id: VecId) -> Option<SystemTime> {
+        self.timestamps.inserted_at(id)
+    }
+
+    /// The time at which the specified vector expires, if it was inserted with a time
+    /// to live.
+    pub fn expires_at(&self, id: VecId) -> Option<SystemTime> {
+        self.expirations.get(id)
+    }
+
+    /// Make the specified vector expire at `deadline`, used to carry expirations over
+    /// to reinserted vectors.
+    pub(crate) fn set_expiration(&mut self, id: VecId, deadline: SystemTime) {
+        self.expirations.insert(id, deadline);
+    }
+
+    /// Whether the id of the specified vector was previously assigned to a removed
+    /// vector, according to the insertions and removals known to this index, which are
+    /// only recorded if it [`records timestamps`](NgtProperties::record_timestamps).
+    pub fn was_recycled(&self, id: VecId) -> bool {
+        self.timestamps.recycled(id)
+    }
+
+    /// The time of the last [`build`](NgtIndex::build), if known.
+    pub fn last_build(&self) -> Option<SystemTime> {
+        self.timestamps.last_build()
+    }
+
+    /// The ids of the vectors inserted after the specified time.
+    ///
+    /// Timestamps are [`persisted`](NgtIndex::persist) alongside the index when it
+    /// [`records them`](NgtProperties::record_timestamps), the vectors inserted while
+    /// it didn't are unknown.
+    pub fn ids_inserted_after(&self, time: SystemTime) -> Vec<VecId> {
+        self.timestamps.inserted_after(time).collect()
+    }
+
+    /// The ids of the vectors inserted since the last [`build`](NgtIndex::build), only
+    /// known if the index [`records timestamps`](NgtProperties::record_timestamps).
+    pub fn unindexed_ids(&self) -> Vec<VecId> {
+        self.timestamps.unindexed().collect()
+    }
+
+    /// The path of the index directory, empty for
+    /// [`in-memory`](NgtIndex::create_in_memory) indexes.
+    pub fn path(&self) -> &Path {
+        Path::new(OsStr::from_bytes(self.path.as_bytes()))
+    }
+
+    /// Whether the index is held in memory only, see
+    /// [`create_in_memory`](NgtIndex::create_in_memory).
+    pub fn is_in_memory(&self) -> bool {
+        self.path.as_bytes().is_empty()
+    }
+
+    /// The dimension of the vectors of the index.
+    pub fn dimension(&self) -> usize {
+        self.prop.dimension as usize
+    }
+
+    /// Create a [`NgtQuery`][] for the specified vector, with the
+    /// [`default_epsilon`](NgtIndex::default_epsilon) and the
+    /// [`search_edge_size`](NgtIndex::search_edge_size) of the index.
+    ///
+    /// Both are read when the index is opened. The search coefficients tuned by
+    /// [`optimize_anng_search_parameters`](crate::optim::optimize_anng_search_parameters)
+    /// aren't part of the query, NGT applies them to every search of an index opened
+    /// after the optimization.
+    pub fn query<'a>(&self, vec: &'a [T]) -> NgtQuery<'a, T> {
+        NgtQuery::new(vec)
+            .epsilon(self.default_epsilon())
+            .edge_size(self.search_edge_size())
+    }
+
+    /// The properties of the index.
+    pub fn properties(&self) -> &NgtProperties<T> {
+        &self.prop
+    }
+
+    /// The type of the vectors of the index.
+    pub fn object_type(&self) -> NgtObject {
+        self.prop.object_type
+    }
+
+    /// The number of edges of each node when creating the graph.
+    pub fn creation_edge_size(&self) -> usize {
+        self.prop.creation_edge_size as usize
+    }
+
+    /// The number of edges explored from each node when searching.
+    pub fn search_edge_size(&self) -> usize {
+        self.prop.search_edge_size as usize
+    }
+
+    /// The epsilon of the queries created by [`query`](NgtIndex::query), see
+    /// [`NgtProperties::default_epsilon`][].
+    pub fn default_epsilon(&self) -> f32 {
+        self.prop.config.epsilon
+    }
+
+    /// The epsilon used by [`NgtQuery`][] searches with the specified
+    /// [`expected_accuracy`](NgtQuery::expected_accuracy), `None` if the index has no
+    /// accuracy table, see [`build_accuracy_table`](crate::optim::build_accuracy_table).
+    pub fn accuracy_epsilon(&self, accuracy: f32) -> Option<f32> {
+        self.accuracy_table
+            .as_ref()
+            .map(|table| table.epsilon(accuracy))
+    }
+
+    /// The distance function of the index, which determines the unit of the
+    /// distances of search results.
+    pub fn distance_type(&self) -> NgtDistance {
+        self.prop.distance_type
+    }
+
+    /// The structure of the index, graph and tree or graph-only.
+    pub fn index_type(&self) -> NgtIndexType {
+        self.prop.index_type
+    }
+
+    /// The kind of graph of the index.
+    pub fn graph_type(&self) -> NgtGraphType {
+        self.prop.graph_type
+    }
+
+    /// The number of vectors inserted (but not necessarily indexed).
+    pub fn nb_inserted(&self) -> usize {
+        unsafe { sys::ngt_get_number_of_objects(self.index, self.ebuf) as usize }
+    }
+
+    /// The number of indexed vectors, available after [`build`](NgtIndex::build).
+    pub fn nb_indexed(&self) -> usize {
+        unsafe { sys::ngt_get_number_of_indexed_objects(self.index, self.ebuf) as usize }
+    }
+}
+
+impl<T, M> Drop for NgtIndex<T, M> {
+    fn drop(&mut self) {
+        if !self.index.is_null() {
             unsafe { sys::ngt_close_index(self.index) };
             self.index = ptr::null_mut();
         }
-        if !self.ebuf.is_null() {
-            unsafe { sys::ngt_destroy_error_object(self.ebuf) };
-            self.ebuf = ptr::null_mut();
+        if !self.ebuf.is_null() {
+            unsafe { sys::ngt_destroy_error_object(self.ebuf) };
+            self.ebuf = ptr::null_mut();
+        }
+        let results = match self.results.get_mut() {
+            Ok(results) => results,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if !results.is_null() {
+            unsafe { sys::ngt_destroy_results(*results) };
+            *results = ptr::null_mut();
+        }
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+pub trait IndexMode: private::Sealed + Copy {
+    #[doc(hidden)]
+    const READ_ONLY: bool;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ModeRead;
+
+impl private::Sealed for ModeRead {}
+impl IndexMode for ModeRead {
+    const READ_ONLY: bool = true;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ModeWrite;
+
+impl private::Sealed for ModeWrite {}
+impl IndexMode for ModeWrite {
+    const READ_ONLY: bool = false;
+}
+
+/// Search results converted one by one from NGT's result set, see
+/// [`NgtIndex::range_search`] and [`NgtIndex::search_iter`].
+pub struct NgtResults<'a, R = SearchResult> {
+    results: sys::NGTObjectDistances,
+    pos: u32,
+    size: u32,
+    nb_skipped: u32,
+    tombstones: &'a Tombstones,
+    ebuf: sys::NGTError,
+    to_result: Box<dyn Fn(VecId, f32) -> Result<R> + 'a>,
+}
+
+impl<R> fmt::Debug for NgtResults<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NgtResults")
+            .field("pos", &self.pos)
+            .field("size", &self.size)
+            .field("nb_skipped", &self.nb_skipped)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R> NgtResults<'_, R> {
+    /// Count the results, and the soft removed ones that will be skipped.
+    unsafe fn count_results(&mut self) {
+        self.size = sys::ngt_get_result_size(self.results, self.ebuf);
+        if !self.tombstones.is_empty() {
+            self.nb_skipped = (0..self.size)
+                .map(|i| sys::ngt_get_result(self.results, i, self.ebuf))
+                .filter(|d| self.tombstones.contains(d.id))
+                .count() as u32;
+        }
+    }
+}
+
+impl<R> Iterator for NgtResults<'_, R> {
+    type Item = Result<R>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.pos >= self.size {
+                return None;
+            }
+
+            let d = unsafe { sys::ngt_get_result(self.results, self.pos, self.ebuf) };
+            self.pos += 1;
+            if d.id == 0 && d.distance == 0.0 {
+                return Some(Err(make_err(self.ebuf)));
+            } else if self.tombstones.contains(d.id) {
+                self.nb_skipped -= 1;
+            } else {
+                return Some((self.to_result)(d.id, d.distance));
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.size - self.pos - self.nb_skipped) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<R> ExactSizeIterator for NgtResults<'_, R> {}
+
+impl<R> Drop for NgtResults<'_, R> {
+    fn drop(&mut self) {
+        if !self.results.is_null() {
+            unsafe { sys::ngt_destroy_results(self.results) };
+            self.results = ptr::null_mut();
+        }
+    }
+}
+
+/// Constant of the reciprocal rank fusion, which dampens the weight of the top ranks.
+const RRF_K: f32 = 60.;
+
+/// How the results of several query vectors are fused, see
+/// [`NgtIndex::search_multi`][].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fusion {
+    /// The distance of a vector is its smallest distance to the queries.
+    MinDistance,
+    /// The distance of a vector is its mean distance to the queries. Distances to
+    /// the queries that didn't find the vector are computed, which isn't supported by
+    /// all the distance types.
+    Mean,
+    /// Reciprocal rank fusion, the score of a vector is the sum of `1 / (60 + rank)`
+    /// over the queries that found it. The distance of the results is the opposite of
+    /// their score.
+    ReciprocalRank,
+}
+
+/// A closed [`NgtIndex`][], see [`NgtIndex::close`][].
+#[derive(Debug, Clone)]
+pub struct ClosedIndex<T, M = ModeWrite> {
+    path: PathBuf,
+    mode: M,
+    _marker: PhantomData<T>,
+}
+
+impl<T, M> ClosedIndex<T, M>
+where
+    T: NgtObjectType,
+    M: IndexMode,
+{
+    /// Reopen the index, in the same mode it was opened before being closed.
+    pub fn reopen(&self) -> Result<NgtIndex<T, M>> {
+        NgtIndex::open_with(&self.path, self.mode)
+    }
+
+    /// The path of the index directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// The consistency report of an index, see [`NgtIndex::verify`][].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Stored vectors without a node in the graph, typically not built yet.
+    pub missing_nodes: Vec<VecId>,
+    /// Edges `(from, to)` pointing to vectors that aren't stored anymore.
+    pub dangling_edges: Vec<(VecId, VecId)>,
+    /// Nodes of the graph without incoming edges, they can't be found by searches
+    /// walking the graph.
+    pub orphan_nodes: Vec<VecId>,
+}
+
+impl VerifyReport {
+    /// Whether no inconsistency was found.
+    pub fn is_consistent(&self) -> bool {
+        self.missing_nodes.is_empty()
+            && self.dangling_edges.is_empty()
+            && self.orphan_nodes.is_empty()
+    }
+}
+
+/// A search query along with its parameters, see [`NgtIndex::search_query`][].
+///
+/// NGT can't abort a search once started, its latency is bounded by the explored
+/// part of the graph instead, that is by [`epsilon`](NgtQuery::epsilon) and
+/// [`edge_size`](NgtQuery::edge_size).
+///
+/// Searches return `R` results, [`SearchResult`][]s unless the query is
+/// [`with_vectors`](NgtQuery::with_vectors).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NgtQuery<'a, T, R = SearchResult> {
+    query: QueryVec<'a, T>,
+    pub size: usize,
+    pub epsilon: f32,
+    pub expected_accuracy: Option<f32>,
+    pub edge_size: usize,
+    pub radius: f32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _result: PhantomData<fn() -> R>,
+}
+
+impl<'a, T> NgtQuery<'a, T>
+where
+    T: NgtObjectType,
+{
+    pub fn new(query: &'a [T]) -> Self {
+        Self::with_query(QueryVec::Borrowed(query))
+    }
+
+    /// Create a query from `f64` values, converting them to the index's object type.
+    ///
+    /// Values are rounded to the nearest float for `f32` and `f16`. Fails if a value
+    /// can't be represented by the object type, for instance a fractional or negative
+    /// value for `u8`, or an out of range value for `f16`.
+    pub fn from_f64(query: &[f64]) -> Result<NgtQuery<'static, T>> {
+        let query = QueryVec::convert(query, T::as_obj(), T::try_from_f64)?;
+        Ok(NgtQuery::with_query(query))
+    }
+
+    /// Create a query from `i32` values, converting them to the index's object type.
+    ///
+    /// Fails if a value can't be represented exactly by the object type, for instance
+    /// a negative value for `u8`, or a value above `2^24` in magnitude for `f32`.
+    pub fn from_i32(query: &[i32]) -> Result<NgtQuery<'static, T>> {
+        let query = QueryVec::convert(query, T::as_obj(), T::try_from_i32)?;
+        Ok(NgtQuery::with_query(query))
+    }
+
+    fn with_query(query: QueryVec<'a, T>) -> Self {
+        Self {
+            query,
+            size: 10,
+            epsilon: 0.1,
+            expected_accuracy: None,
+            edge_size: usize::MIN,
+            radius: -1.,
+            _result: PhantomData,
+        }
+    }
+
+    /// Fetch the stored vectors of the results along with them, searches then return
+    /// [`SearchResultWithVec`][]s.
+    pub fn with_vectors(self) -> NgtQuery<'a, T, SearchResultWithVec<T>> {
+        NgtQuery {
+            query: self.query,
+            size: self.size,
+            epsilon: self.epsilon,
+            expected_accuracy: self.expected_accuracy,
+            edge_size: self.edge_size,
+            radius: self.radius,
+            _result: PhantomData,
+        }
+    }
+}
+
+impl<T, R> NgtQuery<'_, T, R> {
+    pub fn size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Set the epsilon of the search, defaults to `0.1`. Queries created by
+    /// [`NgtIndex::query`][] get the index's
+    /// [`default_epsilon`](NgtIndex::default_epsilon) instead.
+    pub fn epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// Set the accuracy expected from the search, between `0` and `1`, its epsilon is
+    /// then derived from the accuracy table of the index and the query's
+    /// [`epsilon`](NgtQuery::epsilon) is ignored.
+    ///
+    /// Searching fails if the index has no accuracy table, see
+    /// [`build_accuracy_table`](crate::optim::build_accuracy_table).
+    pub fn expected_accuracy(mut self, expected_accuracy: f32) -> Self {
+        self.expected_accuracy = Some(expected_accuracy);
+        self
+    }
+
+    pub fn edge_size(mut self, edge_size: usize) -> Self {
+        self.edge_size = edge_size;
+        self
+    }
+
+    pub fn radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    unsafe fn params(&self, epsilon: f32) -> sys::NGTQueryParameters {
+        sys::NGTQueryParameters {
+            size: self.size,
+            epsilon,
+            edge_size: self.edge_size,
+            radius: self.radius,
+        }
+    }
+}
+
+/// The results of the searches of a [`NgtQuery`][], built from the ids and distances
+/// found by NGT.
+pub trait NgtQueryResult<T>: private::Sealed + Sized {
+    #[doc(hidden)]
+    fn from_search<M: IndexMode>(index: &NgtIndex<T, M>, id: VecId, distance: f32) -> Result<Self>;
+}
+
+impl private::Sealed for SearchResult {}
+impl<T: NgtObjectType> NgtQueryResult<T> for SearchResult {
+    fn from_search<M: IndexMode>(_: &NgtIndex<T, M>, id: VecId, distance: f32) -> Result<Self> {
+        Ok(SearchResult { id, distance })
+    }
+}
+
+impl<T> private::Sealed for SearchResultWithVec<T> {}
+impl<T: NgtObjectType> NgtQueryResult<T> for SearchResultWithVec<T> {
+    fn from_search<M: IndexMode>(index: &NgtIndex<T, M>, id: VecId, distance: f32) -> Result<Self> {
+        Ok(SearchResultWithVec {
+            id,
+            distance,
+            vec: index.get_vec(id)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::iter;
+    use std::result::Result as StdResult;
+
+    use half::f16;
+    use rayon::prelude::*;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::NgtSeedType;
+
+    const EPSILON: f32 = 0.1;
+
+    #[test]
+    fn test_ngt_f32_basics() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        assert_eq!(index.path(), dir.path());
+        assert_eq!(index.dimension(), 3);
+        assert_eq!(index.object_type(), NgtObject::Float);
+        assert_eq!(index.distance_type(), NgtDistance::L2);
+        assert_eq!(index.creation_edge_size(), 10);
+        assert_eq!(index.search_edge_size(), 40);
+        let prop = index.properties();
+        assert_eq!(prop.get_dimension(), index.dimension());
+        assert_eq!(prop.get_creation_edge_size(), 10);
+        assert_eq!(prop.get_search_edge_size(), 40);
+        assert_eq!(prop.get_object_type(), NgtObject::Float);
+        assert_eq!(prop.get_distance_type(), NgtDistance::L2);
+        assert_eq!(prop.get_default_epsilon(), index.default_epsilon());
+
+        // Insert two vectors and get their id
+        let vec1 = vec![1.0, 2.0, 3.0];
+        let vec2 = vec![4.0, 5.0, 6.0];
+        let id1 = index.insert(vec1.clone())?;
+        let id2 = index.insert(vec2.clone())?;
+        assert!(index.nb_inserted() == 2);
+        assert!(index.nb_indexed() == 0);
+
+        // Actually build the index (not yet persisted on disk)
+        // This is required in order to be able to search vectors
+        index.build(2)?;
+        assert!(index.nb_inserted() == 2);
+        assert!(index.nb_indexed() == 2);
+
+        // Perform a vector search (using the NgtQuery API)
+        let query = vec![1.1, 2.1, 3.1];
+        let res = index.search_query(NgtQuery::new(&query))?;
+        assert_eq!(id1, res[0].id);
+        assert_eq!(vec1, index.get_vec(id1)?);
+
+        // Remove a vector and check that it is not present anymore
+        index.remove(id1)?;
+        let res = index.get_vec(id1);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::NotFound);
+        assert_eq!(index.remove(id1).unwrap_err().kind(), ErrorKind::NotFound);
+        assert!(index.nb_inserted() == 1);
+        assert!(index.nb_indexed() == 1);
+
+        // Verify that now our search result is different
+        let res = index.search(&vec![1.1, 2.1, 3.1], 1, EPSILON)?;
+        assert_eq!(id2, res[0].id);
+        assert_eq!(vec2, index.get_vec(id2)?);
+
+        // Persist index on disk, and open it again
+        index.persist()?;
+        index = NgtIndex::open(dir.path())?;
+        assert!(index.nb_inserted() == 1);
+        assert!(index.nb_indexed() == 1);
+
+        // Check that the removed vector wasn't persisted
+        let res = index.get_vec(id1);
+        assert!(res.is_err());
+
+        // Verify that out search result is still consistent
+        let res = index.search(&vec![1.1, 2.1, 3.1], 1, EPSILON)?;
+        assert_eq!(id2, res[0].id);
+        assert_eq!(vec2, index.get_vec(id2)?);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_open_readonly() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create, build and persist an index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        index.insert(vec![4.0, 5.0, 6.0])?;
+        index.build(1)?;
+        index.persist()?;
+        drop(index);
+
+        // Open it in read-only mode and verify that it is searchable
+        let index = NgtIndex::<f32, ModeRead>::open_readonly(dir.path())?;
+        assert_eq!(index.nb_indexed(), 2);
+        let res = index.search(&[1.1, 2.1, 3.1], 1, EPSILON)?;
+        assert_eq!(id1, res[0].id);
+        assert_eq!(vec![1.0, 2.0, 3.0], index.get_vec(id1)?);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_graph_only() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create a graph-only index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?.index_type(NgtIndexType::Graph)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        assert_eq!(index.index_type(), NgtIndexType::Graph);
+
+        // Insert two vectors, build and persist the index
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        index.insert(vec![4.0, 5.0, 6.0])?;
+        index.build(1)?;
+        index.persist()?;
+
+        // Verify that no VP-tree was created
+        #[cfg(not(feature = "shared_mem"))]
+        assert!(!dir.path().join("tre").exists());
+
+        // Verify that the index type is kept when reopening the index
+        let index = NgtIndex::<f32>::open(dir.path())?;
+        assert_eq!(index.index_type(), NgtIndexType::Graph);
+        let res = index.search(&[1.1, 2.1, 3.1], 1, EPSILON)?;
+        assert_eq!(id1, res[0].id);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_graph_type() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create a BKNNG index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?.graph_type(NgtGraphType::Bknng)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        assert_eq!(index.graph_type(), NgtGraphType::Bknng);
+
+        // Insert vectors, build and persist the index
+        let vecs = (0..20)
+            .map(|i| vec![i as f32, (i * i) as f32 / 10.0, (i % 7) as f32])
+            .collect::<Vec<_>>();
+        index.insert_batch(vecs)?;
+        index.build(1)?;
+        index.persist()?;
+
+        // Verify that the graph type is kept when reopening the index
+        let index = NgtIndex::<f32>::open(dir.path())?;
+        assert_eq!(index.graph_type(), NgtGraphType::Bknng);
+
+        // Verify that the built graph is bidirectional
+        for id in index.ids() {
+            for (target, _) in index.edges(id)? {
+                let reverse = index.edges(target)?;
+                assert!(reverse.iter().any(|&(source, _)| source == id));
+            }
+        }
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_linear_search() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index for vectors of dimension 3 searched linearly below 10 vectors
+        let prop = NgtProperties::<f32>::dimension(3)?.linear_search_threshold(10)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+
+        // Insert two vectors and search them without building the index
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        let id2 = index.insert(vec![4.0, 5.0, 6.0])?;
+        let res = index.search(&[1.1, 2.1, 3.1], 2, EPSILON)?;
+        assert_eq!(res.iter().map(|r| r.id).collect::<Vec<_>>(), vec![id1, id2]);
+        let query = vec![4.1, 5.1, 6.1];
+        let res = index.search_query(NgtQuery::new(&query).size(1))?;
+        assert_eq!(id2, res[0].id);
+
+        // Verify that the threshold is kept when reopening the index
+        index.persist()?;
+        drop(index);
+        let index = NgtIndex::<f32>::open(dir.path())?;
+        assert_eq!(index.prop.config.linear_search_threshold, 10);
+        assert_eq!(index.default_epsilon(), 0.1);
+        let res = index.search(&[1.1, 2.1, 3.1], 1, EPSILON)?;
+        assert_eq!(id1, res[0].id);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_build_incremental() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+
+        // Insert and build a first vector
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        index.build_incremental(1)?;
+        assert_eq!(index.nb_indexed(), 1);
+        let last_build = index.last_build();
+
+        // Building without new vectors does nothing
+        index.build_incremental(1)?;
+        assert_eq!(index.last_build(), last_build);
+
+        // Insert and build a second vector
+        let id2 = index.insert(vec![4.0, 5.0, 6.0])?;
+        index.build_incremental(1)?;
+        assert_eq!(index.nb_indexed(), 2);
+        assert!(index.unindexed_ids().is_empty());
+
+        let res = index.search(&[1.1, 2.1, 3.1], 1, EPSILON)?;
+        assert_eq!(id1, res[0].id);
+        let res = index.search(&[4.1, 5.1, 6.1], 1, EPSILON)?;
+        assert_eq!(id2, res[0].id);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_edges() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+
+        // Insert and build three vectors
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        let id2 = index.insert(vec![4.0, 5.0, 6.0])?;
+        let id3 = index.insert(vec![7.0, 8.0, 9.0])?;
+        index.build(1)?;
+
+        // Verify that the neighbors of the first vector are the two others
+        let mut edges = index.edges(id1)?;
+        edges.sort_by(|a, b| a.1.total_cmp(&b.1));
+        assert_eq!(
+            edges.iter().map(|e| e.0).collect::<Vec<_>>(),
+            vec![id2, id3]
+        );
+        assert!(edges[0].1 > 0.);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_search_queries() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+
+        // Insert and build three vectors
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        let id2 = index.insert(vec![4.0, 5.0, 6.0])?;
+        let id3 = index.insert(vec![7.0, 8.0, 9.0])?;
+        index.build(1)?;
+
+        // Search with heterogeneous queries on 2 threads
+        let (q1, q2, q3) = (
+            vec![1.1, 2.1, 3.1],
+            vec![4.1, 5.1, 6.1],
+            vec![7.1, 8.1, 9.1],
+        );
+        let queries = vec![
+            NgtQuery::new(&q1).size(1),
+            NgtQuery::new(&q2).size(2).epsilon(0.2),
+            NgtQuery::new(&q3).size(3),
+        ];
+        let res = index
+            .search_queries(&queries, 2)
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(res.len(), 3);
+        assert_eq!(res[0].iter().map(|r| r.id).collect::<Vec<_>>(), vec![id1]);
+        assert_eq!(res[1][0].id, id2);
+        assert_eq!(res[1].len(), 2);
+        assert_eq!(res[2][0].id, id3);
+        assert_eq!(res[2].len(), 3);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_verify() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+
+        // Insert and build two vectors
+        index.insert(vec![1.0, 2.0, 3.0])?;
+        index.insert(vec![4.0, 5.0, 6.0])?;
+        index.build(1)?;
+        assert!(index.verify()?.is_consistent());
+
+        // Inserted vectors are missing from the graph until repaired
+        let id3 = index.insert(vec![7.0, 8.0, 9.0])?;
+        let report = index.verify()?;
+        assert_eq!(report.missing_nodes, vec![id3]);
+        assert!(!report.is_consistent());
+        assert!(index.repair(1)?.is_consistent());
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "shared_mem"))]
+    fn test_ngt_in_memory() -> Result<()> {
+        // Create an in-memory index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create_in_memory(prop)?;
+        assert!(index.is_in_memory());
+
+        // Insert two vectors, build and search the index
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        index.insert(vec![4.0, 5.0, 6.0])?;
+        index.build(1)?;
+        let res = index.search(&[1.1, 2.1, 3.1], 1, EPSILON)?;
+        assert_eq!(id1, res[0].id);
+
+        // The index can't be persisted
+        assert!(index.persist().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_close() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+
+        // Insert a vector, build and persist the index
+        let id = index.insert(vec![1.0, 2.0, 3.0])?;
+        index.build(1)?;
+        index.persist()?;
+
+        // Close the index and reopen it
+        let closed = index.close()?;
+        assert_eq!(closed.path(), dir.path());
+        let index = closed.reopen()?;
+        let res = index.search(&[1.1, 2.1, 3.1], 1, EPSILON)?;
+        assert_eq!(id, res[0].id);
+
+        // Read-only indexes are reopened as read-only
+        drop(index);
+        let index = NgtIndex::<f32, ModeRead>::open_readonly(dir.path())?;
+        let index: NgtIndex<f32, ModeRead> = index.close()?.reopen()?;
+        assert_eq!(index.nb_inserted(), 1);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_search_filtered() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+
+        // Insert and build 100 vectors
+        let ids = (0..100)
+            .map(|i| index.insert(vec![i as f32; 3]))
+            .collect::<Result<Vec<_>>>()?;
+        index.build(1)?;
+
+        // Only keep the vectors with an even id
+        let res = index.search_filtered(&[0.0; 3], 10, EPSILON, |id| id % 2 == 0)?;
+        assert_eq!(res.len(), 10);
+        assert!(res.iter().all(|r| r.id % 2 == 0));
+        assert_eq!(res[0].id, ids[1]);
+
+        // A filter matching few vectors gives fewer results
+        let res = index.search_filtered(&[0.0; 3], 10, EPSILON, |id| id == ids[99])?;
+        assert_eq!(res.iter().map(|r| r.id).collect::<Vec<_>>(), vec![ids[99]]);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_soft_remove() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+
+        // Insert and build three vectors
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        let id2 = index.insert(vec![4.0, 5.0, 6.0])?;
+        let id3 = index.insert(vec![7.0, 8.0, 9.0])?;
+        index.build(1)?;
+
+        // Soft removed vectors are excluded from search results
+        index.soft_remove(id1)?;
+        let res = index.search(&[1.1, 2.1, 3.1], 2, EPSILON)?;
+        assert_eq!(res.iter().map(|r| r.id).collect::<Vec<_>>(), vec![id2, id3]);
+        let query = vec![1.1, 2.1, 3.1];
+        let res = index.search_query(NgtQuery::new(&query).size(1))?;
+        assert_eq!(id2, res[0].id);
+        let res = index.range_search(&[1.1, 2.1, 3.1], 100., EPSILON)?;
+        assert_eq!(res.len(), 2);
+        assert_eq!(
+            res.map(|r| r.map(|r| r.id)).collect::<Result<Vec<_>>>()?,
+            vec![id2, id3]
+        );
+
+        // Tombstones are persisted
+        index.persist()?;
+        let mut index = NgtIndex::<f32>::open(dir.path())?;
+        let res = index.search(&[1.1, 2.1, 3.1], 1, EPSILON)?;
+        assert_eq!(id2, res[0].id);
+
+        // Compaction physically removes the vectors
+        assert_eq!(index.compact()?, 1);
+        assert!(index.get_vec(id1).is_err());
+        assert_eq!(index.nb_inserted(), 2);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_reuse_removed_ids() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index that doesn't reuse removed ids
+        let prop = NgtProperties::<f32>::dimension(3)?.reuse_removed_ids(false)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+
+        // Removed ids aren't assigned again
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        index.remove(id1)?;
+        let id2 = index.insert(vec![4.0, 5.0, 6.0])?;
+        assert_ne!(id1, id2);
+        assert!(!index.was_recycled(id2));
+        index.build(1)?;
+        let res = index.search(&[1.1, 2.1, 3.1], 1, EPSILON)?;
+        assert_eq!(id2, res[0].id);
+        assert!(index.compact().is_err());
+
+        // Expired vectors can't be compacted in place, and are kept then
+        let id3 = index.insert_with_ttl(vec![7.0, 8.0, 9.0], Duration::ZERO)?;
+        index.build(1)?;
+        assert!(index.expire_now(true).is_err());
+        assert!(index.expires_at(id3).is_some());
+
+        // They are reclaimed by compacting into a new index
+        let dst = tempdir()?;
+        let (compacted, ids) = index.compact_to(dst.path().join("index"), 1)?;
+        assert_eq!(ids.len(), 2);
+        assert_eq!(compacted.nb_inserted(), 2);
+        let (_, new_id3) = ids.iter().find(|&&(src, _)| src == id3).unwrap();
+        assert_eq!(compacted.expires_at(*new_id3), index.expires_at(id3));
+
+        dst.close()?;
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_expiration() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index with expiring vectors
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let id1 = index.insert_with_ttl(vec![1.0, 2.0, 3.0], Duration::ZERO)?;
+        let id2 = index.insert_with_ttl(vec![4.0, 5.0, 6.0], Duration::from_secs(3600))?;
+        let id3 = index.insert(vec![7.0, 8.0, 9.0])?;
+        assert!(index.insert_with_ttl(vec![0.0; 3], Duration::MAX).is_err());
+        index.build(1)?;
+        assert_eq!(index.nb_inserted(), 3);
+        assert!(index.expires_at(id2).is_some());
+        assert!(index.expires_at(id3).is_none());
+
+        // Only the expired vector is removed
+        assert_eq!(index.expire_now(true)?, vec![id1]);
+        assert!(index.get_vec(id1).is_err());
+        assert!(index.expires_at(id1).is_none());
+        let res = index.search(&[1.1, 2.1, 3.1], 3, EPSILON)?;
+        assert_eq!(res.iter().map(|r| r.id).collect::<Vec<_>>(), vec![id2, id3]);
+
+        // Expirations are persisted, even those too far away for u64 nanoseconds
+        let far = Duration::from_secs(1000 * 365 * 24 * 3600);
+        let id4 = index.insert_with_ttl(vec![0.0; 3], far)?;
+        let (deadline2, deadline4) = (index.expires_at(id2), index.expires_at(id4));
+        index.persist()?;
+        let index = NgtIndex::<f32>::open(dir.path())?;
+        assert_eq!(index.expires_at(id2), deadline2);
+        assert_eq!(index.expires_at(id4), deadline4);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_snapshot() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index and its snapshot
+        let dir = tempdir()?;
+        let index_dir = dir.path().join("index");
+        let snapshot_dir = dir.path().join("snapshot");
+
+        // Create an index and snapshot it
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(&index_dir, prop)?;
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        index.build(1)?;
+        let snapshot = index.snapshot_to(&snapshot_dir)?;
+        assert!(index.snapshot_to(&snapshot_dir).is_err());
+
+        // The snapshot isn't affected by later changes
+        let id2 = index.insert(vec![4.0, 5.0, 6.0])?;
+        index.build(1)?;
+        let snapshot = snapshot.reopen()?;
+        assert_eq!(snapshot.path(), snapshot_dir);
+        assert!(snapshot.get_vec(id1).is_ok());
+        assert!(snapshot.get_vec(id2).is_err());
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "shared_mem"))]
+    fn test_ngt_persist_atomic() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        let index_dir = dir.path().join("index");
+
+        // Create an index with a file it doesn't manage
+        let prop = NgtProperties::<f32>::dimension(3)?.record_timestamps(true)?;
+        let mut index = NgtIndex::create(&index_dir, prop)?;
+        std::fs::write(index_dir.join("extra"), "extra")?;
+        std::fs::create_dir(dir.path().join("index.old"))?;
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        index.build(1)?;
+        index.persist_atomic()?;
+
+        // The index is persisted, the directories it didn't create are left alone and
+        // no temporary directory is left behind
+        let index = NgtIndex::<f32>::open(&index_dir)?;
+        assert_eq!(index.get_vec(id1)?, vec![1.0, 2.0, 3.0]);
+        assert!(index.inserted_at(id1).is_some());
+        assert_eq!(std::fs::read_to_string(index_dir.join("extra"))?, "extra");
+        assert!(dir.path().join("index.old").exists());
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 2);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_export_import() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the indexes
+        let dir = tempdir()?;
+
+        // Create an index and export it
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path().join("src"), prop)?;
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        index.build(1)?;
+        let mut archive = Vec::new();
+        index.export(&mut archive)?;
+
+        // Import it elsewhere
+        let index = NgtIndex::<f32>::import(archive.as_slice(), dir.path().join("dst"))?;
+        assert_eq!(index.get_vec(id1)?, vec![1.0, 2.0, 3.0]);
+        let res = index.search(&[1.1, 2.1, 3.1], 1, EPSILON)?;
+        assert_eq!(res[0].id, id1);
+
+        // Invalid archives leave nothing behind
+        assert!(NgtIndex::<f32>::import(&archive[..16], dir.path().join("bad")).is_err());
+        assert!(!dir.path().join("bad").exists());
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_clone_to() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index and its clone
+        let dir = tempdir()?;
+
+        // Create an index and clone it
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path().join("index"), prop)?;
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        index.build(1)?;
+        let mut clone = index.clone_to(dir.path().join("clone"))?;
+
+        // Both indexes are independent
+        let id2 = clone.insert(vec![4.0, 5.0, 6.0])?;
+        clone.build(1)?;
+        assert_eq!(clone.get_vec(id1)?, vec![1.0, 2.0, 3.0]);
+        assert!(index.get_vec(id2).is_err());
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_rebuild_with() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the indexes
+        let dir = tempdir()?;
+
+        // Create an index of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path().join("src"), prop)?;
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        let id2 = index.insert(vec![4.0, 5.0, 6.0])?;
+        let id3 = index.insert(vec![7.0, 8.0, 9.0])?;
+        index.build(1)?;
+
+        // Rebuild it with dimension 2, skipping a vector
+        let prop = NgtProperties::<f32>::dimension(2)?;
+        let (rebuilt, ids) = index.rebuild_with(dir.path().join("dst"), prop, 1, |id, vec| {
+            (id != id2).then(|| vec[..2].to_vec())
+        })?;
+        assert_eq!(
+            ids.iter().map(|&(s, _)| s).collect::<Vec<_>>(),
+            vec![id1, id3]
+        );
+        assert_eq!(rebuilt.get_vec(ids[1].1)?, vec![7.0, 8.0]);
+        assert_eq!(migration::read_id_map(dir.path().join("dst"))?, ids);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_incompatible() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index of f32 vectors
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        index.persist()?;
+        drop(index);
+
+        // It can't be opened with u8 vectors
+        let err = NgtIndex::<u8>::open(dir.path()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IncompatibleIndex);
+        assert!(NgtIndex::<f32>::open(dir.path()).is_ok());
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_validate() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Incoherent properties are rejected, either by NGT or before creating the index
+        let prop = NgtProperties::<f32>::dimension(0);
+        assert!(prop.and_then(|p| p.validate()).is_err());
+        let prop = NgtProperties::<f32>::dimension(3)?.creation_edge_size(0);
+        assert!(prop.and_then(|p| p.validate()).is_err());
+        let prop = NgtProperties::<f32>::dimension(3)?.default_epsilon(f32::NAN)?;
+        assert!(prop.validate().is_err());
+        let prop = NgtProperties::<f32>::dimension(3)?.distance_type(NgtDistance::Hamming)?;
+        assert!(NgtIndex::create(dir.path(), prop).is_err());
+        assert!(!dir.path().join("grp").exists());
+
+        // Search edges can be fewer than creation edges
+        let prop = NgtProperties::<f32>::dimension(3)?.creation_edge_size(100)?;
+        assert!(prop.validate().is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_tuning() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Out of range values are rejected
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        assert!(prop.try_clone()?.seed_size(0).is_err());
+        assert!(prop.try_clone()?.batch_size_for_creation(0).is_err());
+        assert!(prop.try_clone()?.insertion_radius_coefficient(0.5).is_err());
+        assert!(prop.try_clone()?.dynamic_edge_size_base(1 << 16).is_err());
+
+        // Create an index with tuned properties
+        let prop = NgtProperties::<f32>::dimension(3)?
+            .batch_size_for_creation(50)?
+            .truncation_threshold(20)?
+            .insertion_radius_coefficient(1.2)?
+            .path_adjustment_interval(10)?
+            .dynamic_edge_size_base(25)?
+            .dynamic_edge_size_rate(15)?
+            .seed_size(5)?
+            .seed_type(NgtSeedType::FixedNodes)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        index.insert(vec![4.0, 5.0, 6.0])?;
+        index.build(1)?;
+        index.persist()?;
+        drop(index);
+
+        // The tuned properties are kept when reopening the index
+        let index = NgtIndex::<f32>::open(dir.path())?;
+        let tuning = index.properties().tuning;
+        assert_eq!(tuning.batch_size_for_creation, Some(50));
+        assert_eq!(tuning.insertion_radius_coefficient, Some(1.2));
+        assert_eq!(tuning.seed_type, Some(NgtSeedType::FixedNodes));
+        let res = index.search(&[1.1, 2.1, 3.1], 1, EPSILON)?;
+        assert_eq!(res[0].id, id1);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_query_defaults() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index with custom search parameters
+        let prop = NgtProperties::<f32>::dimension(3)?
+            .search_edge_size(60)?
+            .default_epsilon(0.2)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        index.build(1)?;
+
+        // Queries get the parameters of the index
+        let vec = [1.1, 2.1, 3.1];
+        let query = index.query(&vec);
+        assert_eq!(query.epsilon, 0.2);
+        assert_eq!(NgtQuery::new(&vec).epsilon, 0.1);
+        assert_eq!(query.edge_size, 60);
+        let res = index.search_query(query.size(1))?;
+        assert_eq!(res[0].id, id1);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_ngt_query_serde() -> StdResult<(), Box<dyn StdError>> {
+        // Queries round trip, owning their vector once deserialized
+        let vec = vec![1.0, 2.0, 3.0];
+        let query = NgtQuery::<f32>::new(&vec).size(5).epsilon(0.2);
+        let json = serde_json::to_string(&query)?;
+        assert_eq!(serde_json::from_str::<NgtQuery<f32>>(&json)?, query);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_query_conversions() -> Result<()> {
+        let query = NgtQuery::<f32>::from_f64(&[1.0, 2.5, -3.0])?;
+        assert_eq!(&query.query[..], &[1.0, 2.5, -3.0]);
+        assert!(NgtQuery::<f32>::from_f64(&[f64::MAX]).is_err());
+        assert!(NgtQuery::<f32>::from_i32(&[16_777_217]).is_err());
+
+        let query = NgtQuery::<u8>::from_i32(&[0, 255])?;
+        assert_eq!(&query.query[..], &[0, 255]);
+        assert!(NgtQuery::<u8>::from_f64(&[1.5]).is_err());
+        assert!(NgtQuery::<u8>::from_i32(&[-1]).is_err());
+
+        assert!(NgtQuery::<f16>::from_i32(&[2048]).is_ok());
+        assert!(NgtQuery::<f16>::from_i32(&[2049]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_search_iter() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index with a soft removed vector
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        let id2 = index.insert(vec![4.0, 5.0, 6.0])?;
+        let id3 = index.insert(vec![7.0, 8.0, 9.0])?;
+        index.build(1)?;
+        index.soft_remove(id1)?;
+
+        // Results are the same as a regular search
+        let vec = [1.1, 2.1, 3.1];
+        let mut res = index.search_iter(NgtQuery::new(&vec).size(3))?;
+        assert_eq!(res.len(), 2);
+        assert_eq!(res.next().transpose()?.map(|r| r.id), Some(id2));
+        assert_eq!(res.len(), 1);
+        assert_eq!(res.next().transpose()?.map(|r| r.id), Some(id3));
+        assert!(res.next().is_none());
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_search_multi() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index with vectors along a line
+        let prop = NgtProperties::<f32>::dimension(1)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let id0 = index.insert(vec![0.0])?;
+        let id4 = index.insert(vec![4.0])?;
+        let id5 = index.insert(vec![5.0])?;
+        let id10 = index.insert(vec![10.0])?;
+        index.build(1)?;
+        let queries: [&[f32]; 2] = [&[0.0], &[10.0]];
+
+        // The vectors closest to any query come first
+        let res = index.search_multi(&queries, 2, EPSILON, Fusion::MinDistance)?;
+        assert_eq!(
+            res.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![id0, id10]
+        );
+
+        // The vectors closest to all the queries come first
+        let res = index.search_multi(&queries, 2, EPSILON, Fusion::Mean)?;
+        assert_eq!(res[0].distance, 5.0);
+        assert!(res.iter().all(|r| [id0, id4, id5, id10].contains(&r.id)));
+
+        // The vectors ranked first by the queries come first
+        let res = index.search_multi(&queries, 4, EPSILON, Fusion::ReciprocalRank)?;
+        assert_eq!(res.len(), 4);
+        assert!([id0, id10].contains(&res[0].id));
+        assert!(res[0].distance < 0.);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_insert_batch_flat() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Insert two rows from a flat buffer
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        index.insert_batch_flat(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2)?;
+        index.build(1)?;
+        assert_eq!(index.nb_inserted(), 2);
+        let res = index.search(&[4.1, 5.1, 6.1], 1, 0.1)?;
+        assert_eq!(index.get_vec(res[0].id)?, vec![4.0, 5.0, 6.0]);
+
+        // The buffer length must match the rows
+        assert!(index.insert_batch_flat(&[1.0, 2.0, 3.0, 4.0], 1).is_err());
+        assert!(index.insert_batch_flat(&[1.0, 2.0, 3.0], 2).is_err());
+        index.insert_batch_flat(&[], 0)?;
+        assert_eq!(index.nb_inserted(), 2);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_search_into() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index with two vectors
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        let id2 = index.insert(vec![4.0, 5.0, 6.0])?;
+        index.build(1)?;
+
+        // The buffer is cleared and filled by each search
+        let mut out = Vec::new();
+        index.search_into(NgtQuery::new(&[1.1, 2.1, 3.1]).size(2), &mut out)?;
+        assert_eq!(
+            out,
+            index.search(&[1.1, 2.1, 3.1], 2, index.default_epsilon())?
+        );
+        index.search_into(NgtQuery::new(&[4.1, 5.1, 6.1]).size(1), &mut out)?;
+        assert_eq!(out.iter().map(|r| r.id).collect::<Vec<_>>(), vec![id2]);
+
+        // Soft removed vectors are filtered out
+        index.soft_remove(id2)?;
+        index.search_into(NgtQuery::new(&[4.1, 5.1, 6.1]).size(1), &mut out)?;
+        assert_eq!(out.iter().map(|r| r.id).collect::<Vec<_>>(), vec![id1]);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_export_graph() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index with a few vectors
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        for i in 0..5 {
+            index.insert(vec![i as f32; 3])?;
         }
-    }
-}
+        index.build(1)?;
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct NgtQuery<'a, T> {
-    query: &'a [T],
-    pub size: usize,
-    pub epsilon: f32,
-    pub edge_size: usize,
-    pub radius: f32,
-}
+        // The CSV export holds every edge of the graph
+        let mut csv = Vec::new();
+        index.export_graph(&mut csv, GraphFormat::Csv)?;
+        let nb_edges = index
+            .ids()
+            .map(|id| index.edges(id).unwrap().len())
+            .sum::<usize>();
+        assert_eq!(String::from_utf8(csv)?.lines().count(), 1 + nb_edges);
 
-impl<'a, T> NgtQuery<'a, T>
-where
-    T: NgtObjectType,
-{
-    pub fn new(query: &'a [T]) -> Self {
-        Self {
-            query,
-            size: 10,
-            epsilon: crate::EPSILON,
-            edge_size: usize::MIN,
-            radius: -1.,
-        }
-    }
+        // The binary export holds every node and edge of the graph
+        let mut binary = Vec::new();
+        index.export_graph(&mut binary, GraphFormat::Binary)?;
+        assert_eq!(binary.len(), 5 * 8 + nb_edges * 8);
 
-    pub fn size(mut self, size: usize) -> Self {
-        self.size = size;
-        self
+        dir.close()?;
+        Ok(())
     }
 
-    pub fn epsilon(mut self, epsilon: f32) -> Self {
-        self.epsilon = epsilon;
-        self
-    }
+    #[test]
+    fn test_ngt_capacity() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
 
-    pub fn edge_size(mut self, edge_size: usize) -> Self {
-        self.edge_size = edge_size;
-        self
-    }
+        // Create an index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        index.insert(vec![1.0, 2.0, 3.0])?;
 
-    pub fn radius(mut self, radius: f32) -> Self {
-        self.radius = radius;
-        self
+        // Verify that id overflows are detected
+        assert!(index.check_capacity(1).is_ok());
+        let err = index.check_capacity(VecId::MAX).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CapacityExceeded);
+
+        dir.close()?;
+        Ok(())
     }
 
-    unsafe fn params(&self) -> sys::NGTQueryParameters {
-        sys::NGTQueryParameters {
-            size: self.size,
-            epsilon: self.epsilon,
-            edge_size: self.edge_size,
-            radius: self.radius,
+    #[test]
+    fn test_ngt_batch() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::error::Error as StdError;
-    use std::iter;
-    use std::result::Result as StdResult;
+        // Create an index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
 
-    use half::f16;
-    use rayon::prelude::*;
-    use tempfile::tempdir;
+        // Batch insert 2 vectors, build and persist the index
+        index.insert_batch(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]])?;
+        index.build(2)?;
+        index.persist()?;
 
-    use super::*;
-    use crate::ngt::NgtDistance;
-    use crate::EPSILON;
+        // Verify that the index was built correctly with a vector search
+        let res = index.search(&vec![1.1, 2.1, 3.1], 1, EPSILON)?;
+        assert_eq!(1, res[0].id);
+
+        dir.close()?;
+        Ok(())
+    }
 
     #[test]
-    fn test_ngt_f32_basics() -> StdResult<(), Box<dyn StdError>> {
+    fn test_ngt_search_with_vectors() -> StdResult<(), Box<dyn StdError>> {
         // Get a temporary directory to store the index
         let dir = tempdir()?;
         if cfg!(feature = "shared_mem") {
@@ -521,59 +3132,81 @@ mod tests {
         let prop = NgtProperties::<f32>::dimension(3)?;
         let mut index = NgtIndex::create(dir.path(), prop)?;
 
-        // Insert two vectors and get their id
-        let vec1 = vec![1.0, 2.0, 3.0];
-        let vec2 = vec![4.0, 5.0, 6.0];
-        let id1 = index.insert(vec1.clone())?;
-        let id2 = index.insert(vec2.clone())?;
-        assert!(index.nb_inserted() == 2);
-        assert!(index.nb_indexed() == 0);
-
-        // Actually build the index (not yet persisted on disk)
-        // This is required in order to be able to search vectors
-        index.build(2)?;
-        assert!(index.nb_inserted() == 2);
-        assert!(index.nb_indexed() == 2);
+        // Insert two vectors and build the index
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        index.insert(vec![4.0, 5.0, 6.0])?;
+        index.build(1)?;
 
-        // Perform a vector search (using the NgtQuery API)
+        // Verify that the stored vector comes along with the result
         let query = vec![1.1, 2.1, 3.1];
-        let res = index.search_query(NgtQuery::new(&query))?;
-        assert_eq!(id1, res[0].id);
-        assert_eq!(vec1, index.get_vec(id1)?);
+        let res = index.search_query(NgtQuery::new(&query).size(1).with_vectors())?;
+        assert_eq!(res[0].id, id1);
+        assert_eq!(res[0].vec, vec![1.0, 2.0, 3.0]);
 
-        // Remove a vector and check that it is not present anymore
-        index.remove(id1)?;
-        let res = index.get_vec(id1);
-        assert!(res.is_err());
-        assert!(index.nb_inserted() == 1);
-        assert!(index.nb_indexed() == 1);
+        dir.close()?;
+        Ok(())
+    }
 
-        // Verify that now our search result is different
-        let res = index.search(&vec![1.1, 2.1, 3.1], 1, EPSILON)?;
-        assert_eq!(id2, res[0].id);
-        assert_eq!(vec2, index.get_vec(id2)?);
+    #[test]
+    fn test_ngt_timestamps() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
 
-        // Persist index on disk, and open it again
-        index.persist()?;
-        index = NgtIndex::open(dir.path())?;
-        assert!(index.nb_inserted() == 1);
-        assert!(index.nb_indexed() == 1);
+        // Indexes don't record the insertion times by default
+        let default_dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(default_dir.path())?;
+        }
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(default_dir.path(), prop)?;
+        let id = index.insert(vec![1.0, 2.0, 3.0])?;
+        assert_eq!(index.inserted_at(id), None);
+        assert!(index.unindexed_ids().is_empty());
 
-        // Check that the removed vector wasn't persisted
-        let res = index.get_vec(id1);
-        assert!(res.is_err());
+        // Create an index recording them
+        let prop = NgtProperties::<f32>::dimension(3)?.record_timestamps(true)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
 
-        // Verify that out search result is still consistent
-        let res = index.search(&vec![1.1, 2.1, 3.1], 1, EPSILON)?;
-        assert_eq!(id2, res[0].id);
-        assert_eq!(vec2, index.get_vec(id2)?);
+        // Insert and build a first batch
+        let start = SystemTime::now();
+        index.insert_batch(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]])?;
+        assert_eq!(index.unindexed_ids(), vec![1, 2]);
+        index.build(1)?;
+        assert!(index.unindexed_ids().is_empty());
+
+        // Insert another vector after the build
+        let built = index.last_build().unwrap();
+        let id3 = index.insert(vec![7.0, 8.0, 9.0])?;
+        assert_eq!(index.unindexed_ids(), vec![id3]);
+        assert_eq!(index.ids_inserted_after(start), vec![1, 2, id3]);
+        assert_eq!(index.ids_inserted_after(built), vec![id3]);
+
+        // Verify that timestamps are persisted
+        index.persist()?;
+        let mut index = NgtIndex::<f32>::open(dir.path())?;
+        assert_eq!(index.last_build(), Some(built));
+        assert_eq!(index.unindexed_ids(), vec![id3]);
+
+        // Verify that removed ids assigned again are known to be recycled
+        assert!(!index.was_recycled(id3));
+        index.remove(id3)?;
+        let id4 = index.insert(vec![7.0, 8.0, 9.0])?;
+        assert_eq!(id4, id3);
+        assert!(index.was_recycled(id4));
+        index.persist()?;
+        let index = NgtIndex::<f32>::open(dir.path())?;
+        assert!(index.was_recycled(id4));
 
+        default_dir.close()?;
         dir.close()?;
         Ok(())
     }
 
     #[test]
-    fn test_ngt_batch() -> StdResult<(), Box<dyn StdError>> {
+    fn test_ngt_range_search() -> StdResult<(), Box<dyn StdError>> {
         // Get a temporary directory to store the index
         let dir = tempdir()?;
         if cfg!(feature = "shared_mem") {
@@ -584,14 +3217,63 @@ mod tests {
         let prop = NgtProperties::<f32>::dimension(3)?;
         let mut index = NgtIndex::create(dir.path(), prop)?;
 
-        // Batch insert 2 vectors, build and persist the index
-        index.insert_batch(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]])?;
-        index.build(2)?;
-        index.persist()?;
+        // Insert 3 vectors and build the index
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        let id2 = index.insert(vec![4.0, 5.0, 6.0])?;
+        index.insert(vec![7.0, 8.0, 9.0])?;
+        index.build(1)?;
 
-        // Verify that the index was built correctly with a vector search
-        let res = index.search(&vec![1.1, 2.1, 3.1], 1, EPSILON)?;
-        assert_eq!(1, res[0].id);
+        // Verify that only the vectors within the radius are returned
+        let res = index
+            .range_search(&[1.0, 2.0, 3.0], 6.0, EPSILON)?
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(res.iter().map(|r| r.id).collect::<Vec<_>>(), vec![id1, id2]);
+
+        // Verify that every vector within the radius is returned
+        for i in 0..100 {
+            index.insert(vec![i as f32 / 100.; 3])?;
+        }
+        index.build(1)?;
+        let res = index.range_search(&[0.5; 3], 100.0, EPSILON)?;
+        assert_eq!(res.len(), 103);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ngt_iter() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+
+        // Insert 3 vectors and remove the second one
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        let id2 = index.insert(vec![4.0, 5.0, 6.0])?;
+        let id3 = index.insert(vec![7.0, 8.0, 9.0])?;
+        index.build(1)?;
+        index.remove(id2)?;
+
+        // Verify that only the remaining vectors are iterated over
+        assert_eq!(index.ids().collect::<Vec<_>>(), vec![id1, id3]);
+        assert_eq!(
+            index.iter().collect::<Result<Vec<_>>>()?,
+            vec![(id1, vec![1.0, 2.0, 3.0]), (id3, vec![7.0, 8.0, 9.0])]
+        );
+
+        // Verify that soft removed vectors are skipped as well
+        index.soft_remove(id3)?;
+        assert_eq!(index.ids().collect::<Vec<_>>(), vec![id1]);
+        assert_eq!(
+            index.iter().collect::<Result<Vec<_>>>()?,
+            vec![(id1, vec![1.0, 2.0, 3.0])]
+        );
 
         dir.close()?;
         Ok(())
@@ -733,6 +3415,98 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_dist_conversions() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create a normalized cosine index with two orthogonal vectors
+        let prop =
+            NgtProperties::<f32>::dimension(2)?.distance_type(NgtDistance::NormalizedCosine)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        index.insert(vec![1.0, 0.0])?;
+        index.insert(vec![0.0, 2.0])?;
+        index.build(1)?;
+
+        let dist = index.distance_type();
+        assert_eq!(dist, NgtDistance::NormalizedCosine);
+        let res = index.search(&[3.0, 0.0], 2, EPSILON)?;
+        assert!((dist.to_cosine_similarity(res[0].distance).unwrap() - 1.0).abs() < 1e-6);
+        assert!(dist.to_l2(res[0].distance).unwrap().abs() < 1e-3);
+        assert!(dist.to_cosine_similarity(res[1].distance).unwrap().abs() < 1e-6);
+        assert!((dist.to_l2(res[1].distance).unwrap() - 2f32.sqrt()).abs() < 1e-6);
+
+        assert_eq!(NgtDistance::L2.to_l2(1.5), Some(1.5));
+        assert_eq!(NgtDistance::L2.to_cosine_similarity(1.5), None);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_distance_between() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let id1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        let id2 = index.insert(vec![4.0, 6.0, 3.0])?;
+        index.build(1)?;
+
+        // Verify that the distance matches the one computed by NGT
+        assert_eq!(index.distance_between(id1, id2)?, 5.0);
+        let res = index.search(&[1.0, 2.0, 3.0], 2, EPSILON)?;
+        assert!((index.distance_between(id1, id2)? - res[1].distance).abs() < 1e-6);
+
+        // Verify that the distance to a query matches the one computed by NGT
+        let query = [1.1, 2.1, 3.1];
+        let res = index.search(&query, 2, EPSILON)?;
+        for r in res {
+            assert!((index.distance_to(&query, r.id)? - r.distance).abs() < 1e-5);
+        }
+        assert!(index.distance_to(&[1.0], id1).is_err());
+
+        dir.close()?;
+        Ok(())
+    }
+
+    fn test_dist_u8(dist: NgtDistance) -> Result<()> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create a new index
+        let prop = NgtProperties::<u8>::dimension(3)?.distance_type(dist)?;
+        let mut index = NgtIndex::create(&dir.path(), prop)?;
+
+        // Insert two vectors and get their id
+        let id1 = index.insert(vec![1, 2, 3])?;
+        let _id2 = index.insert(vec![252, 253, 254])?;
+
+        // Build index
+        index.build(1)?;
+        index.persist()?;
+
+        // Perform a vector search (with 1 result)
+        let res = index.search(&[1, 2, 3], 1, EPSILON)?;
+        assert_eq!(res[0].id, id1);
+
+        // Checks that vector is removable from the index
+        index.remove(id1)?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_dist_l1() -> Result<()> {
         test_dist(NgtDistance::L1)
@@ -750,7 +3524,8 @@ mod tests {
 
     #[test]
     fn test_dist_hamming() -> Result<()> {
-        test_dist(NgtDistance::Hamming)
+        assert!(test_dist(NgtDistance::Hamming).is_err());
+        test_dist_u8(NgtDistance::Hamming)
     }
 
     #[test]
@@ -770,7 +3545,8 @@ mod tests {
 
     #[test]
     fn test_dist_jaccard() -> Result<()> {
-        test_dist(NgtDistance::Jaccard)
+        assert!(test_dist(NgtDistance::Jaccard).is_err());
+        test_dist_u8(NgtDistance::Jaccard)
     }
 
     #[test]
@@ -778,6 +3554,31 @@ mod tests {
         test_dist(NgtDistance::NormalizedL2)
     }
 
+    #[test]
+    fn test_dist_inner_product() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index with inner product distance
+        let prop = NgtProperties::<f32>::dimension(3)?.distance_type(NgtDistance::InnerProduct)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let id1 = index.insert(vec![1.0, 1.0, 1.0])?;
+        let id2 = index.insert(vec![10.0, 10.0, 10.0])?;
+        index.build(1)?;
+        assert_eq!(index.distance_type(), NgtDistance::InnerProduct);
+
+        // The vector with the largest inner product comes first, not the closest one
+        let res = index.search(&[1.0, 1.0, 1.0], 2, EPSILON)?;
+        assert_eq!(res[0].id, id2);
+        assert_eq!(res[1].id, id1);
+
+        dir.close()?;
+        Ok(())
+    }
+
     #[test]
     fn test_dist_poincare() -> Result<()> {
         test_dist(NgtDistance::Poincare)
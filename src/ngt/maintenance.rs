@@ -0,0 +1,310 @@
+//! Functions aimed at maintaining the directory of an [`NgtIndex`](crate::NgtIndex)
+
+use std::ffi::{CString, OsStr};
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use tempfile::TempDir;
+
+use crate::error::Result;
+
+/// Suffixes of the temporary and backup files left behind by interrupted writes or
+/// manual operations.
+const JUNK_SUFFIXES: &[&str] = &[".tmp", ".bak", ".old", "~"];
+
+/// Files of the NGT index that the quantized index is built from.
+const NGT_FILES: &[&str] = &["grp", "obj", "tre", "prf"];
+
+/// Directory of the quantized index, see [`QgIndex`](crate::qg::QgIndex).
+pub(crate) const QG_DIR: &str = "qg";
+
+/// Infix of the staging directories created next to an index directory, between its
+/// name and the operation, see [`staging_dir`][].
+const STAGING_INFIX: &str = ".staging.";
+
+/// Removes the junk accumulated in the index directory at `path`, and returns the
+/// removed paths. When `dry_run` is set nothing is removed, the paths that would be
+/// removed are only listed.
+///
+/// The following is considered junk:
+///   - Temporary and backup files (ending with `.tmp`, `.bak`, `.old` or `~`).
+///   - Staging directories left next to the index directory by interrupted
+///     operations, such as [`persist_atomic`](crate::NgtIndex::persist_atomic) or
+///     [`shrink`](crate::optim::shrink).
+///   - Only when `remove_stale_qg` is set, a stale quantized index, see
+///     [`is_stale_qg`][]. Its modification times are only a hint, so it is kept
+///     by default.
+///
+/// **The index must not be opened while its directory is garbage collected**.
+pub fn gc<P: AsRef<Path>>(path: P, dry_run: bool, remove_stale_qg: bool) -> Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    if !path.is_dir() {
+        Err(format!("Path {path:?} is not a directory"))?
+    }
+
+    let mut junk = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if entry.file_type()?.is_file() && JUNK_SUFFIXES.iter().any(|s| name.ends_with(s)) {
+            junk.push(entry.path());
+        }
+    }
+    if let Some(name) = path.file_name() {
+        let prefix = format!(".{}{STAGING_INFIX}", name.to_string_lossy());
+        for entry in fs::read_dir(parent_dir(path))? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if entry.file_type()?.is_dir() && name.to_string_lossy().starts_with(&prefix) {
+                junk.push(entry.path());
+            }
+        }
+    }
+    if remove_stale_qg && is_stale_qg(path)? {
+        junk.push(path.join(QG_DIR));
+    }
+    junk.sort();
+
+    if !dry_run {
+        for path in &junk {
+            if path.is_dir() {
+                fs::remove_dir_all(path)?;
+            } else {
+                fs::remove_file(path)?;
+            }
+        }
+    }
+
+    Ok(junk)
+}
+
+/// Recursively copies the directory `src` to `dst`, which must not exist, and syncs
+/// the copied files to the disk.
+pub(crate) fn copy_dir(src: &Path, dst: &Path) -> Result<()> {
+    fs::create_dir(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+            fs::File::open(&target)?.sync_all()?;
+        }
+    }
+    fs::File::open(dst)?.sync_all()?;
+    Ok(())
+}
+
+/// Atomically exchanges the directories `a` and `b`, which must both exist, and syncs
+/// their parent directories to the disk.
+#[cfg_attr(
+    all(feature = "shared_mem", not(feature = "quantized")),
+    allow(dead_code)
+)]
+pub(crate) fn exchange_dirs(a: &Path, b: &Path) -> Result<()> {
+    let (c_a, c_b) = (
+        CString::new(a.as_os_str().as_bytes())?,
+        CString::new(b.as_os_str().as_bytes())?,
+    );
+
+    #[cfg(target_os = "linux")]
+    let res = unsafe {
+        libc::renameat2(
+            libc::AT_FDCWD,
+            c_a.as_ptr(),
+            libc::AT_FDCWD,
+            c_b.as_ptr(),
+            libc::RENAME_EXCHANGE,
+        )
+    };
+    #[cfg(target_os = "macos")]
+    let res = unsafe { libc::renamex_np(c_a.as_ptr(), c_b.as_ptr(), libc::RENAME_SWAP) };
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    let res: i32 = {
+        let _ = (c_a, c_b);
+        return Err(
+            "Atomic directory exchange is not supported on this platform"
+                .to_string()
+                .into(),
+        );
+    };
+    if res != 0 {
+        Err(std::io::Error::last_os_error())?
+    }
+
+    for dir in [a, b] {
+        fs::File::open(parent_dir(dir))?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// The directory holding `path`, the current directory for a bare file name.
+pub(crate) fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Creates a fresh staging directory named after `path` and `operation` next to
+/// `path`, and returns it along with the path where to prepare the new version of the
+/// directory at `path`, which doesn't exist yet. The staging directory is removed
+/// when dropped, or by [`gc`][] if the process was interrupted.
+pub(crate) fn staging_dir(path: &Path, operation: &str) -> Result<(TempDir, PathBuf)> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let staging = tempfile::Builder::new()
+        .prefix(&format!(".{name}{STAGING_INFIX}{operation}."))
+        .tempdir_in(parent_dir(path))?;
+    let staged = staging.path().join("index");
+    Ok((staging, staged))
+}
+
+/// Syncs the directory `staged` prepared in a [`staging_dir`][] to the disk and
+/// atomically exchanges it with the directory at `path`, whose previous version then
+/// ends up in the staging directory.
+///
+/// The entries of `path` missing from `staged` are copied beforehand when `keep`
+/// accepts their name, so that the files that aren't managed by the index, such as a
+/// quantized index, are kept.
+#[cfg_attr(
+    all(feature = "shared_mem", not(feature = "quantized")),
+    allow(dead_code)
+)]
+pub(crate) fn swap_in<F>(staged: &Path, path: &Path, keep: F) -> Result<()>
+where
+    F: Fn(&OsStr) -> bool,
+{
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let target = staged.join(entry.file_name());
+        if target.exists() || !keep(&entry.file_name()) {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    sync_dir(staged)?;
+    exchange_dirs(staged, path)
+}
+
+/// Recursively syncs the files of the directory `dir` and the directory itself to the
+/// disk.
+#[cfg_attr(
+    all(feature = "shared_mem", not(feature = "quantized")),
+    allow(dead_code)
+)]
+fn sync_dir(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            sync_dir(&entry.path())?;
+        } else {
+            fs::File::open(entry.path())?.sync_all()?;
+        }
+    }
+    fs::File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// Whether the index directory at `path` holds a quantized index older than the
+/// NGT index it was built from, which likely must be
+/// [`quantized`](crate::qg::QgIndex::quantize) again.
+pub fn is_stale_qg<P: AsRef<Path>>(path: P) -> Result<bool> {
+    let path = path.as_ref();
+    let qg = path.join(QG_DIR);
+    if !qg.is_dir() {
+        return Ok(false);
+    }
+
+    let Some(quantized) = newest_modification(fs::read_dir(&qg)?.map(|e| Ok(e?.path())))? else {
+        return Ok(false);
+    };
+    let ngt_files = NGT_FILES.iter().map(|f| Ok(path.join(f)));
+    let Some(modified) = newest_modification(ngt_files)? else {
+        return Ok(false);
+    };
+
+    Ok(quantized < modified)
+}
+
+fn newest_modification<I>(paths: I) -> Result<Option<SystemTime>>
+where
+    I: Iterator<Item = std::io::Result<PathBuf>>,
+{
+    let mut newest = None;
+    for path in paths {
+        let path = path?;
+        if path.is_file() {
+            let modified = fs::metadata(path)?.modified()?;
+            newest = newest.max(Some(modified));
+        }
+    }
+    Ok(newest)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::fs::File;
+    use std::result::Result as StdResult;
+    use std::time::Duration;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_gc() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory with index files and junk
+        let dir = tempdir()?;
+        let path = dir.path().join("index");
+        fs::create_dir(&path)?;
+        fs::write(path.join("grp"), "")?;
+        fs::write(path.join("prf.bak"), "")?;
+        fs::write(path.join("obj.tmp"), "")?;
+        fs::create_dir(path.join(QG_DIR))?;
+        fs::write(path.join(QG_DIR).join("grp"), "")?;
+
+        // Make the quantized index older than the NGT index
+        let past = SystemTime::now() - Duration::from_secs(60);
+        File::options()
+            .write(true)
+            .open(path.join(QG_DIR).join("grp"))?
+            .set_modified(past)?;
+
+        // Along with the staging directory of an interrupted operation
+        let staging = dir.path().join(".index.staging.persist.abc123");
+        fs::create_dir_all(staging.join("index"))?;
+        fs::create_dir(dir.path().join(".other.staging.persist.abc123"))?;
+
+        assert!(is_stale_qg(&path)?);
+        let expected = vec![staging.clone(), path.join("obj.tmp"), path.join("prf.bak")];
+
+        // A dry run only lists the junk
+        assert_eq!(gc(&path, true, false)?, expected);
+        assert!(path.join("obj.tmp").exists());
+
+        // Otherwise the junk is removed, but the stale quantized index is kept
+        assert_eq!(gc(&path, false, false)?, expected);
+        assert!(!path.join("obj.tmp").exists());
+        assert!(!staging.exists());
+        assert!(dir.path().join(".other.staging.persist.abc123").exists());
+        assert!(path.join(QG_DIR).exists());
+        assert!(path.join("grp").exists());
+
+        // Unless its removal is requested
+        assert_eq!(gc(&path, false, true)?, vec![path.join(QG_DIR)]);
+        assert!(!path.join(QG_DIR).exists());
+        assert!(gc(&path, false, true)?.is_empty());
+
+        dir.close()?;
+        Ok(())
+    }
+}
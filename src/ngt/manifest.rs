@@ -0,0 +1,175 @@
+use std::fs;
+use std::io::ErrorKind as IoErrorKind;
+use std::path::Path;
+
+use ngt_sys as sys;
+
+use super::{NgtDistance, NgtObject};
+use crate::error::{Error, ErrorKind, Result};
+
+const MANIFEST_FILE: &str = "manifest";
+
+/// Versions and properties an index was written with, persisted in a sidecar file of
+/// the index directory to detect incompatibilities before NGT reads the index.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Manifest {
+    pub(crate) crate_version: String,
+    pub(crate) ngt_version: String,
+    pub(crate) object_type: NgtObject,
+    pub(crate) dimension: usize,
+    pub(crate) distance_type: NgtDistance,
+}
+
+impl Manifest {
+    /// The manifest of an index written by the running versions.
+    pub(crate) fn new(object_type: NgtObject, dimension: usize, distance: NgtDistance) -> Self {
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            ngt_version: sys::NGT_VERSION.to_string(),
+            object_type,
+            dimension,
+            distance_type: distance,
+        }
+    }
+
+    /// Load the manifest from the index directory, indexes created without manifest
+    /// have none.
+    pub(crate) fn load(dir: &Path) -> Result<Option<Self>> {
+        let content = match fs::read_to_string(dir.join(MANIFEST_FILE)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == IoErrorKind::NotFound => return Ok(None),
+            Err(e) => Err(e)?,
+        };
+
+        let (mut crate_version, mut ngt_version) = (None, None);
+        let (mut object_type, mut dimension, mut distance_type) = (None, None, None);
+        for line in content.lines() {
+            let parse_err = || Error::from(format!("Invalid manifest line: {line:?}"));
+            let (key, value) = line.split_once(' ').ok_or_else(parse_err)?;
+            match key {
+                "crate_version" => crate_version = Some(value.to_string()),
+                "ngt_version" => ngt_version = Some(value.to_string()),
+                "object_type" => {
+                    let code = value.parse::<i32>().map_err(|_| parse_err())?;
+                    object_type = Some(NgtObject::try_from(code)?);
+                }
+                "dimension" => dimension = Some(value.parse().map_err(|_| parse_err())?),
+                "distance_type" => {
+                    let code = value.parse::<i32>().map_err(|_| parse_err())?;
+                    distance_type = Some(NgtDistance::try_from(code)?);
+                }
+                _ => Err(parse_err())?,
+            }
+        }
+
+        let missing = |key| Error::from(format!("Missing {key} in manifest"));
+        Ok(Some(Self {
+            crate_version: crate_version.ok_or_else(|| missing("crate_version"))?,
+            ngt_version: ngt_version.ok_or_else(|| missing("ngt_version"))?,
+            object_type: object_type.ok_or_else(|| missing("object_type"))?,
+            dimension: dimension.ok_or_else(|| missing("dimension"))?,
+            distance_type: distance_type.ok_or_else(|| missing("distance_type"))?,
+        }))
+    }
+
+    /// Save the manifest in the index directory.
+    pub(crate) fn save(&self, dir: &Path) -> Result<()> {
+        let content = format!(
+            "crate_version {}\nngt_version {}\nobject_type {}\ndimension {}\ndistance_type {}\n",
+            self.crate_version,
+            self.ngt_version,
+            self.object_type as i32,
+            self.dimension,
+            self.distance_type as i32,
+        );
+        fs::write(dir.join(MANIFEST_FILE), content)?;
+        Ok(())
+    }
+
+    /// Check that the index can be opened by the running NGT version, with vectors of
+    /// the given object type.
+    pub(crate) fn check(&self, object_type: NgtObject) -> Result<()> {
+        let major = |version: &str| version.split('.').next().unwrap_or_default().to_string();
+        if major(&self.ngt_version) != major(sys::NGT_VERSION) {
+            Err(Error::new(
+                ErrorKind::IncompatibleIndex,
+                format!(
+                    "Index written by NGT {} (ngt crate {}) can't be read by NGT {}",
+                    self.ngt_version,
+                    self.crate_version,
+                    sys::NGT_VERSION
+                ),
+            ))?
+        }
+        if self.object_type != object_type {
+            Err(Error::new(
+                ErrorKind::IncompatibleIndex,
+                format!(
+                    "Index of {:?} objects can't be opened with {object_type:?} objects",
+                    self.object_type
+                ),
+            ))?
+        }
+        Ok(())
+    }
+
+    /// Check that the properties NGT read from the index directory are the ones the
+    /// index was written with.
+    pub(crate) fn check_properties(&self, dimension: usize, distance: NgtDistance) -> Result<()> {
+        if self.dimension != dimension || self.distance_type != distance {
+            Err(Error::new(
+                ErrorKind::IncompatibleIndex,
+                format!(
+                    "Index written by ngt crate {} with dimension {} and {:?} distance \
+                     was read with dimension {dimension} and {distance:?} distance",
+                    self.crate_version, self.dimension, self.distance_type
+                ),
+            ))?
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::result::Result as StdResult;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_manifest() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the manifest
+        let dir = tempdir()?;
+        assert_eq!(Manifest::load(dir.path())?, None);
+
+        // Save a manifest and load it back
+        let manifest = Manifest::new(NgtObject::Float, 3, NgtDistance::Cosine);
+        manifest.save(dir.path())?;
+        let loaded = Manifest::load(dir.path())?.unwrap();
+        assert_eq!(loaded, manifest);
+        assert!(loaded.check(NgtObject::Float).is_ok());
+
+        // Incompatible object types and NGT versions are rejected
+        let err = loaded.check(NgtObject::Uint8).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IncompatibleIndex);
+
+        // So are properties read differently by NGT
+        assert!(loaded.check_properties(3, NgtDistance::Cosine).is_ok());
+        let err = loaded.check_properties(4, NgtDistance::Cosine).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IncompatibleIndex);
+        let err = loaded.check_properties(3, NgtDistance::L2).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IncompatibleIndex);
+        let manifest = Manifest {
+            ngt_version: "0.1.0".to_string(),
+            ..manifest
+        };
+        let err = manifest.check(NgtObject::Float).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IncompatibleIndex);
+
+        dir.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+//! Functions aimed at migrating the vectors of an [`NgtIndex`](NgtIndex) to a new index
+
+use std::fs;
+use std::path::Path;
+
+use super::{IndexMode, NgtIndex, NgtObject, NgtObjectType, NgtProperties};
+use crate::error::{Error, Result};
+use crate::VecId;
+
+/// Manifest of the [`IdMap`][] of a migrated index, stored in its directory.
+const ID_MAP_FILE: &str = "id_map";
+
+/// Pairs of source and destination ids of migrated vectors.
+pub type IdMap = Vec<(VecId, VecId)>;
+
+/// Copies the vectors of the `src` index into a new index created at `dst_path` with
+/// the given [`NgtProperties`][], converting their elements to the object type of the
+/// new index. The new index is then built.
+///
+/// Vectors are copied in the order of their ids, and soft removed vectors are
+/// skipped. The new index assigns its own ids, so the pairs of source and destination
+/// ids are returned along with the new index, and written in a manifest that can be
+/// read by [`read_id_map`][].
+pub fn migrate<S, M, D, P>(
+    src: &NgtIndex<S, M>,
+    dst_path: P,
+    prop: NgtProperties<D>,
+    conversion: Conversion,
+    num_threads: usize,
+) -> Result<(NgtIndex<D>, IdMap)>
+where
+    S: NgtObjectType,
+    M: IndexMode,
+    D: NgtObjectType,
+    P: AsRef<Path>,
+{
+    if prop.dimension as usize != src.dimension() {
+        Err(format!(
+            "Dimension {} of the new index differs from the source dimension {}",
+            prop.dimension,
+            src.dimension()
+        ))?
+    }
+
+    src.rebuild_with(dst_path, prop, num_threads, |_, vec| {
+        Some(vec.into_iter().map(|v| conversion.convert(v)).collect())
+    })
+}
+
+/// Reads the [`IdMap`][] of the index migrated by [`migrate`][] or
+/// [`rebuild_with`](NgtIndex::rebuild_with) to the directory at `path`.
+pub fn read_id_map<P: AsRef<Path>>(path: P) -> Result<IdMap> {
+    fs::read_to_string(path.as_ref().join(ID_MAP_FILE))?
+        .lines()
+        .map(|line| {
+            let parse_err = || Error::from(format!("Invalid id map line: {line:?}"));
+            let (src, dst) = line.split_once(' ').ok_or_else(parse_err)?;
+            Ok((
+                src.parse().map_err(|_| parse_err())?,
+                dst.parse().map_err(|_| parse_err())?,
+            ))
+        })
+        .collect()
+}
+
+pub(crate) fn write_id_map(dir: &Path, ids: &IdMap) -> Result<()> {
+    let content = ids
+        .iter()
+        .map(|(src, dst)| format!("{src} {dst}\n"))
+        .collect::<String>();
+    fs::write(dir.join(ID_MAP_FILE), content)?;
+    Ok(())
+}
+
+/// How the elements of vectors are converted by [`migrate`][].
+///
+/// Each element `v` becomes `v * scale + offset`, which is then rounded when
+/// converted to an integer type. Values out of the range of the target type are
+/// saturated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Conversion {
+    scale: f64,
+    offset: f64,
+    rounding: Rounding,
+}
+
+impl Default for Conversion {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            offset: 0.0,
+            rounding: Rounding::Nearest,
+        }
+    }
+}
+
+impl Conversion {
+    /// The identity conversion, elements are only rounded to integer types.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the factor elements are multiplied by, defaults to `1`.
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Set the value added to elements once scaled, defaults to `0`.
+    pub fn offset(mut self, offset: f64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Set how elements are rounded to integer types, defaults to
+    /// [`Nearest`](Rounding::Nearest).
+    pub fn rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    fn convert<S: NgtObjectType, D: NgtObjectType>(&self, v: S) -> D {
+        let v = v.to_f64() * self.scale + self.offset;
+        let v = match D::as_obj() {
+            NgtObject::Uint8 => self.rounding.round(v),
+            NgtObject::Float | NgtObject::Float16 => v,
+        };
+        D::from_f64(v)
+    }
+}
+
+/// How values are rounded when converted to an integer type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round to the nearest integer, half-way cases away from zero.
+    Nearest,
+    /// Round toward negative infinity.
+    Floor,
+    /// Round toward positive infinity.
+    Ceil,
+    /// Round toward zero.
+    Truncate,
+}
+
+impl Rounding {
+    fn round(self, v: f64) -> f64 {
+        match self {
+            Self::Nearest => v.round(),
+            Self::Floor => v.floor(),
+            Self::Ceil => v.ceil(),
+            Self::Truncate => v.trunc(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::result::Result as StdResult;
+
+    use half::f16;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_migrate() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the indexes
+        let dir = tempdir()?;
+
+        // Create a f32 index with a removed vector
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut src = NgtIndex::create(dir.path().join("src"), prop)?;
+        let id1 = src.insert(vec![0.25, 0.5, 0.75])?;
+        let id2 = src.insert(vec![0.2, 0.4, 0.6])?;
+        let id3 = src.insert(vec![1.0, 2.0, 3.0])?;
+        src.build(1)?;
+        src.soft_remove(id2)?;
+
+        // Migrate it to u8 with scaling
+        let prop = NgtProperties::<u8>::dimension(3)?;
+        let conversion = Conversion::new().scale(10.0).rounding(Rounding::Floor);
+        let (dst, ids) = migrate(&src, dir.path().join("u8"), prop, conversion, 1)?;
+        assert_eq!(read_id_map(dir.path().join("u8"))?, ids);
+        assert_eq!(
+            ids.iter().map(|&(s, _)| s).collect::<Vec<_>>(),
+            vec![id1, id3]
+        );
+        assert_eq!(dst.get_vec(ids[0].1)?, vec![2, 5, 7]);
+        assert_eq!(dst.get_vec(ids[1].1)?, vec![10, 20, 30]);
+        let res = dst.search(&[10, 20, 30], 1, 0.1)?;
+        assert_eq!(res[0].id, ids[1].1);
+
+        // Migrate it to f16
+        let prop = NgtProperties::<f16>::dimension(3)?;
+        let (dst, ids) = migrate(&src, dir.path().join("f16"), prop, Conversion::new(), 1)?;
+        let vec = dst.get_vec(ids[1].1)?;
+        assert_eq!(vec, [1.0, 2.0, 3.0].map(f16::from_f32));
+
+        // Dimensions must match
+        let prop = NgtProperties::<u8>::dimension(4)?;
+        assert!(migrate(&src, dir.path().join("dim"), prop, Conversion::new(), 1).is_err());
+
+        dir.close()?;
+        Ok(())
+    }
+}
@@ -1,6 +1,36 @@
+mod archive;
+#[cfg(feature = "ndarray")]
+mod array;
+pub mod bench;
+mod config;
+pub mod distance;
+mod expirations;
+mod graph;
 mod index;
+pub mod maintenance;
+mod manifest;
+pub mod migration;
+mod namespaced;
 pub mod optim;
 mod properties;
+mod sharded;
+mod timestamps;
+mod tombstones;
+mod tuning;
 
-pub use self::index::{NgtIndex, NgtQuery};
-pub use self::properties::{NgtDistance, NgtObject, NgtObjectType, NgtProperties};
+pub use self::graph::GraphFormat;
+pub use self::index::{
+    ClosedIndex, Fusion, IndexMode, ModeRead, ModeWrite, NgtIndex, NgtQuery, NgtQueryResult,
+    NgtResults, VerifyReport,
+};
+pub use self::namespaced::NamespacedIndex;
+pub use self::properties::{
+    NgtDistance, NgtGraphType, NgtIndexType, NgtObject, NgtObjectType, NgtProperties, NgtSeedType,
+};
+pub use self::sharded::{RoundRobin, ShardedIndex, ShardingPolicy};
+pub use self::tuning::TunedParams;
+
+#[cfg(feature = "metadata")]
+pub(crate) use self::timestamps::{decode_time, encode_time};
+#[cfg(feature = "quantized")]
+pub(crate) use self::tombstones::Tombstones;
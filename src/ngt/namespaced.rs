@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{NgtIndex, NgtObjectType, NgtProperties};
+use crate::error::{Error, ErrorKind, Result};
+use crate::{SearchResult, VecId};
+
+/// Multiple [`NgtIndex`][] stored under one root directory, one sub-directory per
+/// namespace.
+///
+/// Each namespace is an independent index, with its own properties and ids.
+#[derive(Debug)]
+pub struct NamespacedIndex<T> {
+    root: PathBuf,
+    indexes: BTreeMap<String, NgtIndex<T>>,
+}
+
+impl<T> NamespacedIndex<T>
+where
+    T: NgtObjectType,
+{
+    /// Open the namespaces stored under the specified root directory, which is
+    /// created if missing.
+    pub fn open<P: AsRef<Path>>(root: P) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(&root)?;
+
+        let mut indexes = BTreeMap::new();
+        for entry in fs::read_dir(&root)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let namespace = entry
+                .file_name()
+                .into_string()
+                .map_err(|name| format!("Invalid namespace {name:?}"))?;
+            indexes.insert(namespace, NgtIndex::open(entry.path())?);
+        }
+
+        Ok(Self { root, indexes })
+    }
+
+    /// Create a new namespace with the given [`NgtProperties`][], and return its
+    /// index.
+    pub fn create(&mut self, namespace: &str, prop: NgtProperties<T>) -> Result<&mut NgtIndex<T>> {
+        check_namespace(namespace)?;
+        if self.indexes.contains_key(namespace) {
+            Err(format!("Namespace {namespace:?} already exists"))?
+        }
+
+        let index = NgtIndex::create(self.root.join(namespace), prop)?;
+        Ok(self.indexes.entry(namespace.to_string()).or_insert(index))
+    }
+
+    /// Remove the namespace and delete its index from the disk.
+    pub fn drop_namespace(&mut self, namespace: &str) -> Result<()> {
+        let index = self
+            .indexes
+            .remove(namespace)
+            .ok_or_else(|| not_found(namespace))?;
+        drop(index);
+        fs::remove_dir_all(self.root.join(namespace))?;
+        Ok(())
+    }
+
+    /// Insert the specified vector into the index of the namespace, see
+    /// [`NgtIndex::insert`][].
+    pub fn insert(&mut self, namespace: &str, vec: Vec<T>) -> Result<VecId> {
+        self.get_mut(namespace)?.insert(vec)
+    }
+
+    /// Build the index of the namespace, see [`NgtIndex::build`][].
+    pub fn build(&mut self, namespace: &str, num_threads: usize) -> Result<()> {
+        self.get_mut(namespace)?.build(num_threads)
+    }
+
+    /// Remove the specified vector from the index of the namespace, see
+    /// [`NgtIndex::remove`][].
+    pub fn remove(&mut self, namespace: &str, id: VecId) -> Result<()> {
+        self.get_mut(namespace)?.remove(id)
+    }
+
+    /// Search the nearest vectors in the index of the namespace, see
+    /// [`NgtIndex::search`][].
+    pub fn search(
+        &self,
+        namespace: &str,
+        vec: &[T],
+        res_size: usize,
+        epsilon: f32,
+    ) -> Result<Vec<SearchResult>> {
+        self.get(namespace)?.search(vec, res_size, epsilon)
+    }
+
+    /// Persist the indexes of all the namespaces.
+    pub fn persist(&mut self) -> Result<()> {
+        for index in self.indexes.values_mut() {
+            index.persist()?;
+        }
+        Ok(())
+    }
+
+    /// The index of the namespace.
+    ///
+    /// Returns an error of kind [`NotFound`](ErrorKind::NotFound) if the namespace
+    /// doesn't exist.
+    pub fn get(&self, namespace: &str) -> Result<&NgtIndex<T>> {
+        self.indexes
+            .get(namespace)
+            .ok_or_else(|| not_found(namespace))
+    }
+
+    /// The mutable index of the namespace.
+    ///
+    /// Returns an error of kind [`NotFound`](ErrorKind::NotFound) if the namespace
+    /// doesn't exist.
+    pub fn get_mut(&mut self, namespace: &str) -> Result<&mut NgtIndex<T>> {
+        self.indexes
+            .get_mut(namespace)
+            .ok_or_else(|| not_found(namespace))
+    }
+
+    /// The existing namespaces, in lexicographic order.
+    pub fn namespaces(&self) -> impl Iterator<Item = &str> {
+        self.indexes.keys().map(String::as_str)
+    }
+
+    /// The root directory of the namespaces.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+/// Namespaces are directory names, they can't be empty nor escape the root directory.
+fn check_namespace(namespace: &str) -> Result<()> {
+    if namespace.is_empty()
+        || namespace == "."
+        || namespace == ".."
+        || namespace.contains(['/', '\\', '\0'])
+    {
+        Err(format!("Invalid namespace {namespace:?}"))?
+    }
+    Ok(())
+}
+
+fn not_found(namespace: &str) -> Error {
+    Error::new(
+        ErrorKind::NotFound,
+        format!("Namespace {namespace:?} doesn't exist"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::result::Result as StdResult;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_namespaced() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the namespaces
+        let dir = tempdir()?;
+
+        // Create two namespaces holding different vectors
+        let mut index = NamespacedIndex::<f32>::open(dir.path())?;
+        index.create("a", NgtProperties::dimension(3)?)?;
+        index.create("b", NgtProperties::dimension(3)?)?;
+        assert!(index.create("b", NgtProperties::dimension(3)?).is_err());
+        assert!(index.create("../c", NgtProperties::dimension(3)?).is_err());
+
+        let id_a = index.insert("a", vec![1.0, 2.0, 3.0])?;
+        index.insert("b", vec![4.0, 5.0, 6.0])?;
+        index.insert("b", vec![1.0, 2.0, 3.0])?;
+        index.build("a", 1)?;
+        index.build("b", 1)?;
+        index.persist()?;
+
+        // Reopen the namespaces and search them
+        drop(index);
+        let mut index = NamespacedIndex::<f32>::open(dir.path())?;
+        assert_eq!(index.namespaces().collect::<Vec<_>>(), vec!["a", "b"]);
+        let res = index.search("a", &[4.1, 5.1, 6.1], 2, 0.1)?;
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].id, id_a);
+        assert_eq!(index.search("b", &[4.1, 5.1, 6.1], 2, 0.1)?.len(), 2);
+
+        // Drop a namespace
+        index.drop_namespace("a")?;
+        assert!(!dir.path().join("a").exists());
+        let err = index.search("a", &[1.0, 2.0, 3.0], 1, 0.1).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+
+        dir.close()?;
+        Ok(())
+    }
+}
@@ -1,18 +1,28 @@
 #![cfg_attr(feature = "shared_mem", allow(unused_imports))]
 
 //! Functions aimed at optimizing [`NgtIndex`](NgtIndex)
+//!
+//! The functions are generic over the [`NgtObjectType`][] of the index, so `u8` and
+//! `f16` indexes are optimized like `f32` ones. Functions working on index paths take
+//! the object type as a type parameter, such as `convert_anng_to_onng::<u8, _>`.
 
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::ffi::CString;
 use std::os::unix::ffi::OsStrExt;
 use std::path::Path;
-use std::ptr;
+use std::time::{Duration, Instant};
+use std::{fs, ptr};
 
 use ngt_sys as sys;
 use scopeguard::defer;
 
-use super::NgtObjectType;
+use super::{IndexMode, NgtObjectType};
+use crate::dataset::recall;
 use crate::error::{make_err, Result};
 use crate::ngt::index::NgtIndex;
+use crate::ngt::maintenance::{staging_dir, swap_in, QG_DIR};
+use crate::ngt::migration::IdMap;
+use crate::VecId;
 
 /// Optimizes the number of initial edges of an ANNG index.
 ///
@@ -43,6 +53,23 @@ pub fn optimize_anng_edges_number<P: AsRef<Path>>(
     }
 }
 
+/// Optimizes the number of initial edges of an open ANNG index, like
+/// [`optimize_anng_edges_number`](optimize_anng_edges_number).
+///
+/// The index is [`persisted`](NgtIndex::persist) and reopened around the optimization,
+/// which NGT runs on the index directory. It must not have been
+/// [`built`](NgtIndex::build) yet, and can be built afterwards.
+#[cfg(not(feature = "shared_mem"))]
+pub fn optimize_anng_edges_number_in_place<T: NgtObjectType>(
+    index: &mut NgtIndex<T>,
+    params: AnngEdgeOptimParams,
+) -> Result<()> {
+    index.persist()?;
+    optimize_anng_edges_number(index.path(), params)?;
+    *index = NgtIndex::open(index.path())?;
+    Ok(())
+}
+
 /// Optimizes the search parameters of an ANNG index.
 ///
 /// Optimizes the search parameters about the explored edges and memory prefetch for the
@@ -52,17 +79,67 @@ where
     T: NgtObjectType,
     P: AsRef<Path>,
 {
-    let mut optimizer = GraphOptimizer::new(GraphOptimParams::default())?;
-    optimizer.set_processing_modes(true, true, true)?;
+    optimize_anng_search_parameters_with::<T, P>(
+        index_path,
+        GraphOptimParams::default(),
+        SearchOptimModes::default(),
+    )
+}
+
+/// Optimizes the search parameters of an ANNG index, like
+/// [`optimize_anng_search_parameters`](optimize_anng_search_parameters) but with the
+/// specified [`GraphOptimParams`][] and only the enabled [`SearchOptimModes`][].
+///
+/// The accuracy ranges of the parameters are the ones the search coefficients are
+/// tuned for, and `nb_queries` the number of sampled queries.
+pub fn optimize_anng_search_parameters_with<T, P>(
+    index_path: P,
+    params: GraphOptimParams,
+    modes: SearchOptimModes,
+) -> Result<()>
+where
+    T: NgtObjectType,
+    P: AsRef<Path>,
+{
+    let mut optimizer = GraphOptimizer::new(params)?;
+    optimizer.set_processing_modes(
+        modes.search_params,
+        modes.prefetch_params,
+        modes.accuracy_table,
+    )?;
     optimizer.adjust_search_coefficients::<P, T>(index_path)?;
     Ok(())
 }
 
+/// Builds the accuracy table of an open index, which is persisted with it.
+///
+/// NGT's optimizer measures the accuracy reached by a range of search epsilons, so
+/// that searches can then specify their
+/// [`expected_accuracy`](crate::NgtQuery::expected_accuracy) rather than an epsilon.
+/// The index is [`persisted`](NgtIndex::persist) and reopened around the generation.
+/// **It must have been [`built`](NgtIndex::build)**.
+pub fn build_accuracy_table<T: NgtObjectType>(index: &mut NgtIndex<T>) -> Result<()> {
+    index.persist()?;
+    let modes = SearchOptimModes {
+        search_params: false,
+        prefetch_params: false,
+        accuracy_table: true,
+    };
+    optimize_anng_search_parameters_with::<T, _>(index.path(), GraphOptimParams::default(), modes)?;
+    *index = NgtIndex::open(index.path())?;
+    Ok(())
+}
+
 /// Refines an ANNG index (RANNG) to improve search performance.
 ///
 /// Improves accuracy of neighboring nodes for each node by searching with each
 /// node. Note that refinement takes a long processing time. An ANNG index can be
 /// refined only after it has been [`built`](NgtIndex::build).
+///
+/// NGT refines all the nodes in a single call of its C API, which can't be
+/// interrupted, so the refinement can't be bounded by a time budget nor resumed later
+/// on. To spread it over maintenance windows, refine a copy of the index made with
+/// [`clone_to`](NgtIndex::clone_to) and swap it in once done.
 #[cfg(not(feature = "shared_mem"))]
 pub fn refine_anng<T: NgtObjectType>(
     index: &mut NgtIndex<T>,
@@ -116,6 +193,525 @@ where
     Ok(())
 }
 
+/// Converts the open `index_anng` to an ONNG at `index_onng_out`, like
+/// [`convert_anng_to_onng`](convert_anng_to_onng), and opens the ONNG.
+///
+/// The ANNG is [`persisted`](NgtIndex::persist) beforehand, as NGT converts the index
+/// directory.
+pub fn convert_anng_index_to_onng<T, P>(
+    index_anng: &mut NgtIndex<T>,
+    index_onng_out: P,
+    params: GraphOptimParams,
+) -> Result<NgtIndex<T>>
+where
+    T: NgtObjectType,
+    P: AsRef<Path>,
+{
+    index_anng.persist()?;
+    convert_anng_to_onng::<T, _>(index_anng.path(), index_onng_out.as_ref(), params)?;
+    NgtIndex::open(index_onng_out)
+}
+
+/// Reconstructs the graph of the open `index` into a new index at `index_out`, which
+/// is then opened.
+///
+/// Unlike [`convert_anng_to_onng`](convert_anng_to_onng), the edges of the graph are
+/// kept and only the shortcut edges are removed: an edge is redundant when its target
+/// can be reached through a shorter path of other edges. This speeds up searches of an
+/// ANNG without the edge reconstruction of an ONNG. The search coefficients of the new
+/// index are then adjusted like by
+/// [`optimize_anng_search_parameters`](optimize_anng_search_parameters).
+///
+/// The index is [`persisted`](NgtIndex::persist) beforehand, as NGT reconstructs the
+/// graph from the index directory. **It must have been [`built`](NgtIndex::build)**.
+pub fn reconstruct_graph<T, P>(
+    index: &mut NgtIndex<T>,
+    index_out: P,
+    params: ReconstructParams,
+) -> Result<NgtIndex<T>>
+where
+    T: NgtObjectType,
+    P: AsRef<Path>,
+{
+    index.persist()?;
+    let mut optimizer = GraphOptimizer::new(params.into())?;
+    optimizer.convert_anng_to_onng::<T, _>(index.path(), index_out.as_ref())?;
+    NgtIndex::open(index_out)
+}
+
+/// Shrinks the graph of the open `index` so that its nodes have `target_out_degree`
+/// edges on average, to reduce its memory footprint before shipping it to
+/// memory-constrained hosts. The memory saved is returned in the [`ShrinkReport`][].
+///
+/// NGT's C API can't edit the edges of the graph, so the graph is reconstructed like
+/// an ONNG: each node keeps its nearest `target_out_degree - target_out_degree / 2`
+/// outgoing edges, and gets the reverse of the `target_out_degree / 2` nearest edges
+/// of the other nodes. The shortcut edges are then removed, which keeps diverse
+/// neighbors. A node may thus exceed `target_out_degree` edges when many nodes have it
+/// among their nearest neighbors, but the average degree doesn't. The new graph is
+/// built in a fresh directory next to the index one, which it then atomically
+/// replaces, and the index is reopened. A quantized index built from the previous
+/// graph is dropped, it must be [`quantized`](crate::qg::QgIndex::quantize) again.
+///
+/// Fails if `target_out_degree` is lower than 2, as nodes need both outgoing and
+/// incoming edges. **The index must have been [`built`](NgtIndex::build)**.
+#[cfg(not(feature = "shared_mem"))]
+pub fn shrink<T: NgtObjectType>(
+    index: &mut NgtIndex<T>,
+    target_out_degree: usize,
+) -> Result<ShrinkReport> {
+    if target_out_degree < 2 {
+        Err(format!(
+            "The target out-degree must be at least 2, got {target_out_degree}"
+        ))?
+    }
+
+    let nb_edges = |index: &NgtIndex<T>| -> Result<usize> {
+        index
+            .stored_ids()
+            .map(|id| Ok(index.edges(id)?.len()))
+            .sum()
+    };
+    let graph_size = |path: &Path| -> Result<u64> { Ok(fs::metadata(path.join("grp"))?.len()) };
+    let edges_before = nb_edges(index)?;
+
+    index.persist()?;
+    let path = index.path().to_path_buf();
+    let graph_size_before = graph_size(&path)?;
+    let (_staging, out) = staging_dir(&path, "shrink")?;
+
+    let nb_incoming = target_out_degree / 2;
+    let params = GraphOptimParams {
+        nb_outgoing: i32::try_from(target_out_degree - nb_incoming)?,
+        nb_incoming: i32::try_from(nb_incoming)?,
+        ..GraphOptimParams::default()
+    };
+    let mut optimizer = GraphOptimizer::new(params)?;
+    optimizer.convert_anng_to_onng::<T, _>(path.as_path(), out.as_path())?;
+
+    // Keep the files that aren't managed by NGT, such as the manifest, but not the
+    // quantized index built from the previous graph. The previous index ends up in the
+    // staging directory, removed when dropped
+    swap_in(&out, &path, |name| name != QG_DIR)?;
+    *index = NgtIndex::open(&path)?;
+
+    Ok(ShrinkReport {
+        edges_before,
+        edges_after: nb_edges(index)?,
+        graph_size_before,
+        graph_size_after: graph_size(&path)?,
+    })
+}
+
+/// Summarizes the effect of an optimization step by comparing two built indexes.
+///
+/// Both indexes are expected to contain the same vectors, typically `index_before` is
+/// a copy of the index taken before calling [`refine_anng`](refine_anng),
+/// [`convert_anng_to_onng`](convert_anng_to_onng) or
+/// [`optimize_anng_edges_number`](optimize_anng_edges_number), and `index_after` is
+/// the optimized index.
+///
+/// Queries are sampled from the vectors of `index_before`, and the ground truth is
+/// obtained by searching `index_before` with the wider
+/// [`gt_epsilon`](ReportParams::gt_epsilon).
+pub fn report<T, M1, M2>(
+    index_before: &NgtIndex<T, M1>,
+    index_after: &NgtIndex<T, M2>,
+    params: ReportParams,
+) -> Result<OptimReport>
+where
+    T: NgtObjectType,
+    M1: IndexMode,
+    M2: IndexMode,
+{
+    let ids = index_before.ids().collect::<Vec<_>>();
+    let step = (ids.len() / params.nb_queries.max(1)).max(1);
+    let queries = ids
+        .into_iter()
+        .step_by(step)
+        .take(params.nb_queries)
+        .map(|id| index_before.get_vec(id))
+        .collect::<Result<Vec<_>>>()?;
+
+    let ground_truth = queries
+        .iter()
+        .map(|query| {
+            index_before
+                .search(query, params.nb_results, params.gt_epsilon)
+                .map(|res| res.into_iter().map(|r| r.id).collect::<Vec<_>>())
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(OptimReport {
+        before: IndexStats::measure(index_before, &queries, &ground_truth, &params)?,
+        after: IndexStats::measure(index_after, &queries, &ground_truth, &params)?,
+    })
+}
+
+/// Degree and connectivity statistics of the graph of an index, to decide whether
+/// [`refine_anng`](refine_anng) or an edge optimization is needed.
+///
+/// Vectors inserted but not [`built`](NgtIndex::build) yet have no node in the graph
+/// and are ignored, as are the edges to removed vectors.
+///
+/// **The index must have been [`built`](NgtIndex::build) beforehand**.
+pub fn graph_stats<T, M>(index: &NgtIndex<T, M>) -> Result<GraphStats>
+where
+    T: NgtObjectType,
+    M: IndexMode,
+{
+    let mut nodes = BTreeMap::new();
+    for id in index.stored_ids() {
+        if let Ok(edges) = index.edges(id) {
+            nodes.insert(id, edges);
+        }
+    }
+    let positions = nodes
+        .keys()
+        .enumerate()
+        .map(|(pos, &id)| (id, pos))
+        .collect::<HashMap<_, _>>();
+
+    // Union-find of the nodes linked by an edge in either direction
+    let mut parents = (0..nodes.len()).collect::<Vec<_>>();
+
+    let mut stats = GraphStats::default();
+    let mut indegrees = vec![0; nodes.len()];
+    for (pos, edges) in nodes.values().enumerate() {
+        let targets = edges
+            .iter()
+            .filter_map(|(target, _)| positions.get(target))
+            .collect::<Vec<_>>();
+        *stats.outdegrees.entry(targets.len()).or_default() += 1;
+        for &target in targets {
+            indegrees[target] += 1;
+            let (a, b) = (root(&mut parents, pos), root(&mut parents, target));
+            parents[a] = b;
+        }
+    }
+    for &indegree in &indegrees {
+        *stats.indegrees.entry(indegree).or_default() += 1;
+    }
+
+    stats.nb_nodes = nodes.len();
+    stats.nb_components = (0..nodes.len())
+        .filter(|&pos| root(&mut parents, pos) == pos)
+        .count();
+    if nodes.len() > 1 {
+        stats.nb_detached = indegrees.iter().filter(|&&indegree| indegree == 0).count();
+    }
+    Ok(stats)
+}
+
+/// Repairs the inconsistencies of the graph found by [`verify`](NgtIndex::verify), to
+/// counter the gradual recall loss of indexes with many insertions and removals.
+///
+/// NGT's C API can't edit the edges of the graph, so the nodes with edges to removed
+/// vectors and the nodes without incoming edges are removed and their vectors
+/// inserted again, which connects them to their current neighbors. The index is then
+/// [`built`](NgtIndex::build) with `num_threads` threads.
+///
+/// The reinserted vectors get new ids, returned in the [`CleanupReport`][], and keep
+/// their expiration time. [`Soft removed`](NgtIndex::soft_remove) vectors aren't
+/// reinserted. When `dry_run` is set the index isn't modified, the nodes that would
+/// be reinserted are only listed.
+///
+/// Fails unless it is a dry run if
+/// [`reuse_removed_ids`](crate::NgtProperties::reuse_removed_ids) is disabled, as
+/// removed nodes would then stay in the graph, such indexes are repaired by
+/// [`compact_to`](NgtIndex::compact_to) which rebuilds them entirely.
+pub fn cleanup<T: NgtObjectType>(
+    index: &mut NgtIndex<T>,
+    dry_run: bool,
+    num_threads: usize,
+) -> Result<CleanupReport> {
+    if !dry_run && !index.prop.config.reuse_removed_ids {
+        Err("Indexes not reusing removed ids can't be cleaned up in place".to_string())?
+    }
+
+    let verify = index.verify()?;
+    let live = index.ids().collect::<BTreeSet<_>>();
+
+    let dangling_targets = verify
+        .dangling_edges
+        .iter()
+        .map(|&(_, target)| target)
+        .collect::<BTreeSet<_>>();
+    let nodes = verify
+        .dangling_edges
+        .iter()
+        .map(|&(source, _)| source)
+        .chain(verify.orphan_nodes)
+        .filter(|id| live.contains(id))
+        .collect::<BTreeSet<_>>();
+
+    let mut reinserted = Vec::new();
+    if !dry_run {
+        for &id in &nodes {
+            let vec = index.get_vec(id)?;
+            let deadline = index.expires_at(id);
+            index.remove(id)?;
+            let new_id = index.insert(vec)?;
+            if let Some(deadline) = deadline {
+                index.set_expiration(new_id, deadline);
+            }
+            reinserted.push((id, new_id));
+        }
+        if !reinserted.is_empty() {
+            index.build(num_threads)?;
+        }
+    }
+
+    Ok(CleanupReport {
+        dangling_targets: dangling_targets.into_iter().collect(),
+        nodes: nodes.into_iter().collect(),
+        reinserted,
+    })
+}
+
+/// The root of the set of `pos` in the union-find `parents`, compressing the path.
+fn root(parents: &mut [usize], mut pos: usize) -> usize {
+    while parents[pos] != pos {
+        parents[pos] = parents[parents[pos]];
+        pos = parents[pos];
+    }
+    pos
+}
+
+/// Samples `n` queries from the vectors of the index, along with the ids of their `k`
+/// nearest neighbors found by a linear scan.
+///
+/// The queries are spread over the stored vectors, and each of their elements is
+/// shifted by a pseudo-random offset up to `perturbation`, so that they don't
+/// exactly match the indexed vectors. The offsets are deterministic, sampling twice
+/// the same index gives the same [`QuerySet`][], which can be reused to compare
+/// optimizations, [`tune`](NgtIndex::tune_epsilon) searches or evaluate their recall.
+pub fn sample_queries<T, M>(
+    index: &NgtIndex<T, M>,
+    n: usize,
+    k: usize,
+    perturbation: f64,
+) -> Result<QuerySet<T>>
+where
+    T: NgtObjectType,
+    M: IndexMode,
+{
+    let ids = index.ids().collect::<Vec<_>>();
+    let step = (ids.len() / n.max(1)).max(1);
+
+    let mut set = QuerySet {
+        queries: Vec::new(),
+        ground_truth: Vec::new(),
+    };
+    for id in ids.into_iter().step_by(step).take(n) {
+        let mut state = u64::from(id);
+        let query = index
+            .get_vec(id)?
+            .into_iter()
+            .map(|v| {
+                // Uniform offset in [-perturbation, perturbation)
+                let offset = (splitmix64(&mut state) >> 11) as f64 / (1u64 << 53) as f64;
+                T::from_f64(v.to_f64() + perturbation * (2. * offset - 1.))
+            })
+            .collect::<Vec<_>>();
+        let truth = index.search_exact(&query, k)?;
+        set.ground_truth
+            .push(truth.into_iter().map(|r| r.id).collect());
+        set.queries.push(query);
+    }
+    Ok(set)
+}
+
+/// A set of queries along with their ground truth, see [`sample_queries`][].
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuerySet<T> {
+    /// The query vectors.
+    pub queries: Vec<Vec<T>>,
+    /// The ids of the nearest neighbors of each query, nearest first.
+    pub ground_truth: Vec<Vec<VecId>>,
+}
+
+impl<T> QuerySet<T>
+where
+    T: NgtObjectType,
+{
+    /// The queries as slices, as taken by [`NgtIndex::tune_epsilon`][].
+    pub fn as_slices(&self) -> Vec<&[T]> {
+        self.queries.iter().map(Vec::as_slice).collect()
+    }
+
+    /// The mean recall of searches of the queries with the specified epsilon, as many
+    /// results as ground truth neighbors being searched.
+    pub fn recall<M: IndexMode>(&self, index: &NgtIndex<T, M>, epsilon: f32) -> Result<f64> {
+        let mut total = 0.;
+        for (query, truth) in self.queries.iter().zip(&self.ground_truth) {
+            let found = index.search(query, truth.len(), epsilon)?;
+            let found = found.into_iter().map(|r| r.id).collect::<Vec<_>>();
+            total += recall(&found, truth);
+        }
+        Ok(total / self.queries.len().max(1) as f64)
+    }
+}
+
+/// The next value of the SplitMix64 generator of the given state.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Parameters for [`report`](report).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportParams {
+    pub nb_queries: usize,
+    pub nb_results: usize,
+    pub epsilon: f32,
+    pub gt_epsilon: f32,
+}
+
+impl Default for ReportParams {
+    fn default() -> Self {
+        Self {
+            nb_queries: 100,
+            nb_results: 10,
+            epsilon: 0.1,
+            gt_epsilon: 0.5,
+        }
+    }
+}
+
+/// Comparison of an index before and after an optimization, see [`report`](report).
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptimReport {
+    pub before: IndexStats,
+    pub after: IndexStats,
+}
+
+impl OptimReport {
+    /// Difference of average out-degree (after - before).
+    pub fn avg_degree_change(&self) -> f64 {
+        self.after.avg_degree - self.before.avg_degree
+    }
+
+    /// Difference of measured recall (after - before).
+    pub fn recall_change(&self) -> f64 {
+        self.after.recall - self.before.recall
+    }
+
+    /// Ratio of average search latencies (before / after), above 1 means faster.
+    pub fn speedup(&self) -> f64 {
+        self.before.avg_latency.as_secs_f64() / self.after.avg_latency.as_secs_f64()
+    }
+}
+
+/// Graph and search statistics of an index, see [`report`](report).
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexStats {
+    pub nb_objects: usize,
+    pub nb_edges: usize,
+    pub min_degree: usize,
+    pub max_degree: usize,
+    pub avg_degree: f64,
+    pub recall: f64,
+    pub avg_latency: Duration,
+}
+
+impl IndexStats {
+    fn measure<T: NgtObjectType, M: IndexMode>(
+        index: &NgtIndex<T, M>,
+        queries: &[Vec<T>],
+        ground_truth: &[Vec<VecId>],
+        params: &ReportParams,
+    ) -> Result<Self> {
+        let mut nb_objects = 0;
+        let mut nb_edges = 0;
+        let mut min_degree = usize::MAX;
+        let mut max_degree = 0;
+        for id in index.stored_ids() {
+            let degree = index.edges(id)?.len();
+            nb_objects += 1;
+            nb_edges += degree;
+            min_degree = min_degree.min(degree);
+            max_degree = max_degree.max(degree);
+        }
+        if nb_objects == 0 {
+            min_degree = 0;
+        }
+
+        let mut nb_hits = 0;
+        let mut nb_expected = 0;
+        let mut elapsed = Duration::ZERO;
+        for (query, expected) in queries.iter().zip(ground_truth) {
+            let start = Instant::now();
+            let res = index.search(query, params.nb_results, params.epsilon)?;
+            elapsed += start.elapsed();
+
+            nb_hits += res.iter().filter(|r| expected.contains(&r.id)).count();
+            nb_expected += expected.len();
+        }
+
+        Ok(Self {
+            nb_objects,
+            nb_edges,
+            min_degree,
+            max_degree,
+            avg_degree: nb_edges as f64 / nb_objects.max(1) as f64,
+            recall: nb_hits as f64 / nb_expected.max(1) as f64,
+            avg_latency: elapsed / queries.len().max(1) as u32,
+        })
+    }
+}
+
+/// Graph statistics of an index, see [`graph_stats`](graph_stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphStats {
+    /// The number of nodes of the graph.
+    pub nb_nodes: usize,
+    /// The number of nodes for each number of outgoing edges.
+    pub outdegrees: BTreeMap<usize, usize>,
+    /// The number of nodes for each number of incoming edges.
+    pub indegrees: BTreeMap<usize, usize>,
+    /// The number of weakly connected components, more than one means that some
+    /// nodes can't be reached from the others.
+    pub nb_components: usize,
+    /// The number of nodes without incoming edges, which searches walking the graph
+    /// can't reach.
+    pub nb_detached: usize,
+}
+
+/// The repairs made by [`cleanup`](cleanup).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    /// The ids of the removed vectors that were still targeted by edges.
+    pub dangling_targets: Vec<VecId>,
+    /// The ids of the nodes with edges to removed vectors or without incoming edges.
+    pub nodes: Vec<VecId>,
+    /// Pairs of previous and new ids of the reinserted vectors, empty for a dry run.
+    pub reinserted: IdMap,
+}
+
+/// The effect of [`shrink`](shrink) on the graph of an index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShrinkReport {
+    /// The number of edges before shrinking.
+    pub edges_before: usize,
+    /// The number of edges after shrinking.
+    pub edges_after: usize,
+    /// The size in bytes of the graph before shrinking, as loaded in memory by NGT.
+    pub graph_size_before: u64,
+    /// The size in bytes of the graph after shrinking, as loaded in memory by NGT.
+    pub graph_size_after: u64,
+}
+
+impl ShrinkReport {
+    /// The number of bytes of memory saved by shrinking the graph, `0` if it grew.
+    pub fn bytes_saved(&self) -> u64 {
+        self.graph_size_before.saturating_sub(self.graph_size_after)
+    }
+}
+
 /// Parameters for [`optimize_anng_edges_number`](optimize_anng_edges_number).
 #[derive(Debug, Clone, PartialEq)]
 pub struct AnngEdgeOptimParams {
@@ -181,6 +777,43 @@ impl Default for AnngRefineParams {
     }
 }
 
+impl AnngRefineParams {
+    /// The epsilon of the searches run for each node, `0.1` by default. A larger
+    /// epsilon finds more accurate neighbors but makes the refinement slower.
+    pub fn epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// The accuracy expected from the searches run for each node, `0` by default to
+    /// use the epsilon instead. Requires the accuracy table of the index.
+    pub fn expected_accuracy(mut self, expected_accuracy: f32) -> Self {
+        self.expected_accuracy = expected_accuracy;
+        self
+    }
+
+    /// The number of edges kept for each node, `0` by default to use the creation
+    /// edge size of the index.
+    pub fn nb_edges(mut self, nb_edges: i32) -> Self {
+        self.nb_edges = nb_edges;
+        self
+    }
+
+    /// The number of edges explored by the searches run for each node, the search
+    /// edge size of the index by default.
+    pub fn edge_size(mut self, edge_size: i32) -> Self {
+        self.edge_size = edge_size;
+        self
+    }
+
+    /// The number of nodes refined at once, `10000` by default. Larger batches are
+    /// faster with many threads but use more memory.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+}
+
 /// Parameters for [`convert_anng_to_onng`](convert_anng_to_onng).
 #[derive(Debug, Clone, PartialEq)]
 pub struct GraphOptimParams {
@@ -211,6 +844,63 @@ impl Default for GraphOptimParams {
     }
 }
 
+/// What is optimized by
+/// [`optimize_anng_search_parameters_with`](optimize_anng_search_parameters_with),
+/// everything by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptimModes {
+    /// The coefficients of the explored edges.
+    pub search_params: bool,
+    /// The memory prefetch offset and size.
+    pub prefetch_params: bool,
+    /// The [accuracy table](build_accuracy_table) of the index.
+    pub accuracy_table: bool,
+}
+
+impl Default for SearchOptimModes {
+    fn default() -> Self {
+        Self {
+            search_params: true,
+            prefetch_params: true,
+            accuracy_table: true,
+        }
+    }
+}
+
+/// Parameters for [`reconstruct_graph`](reconstruct_graph).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconstructParams {
+    pub nb_queries: i32,
+    pub gt_epsilon: f64,
+    pub margin: f64,
+}
+
+impl Default for ReconstructParams {
+    fn default() -> Self {
+        let params = GraphOptimParams::default();
+        Self {
+            nb_queries: params.nb_queries,
+            gt_epsilon: params.gt_epsilon,
+            margin: params.margin,
+        }
+    }
+}
+
+impl From<ReconstructParams> for GraphOptimParams {
+    fn from(params: ReconstructParams) -> Self {
+        // Without outgoing nor incoming edges, NGT keeps the edges of the graph and only
+        // removes its shortcut edges
+        Self {
+            nb_outgoing: 0,
+            nb_incoming: 0,
+            nb_queries: params.nb_queries,
+            gt_epsilon: params.gt_epsilon,
+            margin: params.margin,
+            ..Self::default()
+        }
+    }
+}
+
 struct GraphOptimizer(sys::NGTOptimizer);
 
 impl GraphOptimizer {
@@ -328,6 +1018,7 @@ mod tests {
     use std::error::Error as StdError;
     use std::result::Result as StdResult;
 
+    use half::f16;
     use rand::Rng;
     use tempfile::tempdir;
 
@@ -362,6 +1053,17 @@ mod tests {
         // Further optimize the index
         optimize_anng_search_parameters::<f32, _>(dir.path())?;
 
+        // Only optimize the memory prefetch, for a custom range of accuracies
+        let mut params = GraphOptimParams::default();
+        params.high_accuracy_from = 0.9;
+        params.high_accuracy_to = 0.95;
+        let modes = SearchOptimModes {
+            search_params: false,
+            prefetch_params: true,
+            accuracy_table: false,
+        };
+        optimize_anng_search_parameters_with::<f32, _>(dir.path(), params, modes)?;
+
         dir.close()?;
         Ok(())
     }
@@ -386,10 +1088,312 @@ mod tests {
         // Refine the index
         refine_anng(&mut index, AnngRefineParams::default())?;
 
+        // Refine it again with custom parameters
+        let params = AnngRefineParams::default()
+            .epsilon(0.2)
+            .nb_edges(20)
+            .edge_size(40)
+            .batch_size(100);
+        refine_anng(&mut index, params)?;
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "shared_mem"))]
+    fn test_refine_anng_object_types() -> StdResult<(), Box<dyn StdError>> {
+        // Get temporary directories to store the indexes
+        let dir_u8 = tempdir()?;
+        let dir_f16 = tempdir()?;
+
+        // Create, populate and build u8 and f16 indexes
+        let mut rng = rand::thread_rng();
+        let prop = NgtProperties::<u8>::dimension(3)?;
+        let mut index_u8 = NgtIndex::create(dir_u8.path(), prop)?;
+        let prop = NgtProperties::<f16>::dimension(3)?;
+        let mut index_f16 = NgtIndex::create(dir_f16.path(), prop)?;
+        for _ in 0..1000 {
+            let vec: [u8; 3] = rng.gen();
+            index_u8.insert(vec.to_vec())?;
+            index_f16.insert(vec.iter().map(|&v| f16::from(v)).collect())?;
+        }
+        index_u8.build(4)?;
+        index_f16.build(4)?;
+
+        // Refine them and compare them with themselves
+        refine_anng(&mut index_u8, AnngRefineParams::default())?;
+        refine_anng(&mut index_f16, AnngRefineParams::default())?;
+        let report_u8 = report(&index_u8, &index_u8, ReportParams::default())?;
+        let report_f16 = report(&index_f16, &index_f16, ReportParams::default())?;
+        assert_eq!(report_u8.after.nb_objects, 1000);
+        assert_eq!(report_f16.after.nb_objects, 1000);
+
+        dir_f16.close()?;
+        dir_u8.close()?;
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "shared_mem"))]
+    fn test_reconstruct_graph() -> StdResult<(), Box<dyn StdError>> {
+        // Get temporary directories to store the indexes
+        let dir_in = tempdir()?;
+        let dir_out = tempdir()?;
+        std::fs::remove_dir(dir_out.path())?;
+
+        // Create, populate and build an index
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir_in.path(), prop)?;
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            index.insert(vec![rng.gen(), rng.gen(), rng.gen()])?;
+        }
+        index.build(4)?;
+
+        // Reduce the shortcut edges of the graph
+        let reconstructed = reconstruct_graph(&mut index, dir_out.path(), Default::default())?;
+        assert_eq!(reconstructed.nb_inserted(), 1000);
+        let report = report(&index, &reconstructed, ReportParams::default())?;
+        assert!(report.after.nb_edges <= report.before.nb_edges);
+
+        dir_out.close()?;
+        dir_in.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_accuracy_table() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create, populate and build an index
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            index.insert(vec![rng.gen(), rng.gen(), rng.gen()])?;
+        }
+        index.build(4)?;
+
+        // Searching by accuracy requires the accuracy table
+        let query = [0.5, 0.5, 0.5];
+        assert_eq!(index.accuracy_epsilon(0.9), None);
+        let res = index.search_query(NgtQuery::new(&query).expected_accuracy(0.9));
+        assert!(res.is_err());
+
+        // Build the accuracy table and search by accuracy
+        build_accuracy_table(&mut index)?;
+        assert!(index.accuracy_epsilon(0.9).is_some());
+        let res = index.search_query(NgtQuery::new(&query).size(5).expected_accuracy(0.9))?;
+        assert_eq!(res.len(), 5);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_graph_stats() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create, populate and build an index
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            index.insert(vec![rng.gen(), rng.gen(), rng.gen()])?;
+        }
+        index.build(2)?;
+
+        let stats = graph_stats(&index)?;
+        assert_eq!(stats.nb_nodes, 100);
+        assert_eq!(stats.outdegrees.values().sum::<usize>(), 100);
+        assert_eq!(stats.indegrees.values().sum::<usize>(), 100);
+        assert_eq!(
+            stats.indegrees.get(&0).copied().unwrap_or_default(),
+            stats.nb_detached
+        );
+        assert!(stats.nb_components >= 1);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_cleanup() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create, populate and build an index with churn
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let mut rng = rand::thread_rng();
+        let mut ids = Vec::new();
+        for _ in 0..200 {
+            let vec = vec![rng.gen(), rng.gen(), rng.gen()];
+            ids.push(index.insert_with_ttl(vec, Duration::from_secs(3600))?);
+        }
+        index.build(2)?;
+        for &id in ids.iter().step_by(3) {
+            index.remove(id)?;
+        }
+
+        // A dry run lists the inconsistent nodes without modifying the index
+        let orphans = index.verify()?.orphan_nodes;
+        let report = cleanup(&mut index, true, 2)?;
+        assert!(report.reinserted.is_empty());
+        assert!(report.nodes.len() >= orphans.len());
+        assert_eq!(index.verify()?.orphan_nodes, orphans);
+
+        // Reinsert the inconsistent nodes, the graph is then consistent
+        let deadlines = report
+            .nodes
+            .iter()
+            .map(|&id| index.expires_at(id))
+            .collect::<Vec<_>>();
+        let report = cleanup(&mut index, false, 2)?;
+        assert_eq!(report.reinserted.len(), report.nodes.len());
+        assert!(index.verify()?.dangling_edges.is_empty());
+        for (&(_, id), deadline) in report.reinserted.iter().zip(deadlines) {
+            assert!(index.get_vec(id).is_ok());
+            assert!(deadline.is_some());
+            assert_eq!(index.expires_at(id), deadline);
+        }
+
+        // Indexes not reusing removed ids can only be cleaned up in a dry run
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+        let prop = NgtProperties::<f32>::dimension(3)?.reuse_removed_ids(false)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        index.insert(vec![1.0, 2.0, 3.0])?;
+        index.build(1)?;
+        cleanup(&mut index, true, 1)?;
+        assert!(cleanup(&mut index, false, 1).is_err());
+
         dir.close()?;
         Ok(())
     }
 
+    #[test]
+    fn test_sample_queries() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create, populate and build an index
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let mut rng = rand::thread_rng();
+        for _ in 0..500 {
+            index.insert(vec![rng.gen(), rng.gen(), rng.gen()])?;
+        }
+        index.build(2)?;
+
+        // Sample perturbed queries, deterministically
+        let set = sample_queries(&index, 20, 5, 0.01)?;
+        assert_eq!(set.queries.len(), 20);
+        assert!(set.ground_truth.iter().all(|truth| truth.len() == 5));
+        assert_eq!(set, sample_queries(&index, 20, 5, 0.01)?);
+        assert_ne!(set.queries[0], index.get_vec(1)?);
+
+        // Reuse them for tuning and evaluation
+        let params = index.tune_epsilon(&set.as_slices(), 5, 0.9)?;
+        assert!(params.recall >= 0.9);
+        assert!(set.recall(&index, params.epsilon)? >= 0.9);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "shared_mem"))]
+    fn test_shrink() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+
+        // Create, populate and build an index with many edges
+        let prop = NgtProperties::<f32>::dimension(3)?.creation_edge_size(40)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            index.insert(vec![rng.gen(), rng.gen(), rng.gen()])?;
+        }
+        index.build(4)?;
+
+        // Shrink it in place, leaving the other directories alone
+        let sibling = dir.path().with_extension("shrink");
+        fs::create_dir(&sibling)?;
+        fs::create_dir(dir.path().join(QG_DIR))?;
+        let report = shrink(&mut index, 5)?;
+        assert!(sibling.exists());
+        fs::remove_dir(&sibling)?;
+
+        // A stale quantized index is dropped, and the vectors are kept
+        assert!(!dir.path().join(QG_DIR).exists());
+        assert!(report.edges_after <= 5 * 1000);
+        assert!(report.edges_after < report.edges_before);
+        assert!(report.bytes_saved() > 0);
+        assert_eq!(
+            report.bytes_saved(),
+            report.graph_size_before - report.graph_size_after
+        );
+        assert!(shrink(&mut index, 1).is_err());
+        assert_eq!(index.nb_inserted(), 1000);
+        assert!(index.search(&[0.5; 3], 5, 0.1)?.len() == 5);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "shared_mem"))]
+    fn test_report() -> StdResult<(), Box<dyn StdError>> {
+        // Get temporary directories to store the indexes
+        let dir_before = tempdir()?;
+        let dir_after = tempdir()?;
+
+        // Create two identical indexes for vectors of dimension 3
+        let prop = NgtProperties::<f32>::dimension(3)?;
+        let mut index_before = NgtIndex::create(dir_before.path(), prop.try_clone()?)?;
+        let mut index_after = NgtIndex::create(dir_after.path(), prop)?;
+
+        // Populate and build both indexes
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let vec = vec![rng.gen(), rng.gen(), rng.gen()];
+            index_before.insert(vec.clone())?;
+            index_after.insert(vec)?;
+        }
+        index_before.build(4)?;
+        index_after.build(4)?;
+
+        // Refine only one of them and compare
+        refine_anng(&mut index_after, AnngRefineParams::default())?;
+        let report = report(&index_before, &index_after, ReportParams::default())?;
+        assert_eq!(report.before.nb_objects, 1000);
+        assert_eq!(report.after.nb_objects, 1000);
+        assert!(report.before.avg_degree > 0.);
+        assert!((0. ..=1.).contains(&report.after.recall));
+
+        dir_after.close()?;
+        dir_before.close()?;
+        Ok(())
+    }
+
     #[ignore]
     #[test]
     #[cfg(not(feature = "shared_mem"))]
@@ -433,4 +1437,38 @@ mod tests {
         dir_in.close()?;
         Ok(())
     }
+
+    #[ignore]
+    #[test]
+    #[cfg(not(feature = "shared_mem"))]
+    fn test_optimize_anng_in_place() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the ANNG index
+        let dir_in = tempdir()?;
+
+        // Create an index for vectors of dimension 3 with cosine distance
+        let prop = NgtProperties::<f32>::dimension(3)?
+            .distance_type(NgtDistance::Cosine)?
+            .creation_edge_size(100)?;
+        let mut index = NgtIndex::create(dir_in.path(), prop)?;
+
+        // Populate the index, optimize it without reopening it and build it
+        let mut rng = rand::thread_rng();
+        for _ in 0..25_000 {
+            index.insert(vec![rng.gen(); 3])?;
+        }
+        optimize_anng_edges_number_in_place(&mut index, AnngEdgeOptimParams::default())?;
+        index.build(4)?;
+
+        // Create an output directory for the ONNG index
+        let dir_out = tempdir()?;
+        std::fs::remove_dir(dir_out.path())?;
+
+        // Convert the open ANNG to an ONNG
+        let onng = convert_anng_index_to_onng(&mut index, dir_out.path(), Default::default())?;
+        assert_eq!(onng.nb_inserted(), index.nb_inserted());
+
+        dir_out.close()?;
+        dir_in.close()?;
+        Ok(())
+    }
 }
@@ -1,3 +1,5 @@
+use std::fs;
+use std::path::Path;
 use std::ptr;
 use std::{convert::TryFrom, marker::PhantomData};
 
@@ -6,9 +8,11 @@ use ngt_sys as sys;
 use num_enum::TryFromPrimitive;
 use scopeguard::defer;
 
+use super::config::Config;
 use crate::error::{make_err, Result};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum NgtObject {
     Uint8 = 1,
@@ -20,8 +24,22 @@ mod private {
     pub trait Sealed {}
 }
 
-pub trait NgtObjectType: private::Sealed {
+pub trait NgtObjectType: private::Sealed + Copy {
     fn as_obj() -> NgtObject;
+
+    #[doc(hidden)]
+    fn to_f64(self) -> f64;
+
+    #[doc(hidden)]
+    fn from_f64(v: f64) -> Self;
+
+    /// Convert the value, if it can be represented.
+    #[doc(hidden)]
+    fn try_from_f64(v: f64) -> Option<Self>;
+
+    /// Convert the value, if it can be represented exactly.
+    #[doc(hidden)]
+    fn try_from_i32(v: i32) -> Option<Self>;
 }
 
 impl private::Sealed for f32 {}
@@ -29,6 +47,24 @@ impl NgtObjectType for f32 {
     fn as_obj() -> NgtObject {
         NgtObject::Float
     }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn from_f64(v: f64) -> Self {
+        v as f32
+    }
+
+    fn try_from_f64(v: f64) -> Option<Self> {
+        let converted = v as f32;
+        converted.is_finite().then_some(converted)
+    }
+
+    fn try_from_i32(v: i32) -> Option<Self> {
+        let converted = v as f32;
+        (converted as f64 == v as f64).then_some(converted)
+    }
 }
 
 impl private::Sealed for u8 {}
@@ -36,6 +72,23 @@ impl NgtObjectType for u8 {
     fn as_obj() -> NgtObject {
         NgtObject::Uint8
     }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn from_f64(v: f64) -> Self {
+        v as u8
+    }
+
+    fn try_from_f64(v: f64) -> Option<Self> {
+        let in_range = v.fract() == 0.0 && (0.0..=u8::MAX as f64).contains(&v);
+        in_range.then_some(v as u8)
+    }
+
+    fn try_from_i32(v: i32) -> Option<Self> {
+        u8::try_from(v).ok()
+    }
 }
 
 impl private::Sealed for f16 {}
@@ -43,9 +96,41 @@ impl NgtObjectType for f16 {
     fn as_obj() -> NgtObject {
         NgtObject::Float16
     }
+
+    fn to_f64(self) -> f64 {
+        f16::to_f64(self)
+    }
+
+    fn from_f64(v: f64) -> Self {
+        f16::from_f64(v)
+    }
+
+    fn try_from_f64(v: f64) -> Option<Self> {
+        let converted = f16::from_f64(v);
+        converted.is_finite().then_some(converted)
+    }
+
+    fn try_from_i32(v: i32) -> Option<Self> {
+        let converted = f16::from_f64(v as f64);
+        (converted.to_f64() == v as f64).then_some(converted)
+    }
 }
 
+/// The distance function of an NGT index, which determines the unit of the
+/// distances of search results:
+///
+/// - `L2` and `NormalizedL2` give the (non-squared) euclidean distance.
+/// - `Cosine` and `NormalizedCosine` give `1 - cos(θ)`, in `[0, 2]`.
+/// - `Angle` and `NormalizedAngle` give the angle `θ` in radians, in `[0, π]`.
+/// - `InnerProduct` ranks vectors by decreasing inner product with the query, which
+///   performs a maximum inner product search without augmenting the vectors.
+///
+/// Normalized variants normalize vectors when they are inserted. Use
+/// [`to_l2`](NgtDistance::to_l2) and
+/// [`to_cosine_similarity`](NgtDistance::to_cosine_similarity) to get thresholds
+/// that don't depend on the distance function.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum NgtDistance {
     L1 = 0,
@@ -63,6 +148,300 @@ pub enum NgtDistance {
     Lorentz = 101,
 }
 
+impl NgtDistance {
+    /// Convert a distance to the cosine similarity of the vectors, if it can be
+    /// derived from this distance function.
+    pub fn to_cosine_similarity(self, distance: f32) -> Option<f32> {
+        match self {
+            NgtDistance::Cosine | NgtDistance::NormalizedCosine => Some(1. - distance),
+            NgtDistance::Angle | NgtDistance::NormalizedAngle => Some(distance.cos()),
+            NgtDistance::NormalizedL2 => Some(1. - distance * distance / 2.),
+            _ => None,
+        }
+    }
+
+    /// Convert a distance to the euclidean distance of the vectors, if it can be
+    /// derived from this distance function.
+    pub fn to_l2(self, distance: f32) -> Option<f32> {
+        match self {
+            NgtDistance::L2 | NgtDistance::NormalizedL2 => Some(distance),
+            // Normalized vectors are unit vectors, so that |a - b|² = 2 - 2cos(θ)
+            NgtDistance::NormalizedCosine | NgtDistance::NormalizedAngle => self
+                .to_cosine_similarity(distance)
+                .map(|cos| (2. - 2. * cos).max(0.).sqrt()),
+            _ => None,
+        }
+    }
+}
+
+/// The structure of an NGT index, a graph and a VP-tree (the default), or only a graph.
+///
+/// The VP-tree is used to find the search seeds, a graph-only index saves its memory
+/// and build time at the cost of seeds found by sampling the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NgtIndexType {
+    GraphAndTree,
+    Graph,
+}
+
+impl NgtIndexType {
+    pub(crate) fn read(dir: &Path) -> Result<Self> {
+        match read_property_file(dir, "IndexType")?.as_deref() {
+            Some("GraphAndTree") => Ok(NgtIndexType::GraphAndTree),
+            Some("Graph") => Ok(NgtIndexType::Graph),
+            value => Err(format!("Invalid index type {value:?} in {dir:?}").into()),
+        }
+    }
+}
+
+/// The kind of graph built by an NGT index.
+///
+/// See NGT's [graph types][ngt-graph] for more details.
+///
+/// [ngt-graph]: https://github.com/yahoojapan/NGT/blob/main/bin/ngt/README.md#create
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NgtGraphType {
+    Anng,
+    Knng,
+    Bknng,
+    Onng,
+    Ianng,
+    Dnng,
+    Ranng,
+    Rianng,
+}
+
+impl NgtGraphType {
+    pub(crate) fn read(dir: &Path) -> Result<Self> {
+        match read_property_file(dir, "GraphType")?.as_deref() {
+            Some("ANNG") => Ok(NgtGraphType::Anng),
+            Some("KNNG") => Ok(NgtGraphType::Knng),
+            Some("BKNNG") => Ok(NgtGraphType::Bknng),
+            Some("ONNG") => Ok(NgtGraphType::Onng),
+            Some("IANNG") => Ok(NgtGraphType::Ianng),
+            Some("DNNG") => Ok(NgtGraphType::Dnng),
+            Some("RANNG") => Ok(NgtGraphType::Ranng),
+            Some("RIANNG") => Ok(NgtGraphType::Rianng),
+            value => Err(format!("Invalid graph type {value:?} in {dir:?}").into()),
+        }
+    }
+}
+
+/// How the search seeds are chosen when an NGT index has no VP-tree, see
+/// [`NgtProperties::seed_type`][].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NgtSeedType {
+    RandomNodes,
+    FixedNodes,
+    FirstNode,
+    AllLeafNodes,
+    None,
+}
+
+impl NgtSeedType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            NgtSeedType::RandomNodes => "RandomNodes",
+            NgtSeedType::FixedNodes => "FixedNodes",
+            NgtSeedType::FirstNode => "FirstNode",
+            NgtSeedType::AllLeafNodes => "AllLeafNodes",
+            NgtSeedType::None => "None",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "RandomNodes" => Some(NgtSeedType::RandomNodes),
+            "FixedNodes" => Some(NgtSeedType::FixedNodes),
+            "FirstNode" => Some(NgtSeedType::FirstNode),
+            "AllLeafNodes" => Some(NgtSeedType::AllLeafNodes),
+            "None" => Some(NgtSeedType::None),
+            _ => None,
+        }
+    }
+}
+
+/// Tuning properties that NGT's C API doesn't expose, they are written to the
+/// property file when the index is created. Unset ones keep NGT's defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct Tuning {
+    pub(crate) batch_size_for_creation: Option<usize>,
+    pub(crate) truncation_threshold: Option<usize>,
+    pub(crate) insertion_radius_coefficient: Option<f32>,
+    pub(crate) path_adjustment_interval: Option<usize>,
+    pub(crate) dynamic_edge_size_base: Option<usize>,
+    pub(crate) dynamic_edge_size_rate: Option<usize>,
+    pub(crate) seed_size: Option<usize>,
+    pub(crate) seed_type: Option<NgtSeedType>,
+}
+
+impl Tuning {
+    const BATCH_SIZE_FOR_CREATION: &'static str = "BatchSizeForCreation";
+    const TRUNCATION_THRESHOLD: &'static str = "IncrementalEdgeSizeLimitForTruncation";
+    const INSERTION_RADIUS_COEFFICIENT: &'static str = "InsertionRadiusCoefficient";
+    const PATH_ADJUSTMENT_INTERVAL: &'static str = "PathAdjustmentInterval";
+    const DYNAMIC_EDGE_SIZE_BASE: &'static str = "DynamicEdgeSizeBase";
+    const DYNAMIC_EDGE_SIZE_RATE: &'static str = "DynamicEdgeSizeRate";
+    const SEED_SIZE: &'static str = "SeedSize";
+    const SEED_TYPE: &'static str = "SeedType";
+
+    pub(crate) fn read(dir: &Path) -> Result<Self> {
+        fn parse<V: std::str::FromStr>(dir: &Path, key: &str) -> Result<Option<V>> {
+            read_property_file(dir, key)?
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid {key} {value:?} in {dir:?}").into())
+                })
+                .transpose()
+        }
+
+        let seed_type = read_property_file(dir, Self::SEED_TYPE)?
+            .map(|value| {
+                NgtSeedType::parse(&value)
+                    .ok_or_else(|| format!("Invalid seed type {value:?} in {dir:?}"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            batch_size_for_creation: parse(dir, Self::BATCH_SIZE_FOR_CREATION)?,
+            truncation_threshold: parse(dir, Self::TRUNCATION_THRESHOLD)?,
+            insertion_radius_coefficient: parse(dir, Self::INSERTION_RADIUS_COEFFICIENT)?,
+            path_adjustment_interval: parse(dir, Self::PATH_ADJUSTMENT_INTERVAL)?,
+            dynamic_edge_size_base: parse(dir, Self::DYNAMIC_EDGE_SIZE_BASE)?,
+            dynamic_edge_size_rate: parse(dir, Self::DYNAMIC_EDGE_SIZE_RATE)?,
+            seed_size: parse(dir, Self::SEED_SIZE)?,
+            seed_type,
+        })
+    }
+
+    pub(crate) fn write(&self, dir: &Path) -> Result<()> {
+        let values = [
+            (
+                Self::BATCH_SIZE_FOR_CREATION,
+                self.batch_size_for_creation.map(|v| v.to_string()),
+            ),
+            (
+                Self::TRUNCATION_THRESHOLD,
+                self.truncation_threshold.map(|v| v.to_string()),
+            ),
+            (
+                Self::INSERTION_RADIUS_COEFFICIENT,
+                self.insertion_radius_coefficient.map(|v| v.to_string()),
+            ),
+            (
+                Self::PATH_ADJUSTMENT_INTERVAL,
+                self.path_adjustment_interval.map(|v| v.to_string()),
+            ),
+            (
+                Self::DYNAMIC_EDGE_SIZE_BASE,
+                self.dynamic_edge_size_base.map(|v| v.to_string()),
+            ),
+            (
+                Self::DYNAMIC_EDGE_SIZE_RATE,
+                self.dynamic_edge_size_rate.map(|v| v.to_string()),
+            ),
+            (Self::SEED_SIZE, self.seed_size.map(|v| v.to_string())),
+            (
+                Self::SEED_TYPE,
+                self.seed_type.map(|v| v.as_str().to_string()),
+            ),
+        ];
+        for (key, value) in values {
+            if let Some(value) = value {
+                write_property_file(dir, key, &value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The accuracy table generated by NGT's optimizer, mapping search epsilons to the
+/// accuracy they reach, see [`build_accuracy_table`](crate::optim::build_accuracy_table).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AccuracyTable(Vec<(f32, f32)>);
+
+impl AccuracyTable {
+    const ACCURACY_TABLE: &'static str = "AccuracyTable";
+
+    pub(crate) fn read(dir: &Path) -> Result<Option<Self>> {
+        let Some(value) = read_property_file(dir, Self::ACCURACY_TABLE)? else {
+            return Ok(None);
+        };
+        let table = value
+            .split(',')
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                entry
+                    .split_once(':')
+                    .and_then(|(eps, acc)| Some((eps.parse().ok()?, acc.parse().ok()?)))
+                    .ok_or_else(|| format!("Invalid accuracy table {value:?} in {dir:?}").into())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if table.len() < 2 {
+            Err(format!("Invalid accuracy table {value:?} in {dir:?}"))?
+        }
+        Ok(Some(Self(table)))
+    }
+
+    /// The epsilon reaching the expected accuracy, interpolated between the two
+    /// closest entries of the table like NGT does.
+    pub(crate) fn epsilon(&self, accuracy: f32) -> f32 {
+        let accuracy = accuracy.min(1.);
+        let table = &self.0;
+        let upper = table
+            .iter()
+            .position(|&(_, acc)| acc >= accuracy)
+            .unwrap_or(table.len() - 1)
+            .max(1);
+        let ((eps_lo, acc_lo), (eps_up, acc_up)) = (table[upper - 1], table[upper]);
+        if acc_up == acc_lo {
+            return eps_up;
+        }
+        eps_lo + (accuracy - acc_lo) * (eps_up - eps_lo) / (acc_up - acc_lo)
+    }
+}
+
+const PROPERTY_FILE: &str = "prf";
+
+/// Read the value of `key` in the property file of the index directory.
+///
+/// This gives access to the properties that aren't exposed by NGT's C API.
+fn read_property_file(dir: &Path, key: &str) -> Result<Option<String>> {
+    let content = fs::read_to_string(dir.join(PROPERTY_FILE))?;
+    let value = content
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .find(|(k, _)| *k == key)
+        .map(|(_, value)| value.to_string());
+    Ok(value)
+}
+
+/// Write the value of `key` in the property file of the index directory, the index
+/// must be closed for the change to be taken into account when reopening it.
+fn write_property_file(dir: &Path, key: &str, value: &str) -> Result<()> {
+    let path = dir.join(PROPERTY_FILE);
+    let mut found = false;
+    let mut content = fs::read_to_string(&path)?
+        .lines()
+        .map(|line| match line.split_once('\t') {
+            Some((k, _)) if k == key => {
+                found = true;
+                format!("{key}\t{value}\n")
+            }
+            _ => format!("{line}\n"),
+        })
+        .collect::<String>();
+    if !found {
+        content.push_str(&format!("{key}\t{value}\n"));
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
 #[derive(Debug)]
 pub struct NgtProperties<T> {
     pub(crate) dimension: i32,
@@ -70,6 +449,10 @@ pub struct NgtProperties<T> {
     pub(crate) search_edge_size: i16,
     pub(crate) object_type: NgtObject,
     pub(crate) distance_type: NgtDistance,
+    pub(crate) index_type: NgtIndexType,
+    pub(crate) graph_type: NgtGraphType,
+    pub(crate) config: Config,
+    pub(crate) tuning: Tuning,
     pub(crate) raw_prop: sys::NGTProperty,
     _marker: PhantomData<T>,
 }
@@ -109,6 +492,10 @@ where
                 search_edge_size,
                 object_type,
                 distance_type,
+                index_type: NgtIndexType::GraphAndTree,
+                graph_type: NgtGraphType::Anng,
+                config: Config::default(),
+                tuning: Tuning::default(),
                 raw_prop,
                 _marker: PhantomData,
             })
@@ -130,6 +517,7 @@ where
             Self::set_search_edge_size(raw_prop, self.search_edge_size)?;
             Self::set_object_type(raw_prop, self.object_type)?;
             Self::set_distance_type(raw_prop, self.distance_type)?;
+            Self::set_graph_type(raw_prop, self.graph_type)?;
 
             Ok(Self {
                 dimension: self.dimension,
@@ -137,6 +525,10 @@ where
                 search_edge_size: self.search_edge_size,
                 object_type: self.object_type,
                 distance_type: self.distance_type,
+                index_type: self.index_type,
+                graph_type: self.graph_type,
+                config: self.config,
+                tuning: self.tuning,
                 raw_prop,
                 _marker: PhantomData,
             })
@@ -190,6 +582,10 @@ where
                 search_edge_size,
                 object_type,
                 distance_type,
+                index_type: NgtIndexType::GraphAndTree,
+                graph_type: NgtGraphType::Anng,
+                config: Config::default(),
+                tuning: Tuning::default(),
                 raw_prop,
                 _marker: PhantomData,
             })
@@ -274,6 +670,256 @@ where
         Ok(self)
     }
 
+    /// Set the structure of the index, see [`NgtIndexType`][].
+    pub fn index_type(mut self, index_type: NgtIndexType) -> Result<Self> {
+        self.index_type = index_type;
+        Ok(self)
+    }
+
+    /// Set the kind of graph to build, see [`NgtGraphType`][].
+    pub fn graph_type(mut self, graph_type: NgtGraphType) -> Result<Self> {
+        self.graph_type = graph_type;
+        unsafe { Self::set_graph_type(self.raw_prop, graph_type)? };
+        Ok(self)
+    }
+
+    unsafe fn set_graph_type(raw_prop: sys::NGTProperty, graph_type: NgtGraphType) -> Result<()> {
+        let ebuf = sys::ngt_create_error_object();
+        defer! { sys::ngt_destroy_error_object(ebuf); }
+
+        let ok = match graph_type {
+            NgtGraphType::Anng => sys::ngt_set_property_graph_type_anng(raw_prop, ebuf),
+            NgtGraphType::Knng => sys::ngt_set_property_graph_type_knng(raw_prop, ebuf),
+            NgtGraphType::Bknng => sys::ngt_set_property_graph_type_bknng(raw_prop, ebuf),
+            NgtGraphType::Onng => sys::ngt_set_property_graph_type_onng(raw_prop, ebuf),
+            NgtGraphType::Ianng => sys::ngt_set_property_graph_type_ianng(raw_prop, ebuf),
+            NgtGraphType::Dnng => sys::ngt_set_property_graph_type_dnng(raw_prop, ebuf),
+            NgtGraphType::Ranng => sys::ngt_set_property_graph_type_ranng(raw_prop, ebuf),
+            NgtGraphType::Rianng => sys::ngt_set_property_graph_type_rianng(raw_prop, ebuf),
+        };
+        if !ok {
+            Err(make_err(ebuf))?
+        }
+
+        Ok(())
+    }
+
+    /// Set the number of vectors under which searches use an exact linear scan
+    /// instead of the index, which is both faster and exact for small indexes and
+    /// doesn't require vectors to be [`built`](crate::NgtIndex::build). The
+    /// [`soft removed`](crate::NgtIndex::soft_remove) vectors aren't counted.
+    ///
+    /// Defaults to `0`, which disables linear scans.
+    pub fn linear_search_threshold(mut self, threshold: usize) -> Result<Self> {
+        self.config.linear_search_threshold = threshold;
+        Ok(self)
+    }
+
+    /// Set the epsilon of the queries created by [`NgtIndex::query`](crate::NgtIndex::query),
+    /// see [`NgtIndex::default_epsilon`](crate::NgtIndex::default_epsilon).
+    ///
+    /// Defaults to `0.1`.
+    pub fn default_epsilon(mut self, epsilon: f32) -> Result<Self> {
+        self.config.epsilon = epsilon;
+        Ok(self)
+    }
+
+    /// Set whether NGT may assign the ids of removed vectors to new vectors.
+    ///
+    /// NGT always recycles the ids of the vectors it removes, so when this is
+    /// disabled [`remove`](crate::NgtIndex::remove) falls back to a
+    /// [`soft removal`](crate::NgtIndex::soft_remove) and removed vectors stay stored.
+    ///
+    /// Defaults to `true`.
+    pub fn reuse_removed_ids(mut self, reuse: bool) -> Result<Self> {
+        self.config.reuse_removed_ids = reuse;
+        Ok(self)
+    }
+
+    /// Set whether the insertion time of each vector is recorded, see
+    /// [`inserted_at`](crate::NgtIndex::inserted_at) and
+    /// [`was_recycled`](crate::NgtIndex::was_recycled).
+    ///
+    /// They are held in memory and rewritten to a sidecar file of the index directory
+    /// by each [`persist`](crate::NgtIndex::persist), which is costly for large
+    /// indexes. The time of the last [`build`](crate::NgtIndex::build) is always
+    /// recorded.
+    ///
+    /// Defaults to `false`.
+    pub fn record_timestamps(mut self, record: bool) -> Result<Self> {
+        self.config.record_timestamps = record;
+        Ok(self)
+    }
+
+    /// Set the number of vectors inserted in the graph at once by a
+    /// [`build`](crate::NgtIndex::build).
+    pub fn batch_size_for_creation(mut self, size: usize) -> Result<Self> {
+        if size == 0 {
+            Err("The batch size for creation must be greater than 0".to_string())?
+        }
+        i32::try_from(size)?;
+        self.tuning.batch_size_for_creation = Some(size);
+        Ok(self)
+    }
+
+    /// Set the number of edges of a node above which its edges are truncated when
+    /// inserting vectors.
+    ///
+    /// Defaults to `0`, which disables truncation.
+    pub fn truncation_threshold(mut self, threshold: usize) -> Result<Self> {
+        i32::try_from(threshold)?;
+        self.tuning.truncation_threshold = Some(threshold);
+        Ok(self)
+    }
+
+    /// Set the coefficient of the search radius used to find the neighbors of
+    /// inserted vectors, that is `1 + epsilon` of the insertion search.
+    pub fn insertion_radius_coefficient(mut self, coefficient: f32) -> Result<Self> {
+        if !(coefficient.is_finite() && coefficient >= 1.) {
+            Err(format!(
+                "The insertion radius coefficient must be at least 1, got {coefficient}"
+            ))?
+        }
+        self.tuning.insertion_radius_coefficient = Some(coefficient);
+        Ok(self)
+    }
+
+    /// Set the number of insertions between two adjustments of the graph paths.
+    ///
+    /// Defaults to `0`, which disables path adjustments.
+    pub fn path_adjustment_interval(mut self, interval: usize) -> Result<Self> {
+        i32::try_from(interval)?;
+        self.tuning.path_adjustment_interval = Some(interval);
+        Ok(self)
+    }
+
+    /// Set the base of the number of edges explored when searching, which grows with
+    /// the [`dynamic_edge_size_rate`](NgtProperties::dynamic_edge_size_rate).
+    pub fn dynamic_edge_size_base(mut self, base: usize) -> Result<Self> {
+        i16::try_from(base)?;
+        self.tuning.dynamic_edge_size_base = Some(base);
+        Ok(self)
+    }
+
+    /// Set the growth rate of the number of edges explored when searching, see
+    /// [`dynamic_edge_size_base`](NgtProperties::dynamic_edge_size_base).
+    pub fn dynamic_edge_size_rate(mut self, rate: usize) -> Result<Self> {
+        i16::try_from(rate)?;
+        self.tuning.dynamic_edge_size_rate = Some(rate);
+        Ok(self)
+    }
+
+    /// Set the number of seeds a search starts from.
+    pub fn seed_size(mut self, size: usize) -> Result<Self> {
+        if size == 0 {
+            Err("The seed size must be greater than 0".to_string())?
+        }
+        i16::try_from(size)?;
+        self.tuning.seed_size = Some(size);
+        Ok(self)
+    }
+
+    /// Set how the search seeds are chosen, see [`NgtSeedType`][].
+    pub fn seed_type(mut self, seed_type: NgtSeedType) -> Result<Self> {
+        self.tuning.seed_type = Some(seed_type);
+        Ok(self)
+    }
+
+    /// The dimension of the vectors.
+    pub fn get_dimension(&self) -> usize {
+        self.dimension as usize
+    }
+
+    /// The number of edges of each node when creating the graph.
+    pub fn get_creation_edge_size(&self) -> usize {
+        self.creation_edge_size as usize
+    }
+
+    /// The number of edges explored from each node when searching.
+    pub fn get_search_edge_size(&self) -> usize {
+        self.search_edge_size as usize
+    }
+
+    /// The type of the vectors, determined by `T`.
+    pub fn get_object_type(&self) -> NgtObject {
+        self.object_type
+    }
+
+    /// The distance function, see [`NgtDistance`][].
+    pub fn get_distance_type(&self) -> NgtDistance {
+        self.distance_type
+    }
+
+    /// The structure of the index, see [`NgtIndexType`][].
+    pub fn get_index_type(&self) -> NgtIndexType {
+        self.index_type
+    }
+
+    /// The kind of graph to build, see [`NgtGraphType`][].
+    pub fn get_graph_type(&self) -> NgtGraphType {
+        self.graph_type
+    }
+
+    /// The number of vectors under which searches use an exact linear scan, see
+    /// [`linear_search_threshold`](NgtProperties::linear_search_threshold).
+    pub fn get_linear_search_threshold(&self) -> usize {
+        self.config.linear_search_threshold
+    }
+
+    /// The epsilon of the queries created by the index, see
+    /// [`default_epsilon`](NgtProperties::default_epsilon).
+    pub fn get_default_epsilon(&self) -> f32 {
+        self.config.epsilon
+    }
+
+    /// Whether NGT may assign the ids of removed vectors to new vectors, see
+    /// [`reuse_removed_ids`](NgtProperties::reuse_removed_ids).
+    pub fn get_reuse_removed_ids(&self) -> bool {
+        self.config.reuse_removed_ids
+    }
+
+    /// Whether the insertion time of each vector is recorded, see
+    /// [`record_timestamps`](NgtProperties::record_timestamps).
+    pub fn get_record_timestamps(&self) -> bool {
+        self.config.record_timestamps
+    }
+
+    /// Check that the properties are coherent, which is done when creating an index.
+    ///
+    /// The following is rejected:
+    ///   - A dimension or a creation edge size of zero.
+    ///   - [`Hamming`](NgtDistance::Hamming) and [`Jaccard`](NgtDistance::Jaccard)
+    ///     distances with other objects than `u8`, as they compare bits.
+    ///   - A [`default_epsilon`](NgtProperties::default_epsilon) that isn't finite.
+    ///
+    /// Note that a search edge size lower than the creation edge size is valid, for
+    /// instance before converting an ANNG to an ONNG.
+    pub fn validate(&self) -> Result<()> {
+        if self.dimension == 0 {
+            Err("The dimension must be greater than 0".to_string())?
+        }
+        if self.creation_edge_size == 0 {
+            Err("The creation edge size must be greater than 0".to_string())?
+        }
+        if matches!(
+            self.distance_type,
+            NgtDistance::Hamming | NgtDistance::Jaccard
+        ) && self.object_type != NgtObject::Uint8
+        {
+            Err(format!(
+                "The {:?} distance requires u8 objects, not {:?}",
+                self.distance_type, self.object_type
+            ))?
+        }
+        if !self.config.epsilon.is_finite() {
+            Err(format!(
+                "The default epsilon {} must be finite",
+                self.config.epsilon
+            ))?
+        }
+        Ok(())
+    }
+
     unsafe fn set_distance_type(
         raw_prop: sys::NGTProperty,
         distance_type: NgtDistance,
@@ -353,6 +999,92 @@ where
     }
 }
 
+/// The settings of [`NgtProperties`][] as (de)serialized, only the dimension is
+/// required when deserializing.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PropertiesRepr {
+    dimension: usize,
+    creation_edge_size: Option<usize>,
+    search_edge_size: Option<usize>,
+    distance_type: Option<NgtDistance>,
+    index_type: Option<NgtIndexType>,
+    graph_type: Option<NgtGraphType>,
+    linear_search_threshold: Option<usize>,
+    default_epsilon: Option<f32>,
+    reuse_removed_ids: Option<bool>,
+    record_timestamps: Option<bool>,
+}
+
+#[cfg(feature = "serde")]
+impl PropertiesRepr {
+    fn into_properties<T: NgtObjectType>(self) -> Result<NgtProperties<T>> {
+        let mut prop = NgtProperties::dimension(self.dimension)?;
+        if let Some(size) = self.creation_edge_size {
+            prop = prop.creation_edge_size(size)?;
+        }
+        if let Some(size) = self.search_edge_size {
+            prop = prop.search_edge_size(size)?;
+        }
+        if let Some(distance_type) = self.distance_type {
+            prop = prop.distance_type(distance_type)?;
+        }
+        if let Some(index_type) = self.index_type {
+            prop = prop.index_type(index_type)?;
+        }
+        if let Some(graph_type) = self.graph_type {
+            prop = prop.graph_type(graph_type)?;
+        }
+        if let Some(threshold) = self.linear_search_threshold {
+            prop = prop.linear_search_threshold(threshold)?;
+        }
+        if let Some(epsilon) = self.default_epsilon {
+            prop = prop.default_epsilon(epsilon)?;
+        }
+        if let Some(reuse) = self.reuse_removed_ids {
+            prop = prop.reuse_removed_ids(reuse)?;
+        }
+        if let Some(record) = self.record_timestamps {
+            prop = prop.record_timestamps(record)?;
+        }
+        Ok(prop)
+    }
+}
+
+/// The object type isn't serialized, it is determined by `T`.
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for NgtProperties<T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let repr = PropertiesRepr {
+            dimension: self.dimension as usize,
+            creation_edge_size: Some(self.creation_edge_size as usize),
+            search_edge_size: Some(self.search_edge_size as usize),
+            distance_type: Some(self.distance_type),
+            index_type: Some(self.index_type),
+            graph_type: Some(self.graph_type),
+            linear_search_threshold: Some(self.config.linear_search_threshold),
+            default_epsilon: Some(self.config.epsilon),
+            reuse_removed_ids: Some(self.config.reuse_removed_ids),
+            record_timestamps: Some(self.config.record_timestamps),
+        };
+        serde::Serialize::serialize(&repr, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: NgtObjectType> serde::Deserialize<'de> for NgtProperties<T> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let repr = <PropertiesRepr as serde::Deserialize>::deserialize(deserializer)?;
+        repr.into_properties().map_err(serde::de::Error::custom)
+    }
+}
+
 impl<T> Drop for NgtProperties<T> {
     fn drop(&mut self) {
         if !self.raw_prop.is_null() {
@@ -361,3 +1093,33 @@ impl<T> Drop for NgtProperties<T> {
         }
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use std::error::Error as StdError;
+    use std::result::Result as StdResult;
+
+    use super::*;
+
+    #[test]
+    fn test_properties_serde() -> StdResult<(), Box<dyn StdError>> {
+        // Missing settings get their default value
+        let json = r#"{"dimension": 3, "distance_type": "Cosine", "default_epsilon": 0.2}"#;
+        let prop: NgtProperties<f32> = serde_json::from_str(json)?;
+        assert_eq!(prop.dimension, 3);
+        assert_eq!(prop.creation_edge_size, 10);
+        assert_eq!(prop.distance_type, NgtDistance::Cosine);
+        assert_eq!(prop.config.epsilon, 0.2);
+
+        // Properties round trip
+        let json = serde_json::to_string(&prop.try_clone()?.search_edge_size(50)?)?;
+        let prop: NgtProperties<f32> = serde_json::from_str(&json)?;
+        assert_eq!(prop.search_edge_size, 50);
+        assert_eq!(prop.distance_type, NgtDistance::Cosine);
+
+        // Invalid settings are rejected
+        assert!(serde_json::from_str::<NgtProperties<f32>>(r#"{"dimension": -1}"#).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,210 @@
+use std::{panic, thread};
+
+use super::{NgtIndex, NgtObjectType};
+use crate::error::Result;
+use crate::{SearchResult, VecId};
+
+/// Decides which shard of a [`ShardedIndex`][] a vector is inserted into.
+///
+/// It is implemented by closures taking the vector and the number of shards.
+pub trait ShardingPolicy<T> {
+    /// The index of the shard the vector goes to, lower than `nb_shards`.
+    fn shard(&mut self, vec: &[T], nb_shards: usize) -> usize;
+}
+
+impl<T, F> ShardingPolicy<T> for F
+where
+    F: FnMut(&[T], usize) -> usize,
+{
+    fn shard(&mut self, vec: &[T], nb_shards: usize) -> usize {
+        self(vec, nb_shards)
+    }
+}
+
+/// Inserts vectors into each shard in turn.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoundRobin {
+    next: usize,
+}
+
+impl<T> ShardingPolicy<T> for RoundRobin {
+    fn shard(&mut self, _vec: &[T], nb_shards: usize) -> usize {
+        let shard = self.next % nb_shards;
+        self.next = shard + 1;
+        shard
+    }
+}
+
+/// Multiple [`NgtIndex`][] searched as one.
+///
+/// Each shard keeps its own ids, so vectors are identified by their shard index along
+/// with their id within the shard.
+#[derive(Debug)]
+pub struct ShardedIndex<T, P = RoundRobin> {
+    shards: Vec<NgtIndex<T>>,
+    policy: P,
+}
+
+impl<T, P> ShardedIndex<T, P>
+where
+    T: NgtObjectType,
+    P: ShardingPolicy<T>,
+{
+    /// Combine the given shards, inserts are routed to them by the sharding policy.
+    ///
+    /// The shards must all have the same dimension.
+    pub fn new(shards: Vec<NgtIndex<T>>, policy: P) -> Result<Self> {
+        let Some(first) = shards.first() else {
+            Err("A sharded index needs at least one shard".to_string())?
+        };
+        if shards.iter().any(|s| s.dimension() != first.dimension()) {
+            Err("The shards must all have the same dimension".to_string())?
+        }
+        Ok(Self { shards, policy })
+    }
+
+    /// Insert the specified vector into the shard chosen by the sharding policy, and
+    /// return the shard index along with the vector id.
+    ///
+    /// **The method [`build`](ShardedIndex::build) must be called after inserting
+    /// vectors**.
+    pub fn insert(&mut self, vec: Vec<T>) -> Result<(usize, VecId)> {
+        let nb_shards = self.shards.len();
+        let shard = self.policy.shard(&vec, nb_shards);
+        if shard >= nb_shards {
+            Err(format!("Shard {shard} out of {nb_shards} shards"))?
+        }
+        let id = self.shards[shard].insert(vec)?;
+        Ok((shard, id))
+    }
+
+    /// Build all the shards, see [`NgtIndex::build`][].
+    pub fn build(&mut self, num_threads: usize) -> Result<()> {
+        for shard in &mut self.shards {
+            shard.build(num_threads)?;
+        }
+        Ok(())
+    }
+
+    /// Persist all the shards, see [`NgtIndex::persist`][].
+    pub fn persist(&mut self) -> Result<()> {
+        for shard in &mut self.shards {
+            shard.persist()?;
+        }
+        Ok(())
+    }
+
+    /// Remove the specified vector from the specified shard.
+    pub fn remove(&mut self, shard: usize, id: VecId) -> Result<()> {
+        let nb_shards = self.shards.len();
+        self.shards
+            .get_mut(shard)
+            .ok_or_else(|| format!("Shard {shard} out of {nb_shards} shards"))?
+            .remove(id)
+    }
+
+    /// Search the nearest vectors in all the shards concurrently, and return the
+    /// `res_size` nearest ones along with their shard index.
+    ///
+    /// Results at equal distance are ordered by shard index and then by id.
+    pub fn search(
+        &self,
+        vec: &[T],
+        res_size: usize,
+        epsilon: f32,
+    ) -> Result<Vec<(usize, SearchResult)>>
+    where
+        T: Sync,
+    {
+        let shard_results = thread::scope(|s| {
+            let handles = self
+                .shards
+                .iter()
+                .map(|shard| s.spawn(move || shard.search(vec, res_size, epsilon)))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+                .collect::<Vec<_>>()
+        });
+
+        let mut results = Vec::new();
+        for (shard, res) in shard_results.into_iter().enumerate() {
+            results.extend(res?.into_iter().map(|r| (shard, r)));
+        }
+        results.sort_by(|(s1, r1), (s2, r2)| {
+            r1.distance
+                .total_cmp(&r2.distance)
+                .then(s1.cmp(s2))
+                .then(r1.id.cmp(&r2.id))
+        });
+        results.truncate(res_size);
+
+        Ok(results)
+    }
+
+    /// The shards of the index.
+    pub fn shards(&self) -> &[NgtIndex<T>] {
+        &self.shards
+    }
+
+    /// The mutable shards of the index.
+    pub fn shards_mut(&mut self) -> &mut [NgtIndex<T>] {
+        &mut self.shards
+    }
+
+    /// Split the index into its shards.
+    pub fn into_shards(self) -> Vec<NgtIndex<T>> {
+        self.shards
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::result::Result as StdResult;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::NgtProperties;
+
+    #[test]
+    fn test_sharded() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the shards
+        let dir = tempdir()?;
+
+        // Create two shards
+        let shards = (0..2)
+            .map(|i| {
+                let prop = NgtProperties::<f32>::dimension(3)?;
+                NgtIndex::create(dir.path().join(i.to_string()), prop)
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        let mut index = ShardedIndex::new(shards, RoundRobin::default())?;
+
+        // Vectors are inserted into each shard in turn
+        let v1 = index.insert(vec![1.0, 2.0, 3.0])?;
+        let v2 = index.insert(vec![1.0, 2.0, 3.0])?;
+        let v3 = index.insert(vec![4.0, 5.0, 6.0])?;
+        assert_eq!((v1.0, v2.0, v3.0), (0, 1, 0));
+        index.build(1)?;
+
+        // Results are merged by distance, ties broken by shard
+        let res = index.search(&[1.0, 2.0, 3.0], 3, 0.1)?;
+        let found = res.iter().map(|&(s, ref r)| (s, r.id)).collect::<Vec<_>>();
+        assert_eq!(found, vec![v1, v2, v3]);
+        let res = index.search(&[1.0, 2.0, 3.0], 1, 0.1)?;
+        assert_eq!(res.len(), 1);
+
+        // Custom policies route vectors
+        let shards = index.into_shards();
+        let mut index = ShardedIndex::new(shards, |_: &[f32], n: usize| n - 1)?;
+        assert_eq!(index.insert(vec![7.0, 8.0, 9.0])?.0, 1);
+        let mut index = ShardedIndex::new(index.into_shards(), |_: &[f32], n: usize| n)?;
+        assert!(index.insert(vec![7.0, 8.0, 9.0]).is_err());
+
+        dir.close()?;
+        Ok(())
+    }
+}
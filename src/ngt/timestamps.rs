@@ -0,0 +1,146 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, Result};
+use crate::VecId;
+
+const TIMESTAMPS_FILE: &str = "timestamps";
+
+/// Build timestamps of an index, along with the insertion times of its vectors and
+/// their removals when recording them is enabled, persisted in a sidecar file of the
+/// index directory.
+///
+/// Removed ids are only kept until NGT assigns them again, recycled ids are then kept
+/// as long as their vector is stored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Timestamps {
+    record: bool,
+    inserted: BTreeMap<VecId, SystemTime>,
+    removed: BTreeSet<VecId>,
+    recycled: BTreeSet<VecId>,
+    last_build: Option<SystemTime>,
+}
+
+impl Timestamps {
+    /// Empty timestamps, recording the insertions and removals of vectors if `record`.
+    pub(crate) fn new(record: bool) -> Self {
+        Self {
+            record,
+            ..Self::default()
+        }
+    }
+
+    /// Load the timestamps from the index directory, indexes created without
+    /// timestamps get empty ones.
+    pub(crate) fn load(dir: &Path, record: bool) -> Result<Self> {
+        let content = match fs::read_to_string(dir.join(TIMESTAMPS_FILE)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::new(record)),
+            Err(e) => Err(e)?,
+        };
+
+        let mut timestamps = Self::new(record);
+        for line in content.lines() {
+            let parse_err = || Error::from(format!("Invalid timestamps line: {line:?}"));
+            let (key, rest) = line.split_once(' ').ok_or_else(parse_err)?;
+            let parse_time = |time: &str| decode_time(time).ok_or_else(parse_err);
+            let parse_id = |id: &str| id.parse().map_err(|_| parse_err());
+            if key == "build" {
+                timestamps.last_build = Some(parse_time(rest)?);
+            } else if key == "removed" {
+                timestamps.removed.insert(parse_id(rest)?);
+            } else if key == "recycled" {
+                timestamps.recycled.insert(parse_id(rest)?);
+            } else {
+                timestamps
+                    .inserted
+                    .insert(parse_id(key)?, parse_time(rest)?);
+            }
+        }
+
+        Ok(timestamps)
+    }
+
+    /// Save the timestamps in the index directory.
+    pub(crate) fn save(&self, dir: &Path) -> Result<()> {
+        let mut content = String::new();
+        if let Some(time) = self.last_build {
+            content.push_str(&format!("build {}\n", encode_time(time)));
+        }
+        for (id, time) in &self.inserted {
+            content.push_str(&format!("{id} {}\n", encode_time(*time)));
+        }
+        for id in &self.removed {
+            content.push_str(&format!("removed {id}\n"));
+        }
+        for id in &self.recycled {
+            content.push_str(&format!("recycled {id}\n"));
+        }
+        fs::write(dir.join(TIMESTAMPS_FILE), content)?;
+        Ok(())
+    }
+
+    pub(crate) fn insert(&mut self, id: VecId) {
+        if !self.record {
+            return;
+        }
+        self.inserted.insert(id, SystemTime::now());
+        if self.removed.remove(&id) {
+            self.recycled.insert(id);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, id: VecId) {
+        if !self.record {
+            return;
+        }
+        self.inserted.remove(&id);
+        self.recycled.remove(&id);
+        self.removed.insert(id);
+    }
+
+    pub(crate) fn build(&mut self) {
+        self.last_build = Some(SystemTime::now());
+    }
+
+    pub(crate) fn inserted_at(&self, id: VecId) -> Option<SystemTime> {
+        self.inserted.get(&id).copied()
+    }
+
+    /// Whether the id was assigned again to a vector after being removed.
+    pub(crate) fn recycled(&self, id: VecId) -> bool {
+        self.recycled.contains(&id)
+    }
+
+    pub(crate) fn last_build(&self) -> Option<SystemTime> {
+        self.last_build
+    }
+
+    pub(crate) fn inserted_after(&self, time: SystemTime) -> impl Iterator<Item = VecId> + '_ {
+        self.inserted
+            .iter()
+            .filter(move |(_, &inserted)| inserted > time)
+            .map(|(&id, _)| id)
+    }
+
+    pub(crate) fn unindexed(&self) -> impl Iterator<Item = VecId> + '_ {
+        self.inserted_after(self.last_build.unwrap_or(UNIX_EPOCH))
+    }
+}
+
+/// Encode a time of the sidecar files of the index directory as the seconds and
+/// nanoseconds elapsed since the Unix epoch, `secs.nanos`, without losing precision.
+pub(crate) fn encode_time(time: SystemTime) -> String {
+    let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!("{}.{:09}", elapsed.as_secs(), elapsed.subsec_nanos())
+}
+
+/// Decode a time encoded by [`encode_time`][].
+pub(crate) fn decode_time(time: &str) -> Option<SystemTime> {
+    let (secs, nanos) = time.split_once('.')?;
+    let nanos = nanos.parse().ok().filter(|&nanos| nanos < 1_000_000_000)?;
+    UNIX_EPOCH.checked_add(Duration::new(secs.parse().ok()?, nanos))
+}
@@ -0,0 +1,73 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::VecId;
+
+const TOMBSTONES_FILE: &str = "tombstones";
+
+/// Ids of the soft removed vectors of an index, persisted in a sidecar file of the
+/// index directory.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Tombstones {
+    ids: BTreeSet<VecId>,
+}
+
+impl Tombstones {
+    /// Load the tombstones from the index directory, indexes created without
+    /// tombstones get empty ones.
+    pub(crate) fn load(dir: &Path) -> Result<Self> {
+        let content = match fs::read_to_string(dir.join(TOMBSTONES_FILE)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => Err(e)?,
+        };
+
+        let ids = content
+            .lines()
+            .map(|line| {
+                line.parse()
+                    .map_err(|_| Error::from(format!("Invalid tombstones line: {line:?}")))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { ids })
+    }
+
+    /// Save the tombstones in the index directory.
+    pub(crate) fn save(&self, dir: &Path) -> Result<()> {
+        let content = self
+            .ids
+            .iter()
+            .map(|id| format!("{id}\n"))
+            .collect::<String>();
+        fs::write(dir.join(TOMBSTONES_FILE), content)?;
+        Ok(())
+    }
+
+    pub(crate) fn insert(&mut self, id: VecId) {
+        self.ids.insert(id);
+    }
+
+    pub(crate) fn remove(&mut self, id: VecId) {
+        self.ids.remove(&id);
+    }
+
+    pub(crate) fn contains(&self, id: VecId) -> bool {
+        self.ids.contains(&id)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub(crate) fn take(&mut self) -> BTreeSet<VecId> {
+        std::mem::take(&mut self.ids)
+    }
+}
@@ -0,0 +1,179 @@
+use std::time::{Duration, Instant};
+
+use super::{IndexMode, NgtIndex, NgtObjectType, NgtQuery};
+use crate::dataset::recall;
+use crate::error::Result;
+
+/// The epsilons tried by [`NgtIndex::tune_epsilon`][], in increasing order.
+const EPSILONS: [f32; 9] = [0.0, 0.02, 0.05, 0.08, 0.1, 0.15, 0.2, 0.3, 0.5];
+
+/// Search parameters found by [`NgtIndex::tune_epsilon`][], along with the recall and
+/// latency measured with them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TunedParams {
+    /// The epsilon of the searches.
+    pub epsilon: f32,
+    /// The edge size of the searches, `None` for the index search edge size.
+    pub edge_size: Option<usize>,
+    /// The mean recall of the queries.
+    pub recall: f64,
+    /// The mean latency of the queries.
+    pub latency: Duration,
+}
+
+impl TunedParams {
+    /// A [`NgtQuery`][] of `size` results using the tuned parameters.
+    pub fn query<'a, T: NgtObjectType>(&self, vec: &'a [T], size: usize) -> NgtQuery<'a, T> {
+        let query = NgtQuery::new(vec).size(size).epsilon(self.epsilon);
+        match self.edge_size {
+            Some(edge_size) => query.edge_size(edge_size),
+            None => query,
+        }
+    }
+}
+
+impl<T, M> NgtIndex<T, M>
+where
+    T: NgtObjectType,
+    M: IndexMode,
+{
+    /// Find the smallest epsilon reaching `target_recall` for the `k` nearest
+    /// neighbors of the specified queries, see
+    /// [`tune_epsilon_with_edge_sizes`](NgtIndex::tune_epsilon_with_edge_sizes).
+    ///
+    /// **The index must have been [`built`](NgtIndex::build) beforehand**.
+    pub fn tune_epsilon(
+        &self,
+        queries: &[&[T]],
+        k: usize,
+        target_recall: f64,
+    ) -> Result<TunedParams> {
+        self.tune_epsilon_with_edge_sizes(queries, k, target_recall, &[])
+    }
+
+    /// Sweep the epsilon of searches for each of the specified edge sizes (the index
+    /// search edge size when empty), measuring the recall and latency of the `k`
+    /// nearest neighbors of the queries against an exact linear scan.
+    ///
+    /// For each edge size, the smallest epsilon reaching `target_recall` is kept, and
+    /// the fastest of them is returned. If the target recall isn't reached, the
+    /// parameters with the best recall are returned, which the caller can check.
+    ///
+    /// The queries should be representative of real ones, but not stored in the index.
+    ///
+    /// **The index must have been [`built`](NgtIndex::build) beforehand**.
+    pub fn tune_epsilon_with_edge_sizes(
+        &self,
+        queries: &[&[T]],
+        k: usize,
+        target_recall: f64,
+        edge_sizes: &[usize],
+    ) -> Result<TunedParams> {
+        if queries.is_empty() || k == 0 {
+            Err("Tuning requires queries and a positive k".to_string())?
+        }
+
+        let truths = queries
+            .iter()
+            .map(|query| {
+                let res = self.search_exact(query, k)?;
+                Ok(res.into_iter().map(|r| r.id).collect::<Vec<_>>())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let edge_sizes = if edge_sizes.is_empty() {
+            vec![None]
+        } else {
+            edge_sizes.iter().copied().map(Some).collect()
+        };
+
+        let mut best: Option<TunedParams> = None;
+        for edge_size in edge_sizes {
+            for epsilon in EPSILONS {
+                let mut params = TunedParams {
+                    epsilon,
+                    edge_size,
+                    recall: 0.,
+                    latency: Duration::ZERO,
+                };
+                let start = Instant::now();
+                for (query, truth) in queries.iter().zip(&truths) {
+                    let found = self.search_query(params.query(query, k))?;
+                    let found = found.into_iter().map(|r| r.id).collect::<Vec<_>>();
+                    params.recall += recall(&found, truth);
+                }
+                params.latency = start.elapsed() / queries.len() as u32;
+                params.recall /= queries.len() as f64;
+
+                let reached = params.recall >= target_recall;
+                best = match best {
+                    Some(b) if b.recall >= target_recall => {
+                        if reached && params.latency < b.latency {
+                            Some(params)
+                        } else {
+                            Some(b)
+                        }
+                    }
+                    Some(b) if !reached && params.recall <= b.recall => Some(b),
+                    _ => Some(params),
+                };
+                if reached {
+                    break;
+                }
+            }
+        }
+
+        let Some(best) = best else {
+            Err("No epsilon was tried".to_string())?
+        };
+        Ok(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::result::Result as StdResult;
+
+    use rand::Rng;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::NgtProperties;
+
+    #[test]
+    fn test_tune_epsilon() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        if cfg!(feature = "shared_mem") {
+            std::fs::remove_dir(dir.path())?;
+        }
+
+        // Create an index with random vectors
+        let mut rng = rand::thread_rng();
+        let mut random_vec = || (0..8).map(|_| rng.gen::<f32>()).collect::<Vec<_>>();
+        let prop = NgtProperties::<f32>::dimension(8)?;
+        let mut index = NgtIndex::create(dir.path(), prop)?;
+        index.insert_batch((0..500).map(|_| random_vec()).collect())?;
+        index.build(2)?;
+
+        // Tune the epsilon for a high recall
+        let queries = (0..20).map(|_| random_vec()).collect::<Vec<_>>();
+        let queries = queries.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        let params = index.tune_epsilon(&queries, 5, 0.9)?;
+        assert!(params.recall >= 0.9);
+        assert!(EPSILONS.contains(&params.epsilon));
+        assert_eq!(params.edge_size, None);
+        let res = index.search_query(params.query(queries[0], 5))?;
+        assert_eq!(res.len(), 5);
+
+        // Tune the edge size along with the epsilon
+        let params = index.tune_epsilon_with_edge_sizes(&queries, 5, 0.9, &[10, 40])?;
+        assert!(params.edge_size == Some(10) || params.edge_size == Some(40));
+
+        assert!(index.tune_epsilon(&[], 5, 0.9).is_err());
+
+        dir.close()?;
+        Ok(())
+    }
+}
@@ -1,17 +1,19 @@
-use std::ffi::CString;
+use std::ffi::{CString, OsStr};
 use std::marker::PhantomData;
 use std::os::unix::ffi::OsStrExt;
 use std::path::Path;
-use std::{mem, ptr};
+use std::{mem, panic, ptr, thread};
 
 use half::f16;
 use ngt_sys as sys;
 use scopeguard::defer;
 
-use crate::error::{make_err, Error, Result};
+use crate::error::{make_err, Error, ErrorKind, Result};
+use crate::ngt::{IndexMode, ModeRead, ModeWrite, Tombstones};
+use crate::query::QueryVec;
 use crate::{SearchResult, VecId};
 
-use super::{QbgBuildParams, QbgConstructParams, QbgObject, QbgObjectType};
+use super::{QbgBuildParams, QbgConstructParams, QbgDistance, QbgObject, QbgObjectType};
 
 #[derive(Debug)]
 pub struct QbgIndex<T, M> {
@@ -20,9 +22,15 @@ pub struct QbgIndex<T, M> {
     _mode: M,
     dimension: u32,
     ebuf: sys::NGTError,
+    tombstones: Tombstones,
+    construct_params: Option<QbgConstructParams<T>>,
+    build_params: Option<QbgBuildParams>,
     _marker: PhantomData<T>,
 }
 
+unsafe impl<T, M> Send for QbgIndex<T, M> {}
+unsafe impl<T, M> Sync for QbgIndex<T, M> {}
+
 impl<T> QbgIndex<T, ModeWrite>
 where
     T: QbgObjectType,
@@ -35,11 +43,14 @@ where
             let ebuf = sys::ngt_create_error_object();
             defer! { sys::ngt_destroy_error_object(ebuf); }
 
-            let path = CString::new(path.as_ref().as_os_str().as_bytes())?;
+            let dir = path.as_ref();
+            let path = CString::new(dir.as_os_str().as_bytes())?;
 
+            let construct_params = create_params.clone();
             if !sys::qbg_create(path.as_ptr(), &mut create_params.into_raw() as *mut _, ebuf) {
                 Err(make_err(ebuf))?
             }
+            construct_params.write(dir)?;
 
             let index = sys::qbg_open_index(path.as_ptr(), false, ebuf);
             if index.is_null() {
@@ -57,29 +68,80 @@ where
                 _mode: ModeWrite,
                 dimension,
                 ebuf: sys::ngt_create_error_object(),
+                tombstones: Tombstones::default(),
+                construct_params: Some(construct_params),
+                build_params: None,
                 _marker: PhantomData,
             })
         }
     }
 
-    pub fn insert(&mut self, mut vec: Vec<T>) -> Result<VecId> {
+    pub fn insert(&mut self, vec: Vec<T>) -> Result<VecId> {
+        if vec.len() != self.dimension as usize {
+            Err(format!(
+                "Inconsistent vector dim, expected: {} got: {}",
+                self.dimension,
+                vec.len()
+            ))?
+        }
+        self.append(&vec)
+    }
+
+    /// Insert the multiple vectors into the index.
+    ///
+    /// QBG appends vectors one at a time through NGT's C API, the batch only saves
+    /// the per-call checks and allocations.
+    pub fn insert_batch(&mut self, batch: Vec<Vec<T>>) -> Result<()> {
+        let dim = self.dimension as usize;
+        if let Some(vec) = batch.iter().find(|vec| vec.len() != dim) {
+            Err(format!(
+                "Inconsistent batch dim, expected: {dim} got: {}",
+                vec.len()
+            ))?
+        }
+        batch
+            .iter()
+            .try_for_each(|vec| self.append(vec).map(|_| ()))
+    }
+
+    /// Insert the `rows` vectors stored contiguously in `data`, in row-major order.
+    ///
+    /// Unlike [`insert_batch`](QbgIndex::insert_batch), the vectors are passed to NGT
+    /// without being copied, which matters for large ingests.
+    pub fn insert_batch_flat(&mut self, data: &[T], rows: usize) -> Result<()> {
+        let dim = self.dimension as usize;
+        if rows.checked_mul(dim) != Some(data.len()) {
+            Err(format!(
+                "Inconsistent batch length, expected {rows} rows of dim {dim} got: {}",
+                data.len()
+            ))?
+        }
+        if dim == 0 {
+            return Ok(());
+        }
+        data.chunks_exact(dim)
+            .try_for_each(|vec| self.append(vec).map(|_| ()))
+    }
+
+    /// Append a vector of the index dimension.
+    fn append(&mut self, vec: &[T]) -> Result<VecId> {
         unsafe {
             let id = match T::as_obj() {
                 QbgObject::Float => sys::qbg_append_object(
                     self.index,
-                    vec.as_mut_ptr() as *mut _,
+                    vec.as_ptr() as *mut _,
                     self.dimension,
                     self.ebuf,
                 ),
                 QbgObject::Uint8 => sys::qbg_append_object_as_uint8(
                     self.index,
-                    vec.as_mut_ptr() as *mut _,
+                    vec.as_ptr() as *mut _,
                     self.dimension,
                     self.ebuf,
                 ),
                 QbgObject::Float16 => sys::qbg_append_object_as_float16(
                     self.index,
-                    vec.as_mut_ptr() as *mut _,
+                    vec.as_ptr() as *mut _,
                     self.dimension,
                     self.ebuf,
                 ),
@@ -92,17 +154,46 @@ where
         }
     }
 
+    /// Build the index, running the hierarchical clustering and the optimization of
+    /// the quantizer.
+    ///
+    /// NGT writes the build logs directly to the standard output and error of the
+    /// process, which can't be captured from Rust without redirecting them for all the
+    /// threads of the process. To monitor long builds in structured logs, run them in
+    /// a dedicated process and capture its output. NGT exposes no counters while
+    /// building, so the progress of the build can't be reported otherwise.
+    ///
+    /// Building an index that was already built redoes the whole clustering, as NGT's
+    /// C API can't assign appended vectors to the existing blobs and codebooks.
     pub fn build(&mut self, build_params: QbgBuildParams) -> Result<()> {
         unsafe {
             if !sys::qbg_build_index(
                 self.path.as_ptr(),
-                &mut build_params.into_raw() as *mut _,
+                &mut build_params.clone().into_raw() as *mut _,
                 self.ebuf,
             ) {
                 Err(make_err(self.ebuf))?
             }
-            Ok(())
         }
+        build_params.write(self.path())?;
+        self.build_params = Some(build_params);
+        Ok(())
+    }
+
+    /// Remove the specified vector, it is then excluded from search results.
+    ///
+    /// QBG can't remove vectors from its blobs through NGT's C API, so the vector is
+    /// marked as removed in a sidecar file of the index directory when
+    /// [`persisting`](QbgIndex::persist) it, and filtered out of the search results.
+    pub fn remove(&mut self, id: VecId) -> Result<()> {
+        if self.tombstones.contains(id) || self.get_vec(id).is_err() {
+            Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Vector {id} not found"),
+            ))?
+        }
+        self.tombstones.insert(id);
+        Ok(())
     }
 
     pub fn persist(&mut self) -> Result<()> {
@@ -110,8 +201,8 @@ where
             if !sys::qbg_save_index(self.index, self.ebuf) {
                 Err(make_err(self.ebuf))?
             }
-            Ok(())
         }
+        self.tombstones.save(self.path())
     }
 
     pub fn into_readable(self) -> Result<QbgIndex<T, ModeRead>> {
@@ -127,7 +218,7 @@ where
 {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
         if !path.as_ref().exists() {
-            Err(Error(format!("Path {:?} does not exist", path.as_ref())))?
+            Err(format!("Path {:?} does not exist", path.as_ref()))?
         }
 
         unsafe {
@@ -145,72 +236,155 @@ where
                 Err(make_err(ebuf))?
             }
 
+            let dir = Path::new(OsStr::from_bytes(path.as_bytes()));
+            let tombstones = Tombstones::load(dir)?;
+            let construct_params = QbgConstructParams::read(dir)?;
+            let build_params = QbgBuildParams::read(dir)?;
+
             Ok(QbgIndex {
                 index,
                 path,
                 _mode: ModeRead,
                 dimension,
                 ebuf: sys::ngt_create_error_object(),
+                tombstones,
+                construct_params,
+                build_params,
                 _marker: PhantomData,
             })
         }
     }
 
     pub fn search(&self, query: QbgQuery<T>) -> Result<Vec<SearchResult>> {
-        unsafe {
-            let results = sys::ngt_create_empty_results(self.ebuf);
-            if results.is_null() {
-                Err(make_err(self.ebuf))?
-            }
-            defer! { sys::qbg_destroy_results(results); }
+        unsafe { self.search_in(&query, self.ebuf) }
+    }
 
-            match T::as_obj() {
-                QbgObject::Float => {
-                    let q = sys::QBGQueryFloat {
-                        query: query.query.as_ptr() as *mut f32,
-                        params: query.params(),
-                    };
-                    if !sys::qbg_search_index_float(self.index, q, results, self.ebuf) {
-                        Err(make_err(self.ebuf))?
-                    }
-                }
-                QbgObject::Uint8 => {
-                    let q = sys::QBGQueryUint8 {
-                        query: query.query.as_ptr() as *mut u8,
-                        params: query.params(),
-                    };
-                    if !sys::qbg_search_index_uint8(self.index, q, results, self.ebuf) {
-                        Err(make_err(self.ebuf))?
-                    }
+    /// Search the nearest vectors to each of the specified [`QbgQuery`][], which can
+    /// have different parameters, using `num_threads` threads.
+    ///
+    /// The results are in the same order as the queries.
+    pub fn search_batch(
+        &self,
+        queries: &[QbgQuery<T>],
+        num_threads: usize,
+    ) -> Vec<Result<Vec<SearchResult>>>
+    where
+        T: Sync,
+    {
+        if queries.is_empty() {
+            return Vec::new();
+        }
+        let chunk_size = queries.len().div_ceil(num_threads.max(1));
+
+        thread::scope(|s| {
+            let handles = queries
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    s.spawn(move || unsafe {
+                        // Each thread reports its errors in its own error object
+                        let ebuf = sys::ngt_create_error_object();
+                        defer! { sys::ngt_destroy_error_object(ebuf); }
+
+                        chunk
+                            .iter()
+                            .map(|query| self.search_in(query, ebuf))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+                .collect()
+        })
+    }
+
+    unsafe fn search_in(
+        &self,
+        query: &QbgQuery<T>,
+        ebuf: sys::NGTError,
+    ) -> Result<Vec<SearchResult>> {
+        if query.query.len() != self.dimension as usize {
+            Err(format!(
+                "Invalid query dimension {}, expected {}",
+                query.query.len(),
+                self.dimension
+            ))?
+        }
+
+        if self.tombstones.is_empty() {
+            return self.search_blobs(query, ebuf);
+        }
+
+        // Over-fetch so that enough results remain once the removed ones are filtered
+        let fetch = QbgQuery {
+            size: query.size + self.tombstones.len(),
+            ..query.clone()
+        };
+        let mut res = self.search_blobs(&fetch, ebuf)?;
+        res.retain(|r| !self.tombstones.contains(r.id));
+        res.truncate(query.size);
+        Ok(res)
+    }
+
+    unsafe fn search_blobs(
+        &self,
+        query: &QbgQuery<T>,
+        ebuf: sys::NGTError,
+    ) -> Result<Vec<SearchResult>> {
+        let results = sys::ngt_create_empty_results(ebuf);
+        if results.is_null() {
+            Err(make_err(ebuf))?
+        }
+        defer! { sys::qbg_destroy_results(results); }
+
+        match T::as_obj() {
+            QbgObject::Float => {
+                let q = sys::QBGQueryFloat {
+                    query: query.query.as_ptr() as *mut f32,
+                    params: query.params(),
+                };
+                if !sys::qbg_search_index_float(self.index, q, results, ebuf) {
+                    Err(make_err(ebuf))?
                 }
-                QbgObject::Float16 => {
-                    let q = sys::QBGQueryFloat16 {
-                        query: query.query.as_ptr() as *mut _,
-                        params: query.params(),
-                    };
-                    if !sys::qbg_search_index_float16(self.index, q, results, self.ebuf) {
-                        Err(make_err(self.ebuf))?
-                    }
+            }
+            QbgObject::Uint8 => {
+                let q = sys::QBGQueryUint8 {
+                    query: query.query.as_ptr() as *mut u8,
+                    params: query.params(),
+                };
+                if !sys::qbg_search_index_uint8(self.index, q, results, ebuf) {
+                    Err(make_err(ebuf))?
                 }
             }
-
-            let rsize = sys::qbg_get_result_size(results, self.ebuf);
-            let mut ret = Vec::with_capacity(rsize as usize);
-
-            for i in 0..rsize {
-                let d = sys::qbg_get_result(results, i, self.ebuf);
-                if d.id == 0 && d.distance == 0.0 {
-                    Err(make_err(self.ebuf))?
-                } else {
-                    ret.push(SearchResult {
-                        id: d.id,
-                        distance: d.distance,
-                    });
+            QbgObject::Float16 => {
+                let q = sys::QBGQueryFloat16 {
+                    query: query.query.as_ptr() as *mut _,
+                    params: query.params(),
+                };
+                if !sys::qbg_search_index_float16(self.index, q, results, ebuf) {
+                    Err(make_err(ebuf))?
                 }
             }
+        }
 
-            Ok(ret)
+        let rsize = sys::qbg_get_result_size(results, ebuf);
+        let mut ret = Vec::with_capacity(rsize as usize);
+
+        for i in 0..rsize {
+            let d = sys::qbg_get_result(results, i, ebuf);
+            if d.id == 0 && d.distance == 0.0 {
+                Err(make_err(ebuf))?
+            } else {
+                ret.push(SearchResult {
+                    id: d.id,
+                    distance: d.distance,
+                });
+            }
         }
+
+        Ok(ret)
     }
 
     pub fn into_writable(self) -> Result<QbgIndex<T, ModeWrite>> {
@@ -231,12 +405,20 @@ where
                 Err(make_err(ebuf))?
             }
 
+            let dir = Path::new(OsStr::from_bytes(path.as_bytes()));
+            let tombstones = Tombstones::load(dir)?;
+            let construct_params = QbgConstructParams::read(dir)?;
+            let build_params = QbgBuildParams::read(dir)?;
+
             Ok(QbgIndex {
                 index,
                 path,
                 _mode: ModeWrite,
                 dimension,
                 ebuf: sys::ngt_create_error_object(),
+                tombstones,
+                construct_params,
+                build_params,
                 _marker: PhantomData,
             })
         }
@@ -248,6 +430,116 @@ where
     T: QbgObjectType,
     M: IndexMode,
 {
+    /// The path of the index directory.
+    pub fn path(&self) -> &Path {
+        Path::new(OsStr::from_bytes(self.path.as_bytes()))
+    }
+
+    /// The dimension of the vectors of the index.
+    pub fn dimension(&self) -> usize {
+        self.dimension as usize
+    }
+
+    /// The distance function of the index, QBG only supports L2.
+    pub fn distance_type(&self) -> QbgDistance {
+        QbgDistance::L2
+    }
+
+    /// The type of the vectors of the index.
+    pub fn object_type(&self) -> QbgObject {
+        T::as_obj()
+    }
+
+    /// The parameters the index was [`created`](QbgIndex::create) with, `None` for
+    /// indexes created by other means.
+    pub fn construction_params(&self) -> Option<&QbgConstructParams<T>> {
+        self.construct_params.as_ref()
+    }
+
+    /// The parameters the index was last [`built`](QbgIndex::build) with, `None` for
+    /// indexes not built yet or built by other means.
+    pub fn build_params(&self) -> Option<&QbgBuildParams> {
+        self.build_params.as_ref()
+    }
+
+    /// A query of the specified vector, with defaults suited to the index.
+    ///
+    /// The number of explored blobs is scaled to the number of blobs the index was
+    /// [`created`](QbgIndex::create) with, when it was set. Otherwise the defaults of
+    /// [`QbgQuery::new`][] are used.
+    ///
+    /// The [`number_of_edges`](QbgQuery::number_of_edges) and the
+    /// [`epsilon`](QbgQuery::epsilon) always keep the defaults of [`QbgQuery::new`][]:
+    /// they bound the walk on the graph built by NGT over the blobs, whose edges and
+    /// distances neither the construction parameters nor NGT's C API describe. The
+    /// default of `0` edges lets NGT explore all the edges of each node.
+    pub fn query<'a>(&self, vec: &'a [T]) -> QbgQuery<'a, T> {
+        let query = QbgQuery::new(vec);
+        match self.construct_params.as_ref().map(|p| p.number_of_blobs) {
+            Some(nb_blobs) if nb_blobs > 0 => {
+                let explored = (nb_blobs / 32).clamp(nb_blobs.min(16), nb_blobs);
+                query.number_of_explored_blobs(explored)
+            }
+            _ => query,
+        }
+    }
+
+    /// The largest id of the vectors of the index, `0` if it is empty.
+    ///
+    /// NGT's C API doesn't expose the number of vectors of a QBG index, but their ids
+    /// are contiguous from `1`, so the largest one is found by probing them.
+    pub fn max_id(&self) -> VecId {
+        let exists = |id| self.get_vec(id).is_ok();
+
+        // Find an upper bound, then bisect between the last existing id and it
+        let (mut lo, mut hi) = (0, 1);
+        while exists(hi) {
+            lo = hi;
+            hi = hi.saturating_mul(2);
+        }
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if exists(mid) {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// The number of vectors inserted, including the [`removed`](QbgIndex::remove) ones.
+    pub fn nb_inserted(&self) -> usize {
+        self.max_id() as usize
+    }
+
+    /// Statistics of the index, to diagnose a poor recall.
+    ///
+    /// NGT's C API doesn't expose the blobs nor the codebooks of a QBG index, so the
+    /// distribution of the vectors among the blobs and the codebook sizes can't be
+    /// reported. The quantization configuration is only known for indexes
+    /// [`created`](QbgIndex::create) with this crate.
+    pub fn stats(&self) -> QbgStats {
+        let nb_inserted = self.nb_inserted();
+        let params = self.construct_params.as_ref();
+        QbgStats {
+            nb_objects: nb_inserted - self.tombstones.len().min(nb_inserted),
+            nb_removed: self.tombstones.len(),
+            dimension: self.dimension(),
+            extended_dimension: params.map(|p| p.extended_dimension),
+            number_of_subvectors: params.map(|p| p.number_of_subvectors),
+            number_of_blobs: params
+                .map(|p| p.number_of_blobs)
+                .filter(|&nb_blobs| nb_blobs > 0),
+        }
+    }
+
+    /// The ids of the vectors of the index, [`removed`](QbgIndex::remove) vectors are
+    /// skipped.
+    pub fn ids(&self) -> impl Iterator<Item = VecId> + '_ {
+        (1..=self.max_id()).filter(|&id| !self.tombstones.contains(id))
+    }
+
     /// Get the specified vector.
     pub fn get_vec(&self, id: VecId) -> Result<Vec<T>> {
         unsafe {
@@ -293,6 +585,23 @@ where
     }
 }
 
+/// Statistics of a QBG index, see [`QbgIndex::stats`][].
+#[derive(Debug, Clone, PartialEq)]
+pub struct QbgStats {
+    /// The number of vectors, excluding the removed ones.
+    pub nb_objects: usize,
+    /// The number of removed vectors.
+    pub nb_removed: usize,
+    /// The dimension of the vectors.
+    pub dimension: usize,
+    /// The dimension the vectors are padded to, when known.
+    pub extended_dimension: Option<usize>,
+    /// The number of subvectors of the quantization, when known.
+    pub number_of_subvectors: Option<usize>,
+    /// The number of blobs, when it was set at creation rather than chosen by NGT.
+    pub number_of_blobs: Option<usize>,
+}
+
 impl<T, M> Drop for QbgIndex<T, M> {
     fn drop(&mut self) {
         if !self.index.is_null() {
@@ -306,27 +615,16 @@ impl<T, M> Drop for QbgIndex<T, M> {
     }
 }
 
-mod private {
-    pub trait Sealed {}
-}
-
-pub trait IndexMode: private::Sealed {}
-
-#[derive(Debug, Clone, Copy)]
-pub struct ModeRead;
-
-impl private::Sealed for ModeRead {}
-impl IndexMode for ModeRead {}
-
-#[derive(Debug, Clone, Copy)]
-pub struct ModeWrite;
-
-impl private::Sealed for ModeWrite {}
-impl IndexMode for ModeWrite {}
-
+/// A search query along with its parameters, see [`QbgIndex::search`][].
+///
+/// QBG can't abort a search once started, its latency is bounded by the explored
+/// blobs instead, that is by
+/// [`number_of_explored_blobs`](QbgQuery::number_of_explored_blobs) and
+/// [`epsilon`](QbgQuery::epsilon).
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QbgQuery<'a, T> {
-    query: &'a [T],
+    query: QueryVec<'a, T>,
     pub size: usize,
     pub epsilon: f32,
     pub blob_epsilon: f32,
@@ -341,6 +639,29 @@ where
     T: QbgObjectType,
 {
     pub fn new(query: &'a [T]) -> Self {
+        Self::with_query(QueryVec::Borrowed(query))
+    }
+
+    /// Create a query from `f64` values, converting them to the index's object type.
+    ///
+    /// Values are rounded to the nearest float for `f32` and `f16`. Fails if a value
+    /// can't be represented by the object type, for instance a fractional or negative
+    /// value for `u8`, or an out of range value for `f16`.
+    pub fn from_f64(query: &[f64]) -> Result<QbgQuery<'static, T>> {
+        let query = QueryVec::convert(query, T::as_obj(), T::from_f64)?;
+        Ok(QbgQuery::with_query(query))
+    }
+
+    /// Create a query from `i32` values, converting them to the index's object type.
+    ///
+    /// Fails if a value can't be represented exactly by the object type, for instance
+    /// a negative value for `u8`, or a value above `2^24` in magnitude for `f32`.
+    pub fn from_i32(query: &[i32]) -> Result<QbgQuery<'static, T>> {
+        let query = QueryVec::convert(query, T::as_obj(), T::from_i32)?;
+        Ok(QbgQuery::with_query(query))
+    }
+
+    fn with_query(query: QueryVec<'a, T>) -> Self {
         Self {
             query,
             size: 20,
@@ -411,6 +732,28 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_qbg_query_conversions() -> Result<()> {
+        let query = QbgQuery::<f32>::from_f64(&[1.0, 2.5, -3.0])?;
+        assert_eq!(&query.query[..], &[1.0, 2.5, -3.0]);
+        assert!(QbgQuery::<f32>::from_f64(&[f64::MAX]).is_err());
+        assert!(QbgQuery::<f32>::from_i32(&[16_777_216]).is_ok());
+        assert!(QbgQuery::<f32>::from_i32(&[16_777_217]).is_err());
+
+        let query = QbgQuery::<u8>::from_f64(&[0.0, 255.0])?;
+        assert_eq!(&query.query[..], &[0, 255]);
+        assert!(QbgQuery::<u8>::from_f64(&[1.5]).is_err());
+        assert!(QbgQuery::<u8>::from_i32(&[256]).is_err());
+        assert!(QbgQuery::<u8>::from_i32(&[-1]).is_err());
+
+        let query = QbgQuery::<f16>::from_i32(&[1, -2])?;
+        assert_eq!(&query.query[..], &[f16::from_f32(1.0), f16::from_f32(-2.0)]);
+        assert!(QbgQuery::<f16>::from_i32(&[2049]).is_err());
+        assert!(QbgQuery::<f16>::from_i32(&[100_000]).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_qbg_f32() -> StdResult<(), Box<dyn StdError>> {
         // Get a temporary directory to store the index
@@ -420,6 +763,9 @@ mod tests {
         // Create a QGB index
         let ndims = 3;
         let mut index = QbgIndex::create(dir.path(), QbgConstructParams::dimension(ndims))?;
+        assert_eq!(index.path(), dir.path());
+        assert_eq!(index.dimension(), ndims);
+        assert_eq!(index.object_type(), QbgObject::Float);
 
         // Insert vectors and get their ids
         let nvecs = 64;
@@ -440,6 +786,19 @@ mod tests {
         index.persist()?;
 
         let index = index.into_readable()?;
+        assert_eq!(index.nb_inserted(), nvecs);
+        assert_eq!(index.max_id(), *ids.last().unwrap());
+        let construct_params = index.construction_params().unwrap();
+        assert_eq!(construct_params.get_dimension(), ndims);
+        assert_eq!(construct_params.get_extended_dimension(), 16);
+        assert_eq!(index.build_params(), Some(&QbgBuildParams::default()));
+        let stats = index.stats();
+        assert_eq!(stats.nb_objects, nvecs);
+        assert_eq!(stats.dimension, ndims);
+        assert_eq!(stats.extended_dimension, Some(16));
+        assert_eq!(stats.number_of_subvectors, Some(1));
+        assert_eq!(stats.number_of_blobs, None);
+        assert_eq!(index.ids().collect::<Vec<_>>(), ids);
 
         // Perform a vector search (with 2 results)
         let v: Vec<f32> = (1..=ndims).into_iter().map(|x| x as f32).collect();
@@ -448,7 +807,92 @@ mod tests {
         assert_eq!(ids[0], res[0].id);
         assert_eq!(v, index.get_vec(ids[0])?);
 
+        // Perform a batch of vector searches from 2 threads
+        let vecs = ids
+            .iter()
+            .map(|&id| index.get_vec(id))
+            .collect::<Result<Vec<_>>>()?;
+        let queries = vecs
+            .iter()
+            .map(|v| QbgQuery::new(v).size(1))
+            .collect::<Vec<_>>();
+        let res = index.search_batch(&queries, 2);
+        assert_eq!(res.len(), nvecs);
+        for (query, res) in queries.into_iter().zip(res) {
+            assert_eq!(res?, index.search(query)?);
+        }
+        let res = index.search_batch(&[QbgQuery::new(&[1.0])], 2);
+        assert!(res[0].is_err());
+
+        // Removed vectors are excluded from the results once persisted
+        let mut index = index.into_writable()?;
+        index.remove(ids[0])?;
+        assert!(index.remove(ids[0]).is_err());
+        index.persist()?;
+        let index = index.into_readable()?;
+        let res = index.search(QbgQuery::new(&v).size(2))?;
+        assert_eq!(res.len(), 2);
+        assert!(res.iter().all(|r| r.id != ids[0]));
+        assert_eq!(index.ids().count(), nvecs - 1);
+        assert_eq!(index.stats().nb_objects, nvecs - 1);
+        assert_eq!(index.stats().nb_removed, 1);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_qbg_insert_batch() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        std::fs::remove_dir(dir.path())?;
+
+        // Insert vectors by batches, nested and flat
+        let ndims = 3;
+        let mut index = QbgIndex::create(dir.path(), QbgConstructParams::dimension(ndims))?;
+        index.insert_batch((0..32).map(|i| vec![i as f32; ndims]).collect())?;
+        let data = (32..64)
+            .flat_map(|i| vec![i as f32; ndims])
+            .collect::<Vec<_>>();
+        index.insert_batch_flat(&data, 32)?;
+        assert!(index.insert_batch(vec![vec![0.0; ndims + 1]]).is_err());
+        assert!(index.insert_batch_flat(&data, 31).is_err());
+        assert!(index.insert(vec![0.0; ndims - 1]).is_err());
+
+        index.build(QbgBuildParams::default())?;
+        index.persist()?;
+        let index = index.into_readable()?;
+        assert_eq!(index.nb_inserted(), 64);
+        assert_eq!(index.get_vec(40)?, vec![39.0; ndims]);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_qbg_query_defaults() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+        std::fs::remove_dir(dir.path())?;
+
+        // The explored blobs are scaled to the number of blobs
+        let params = QbgConstructParams::dimension(3).number_of_blobs(1000);
+        let index = QbgIndex::<f32, _>::create(dir.path(), params.clone())?;
+        let query = index.query(&[1.0; 3]);
+        assert_eq!(query.number_of_explored_blobs, 31);
+
+        // The construction parameters are kept for when reopening the index
+        assert_eq!(QbgConstructParams::read(dir.path())?, Some(params));
+
+        // Otherwise the defaults are used
+        let other_dir = tempdir()?;
+        std::fs::remove_dir(other_dir.path())?;
+        let params = QbgConstructParams::dimension(3);
+        let index = QbgIndex::<f32, _>::create(other_dir.path(), params)?;
+        assert_eq!(index.query(&[1.0; 3]), QbgQuery::new(&[1.0; 3]));
+
         dir.close()?;
+        other_dir.close()?;
         Ok(())
     }
 
@@ -40,7 +40,7 @@
 //! let id1 = index.insert(vec1)?;
 //! let id2 = index.insert(vec2)?;
 //!
-//! // Add enough dummy vectors to build an index
+//! // Insert more vectors, the blobs are clustered from them
 //! for i in 0..64 {
 //!     index.insert(vec![100. + i as f32; 3])?;
 //! }
@@ -65,7 +65,9 @@
 mod index;
 mod properties;
 
-pub use self::index::{IndexMode, ModeRead, ModeWrite, QbgIndex, QbgQuery};
+pub use self::index::{QbgIndex, QbgQuery, QbgStats};
 pub use self::properties::{
-    QbgBuildParams, QbgConstructParams, QbgDistance, QbgObject, QbgObjectType,
+    QbgBuildParams, QbgClusteringInitMode, QbgConstructParams, QbgDistance, QbgObject,
+    QbgObjectType,
 };
+pub use crate::ngt::{IndexMode, ModeRead, ModeWrite};
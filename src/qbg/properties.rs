@@ -1,12 +1,19 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind as IoErrorKind;
 use std::marker::PhantomData;
+use std::path::Path;
+use std::str::FromStr;
 
 use half::f16;
 use ngt_sys as sys;
 use num_enum::TryFromPrimitive;
 
-use crate::error::Error;
+use crate::error::{Error, Result};
 
+/// The type of the vectors of a QBG index, `u8`, `f32` or half-precision `f16`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum QbgObject {
     Uint8 = 0,
@@ -18,8 +25,16 @@ mod private {
     pub trait Sealed {}
 }
 
-pub trait QbgObjectType: private::Sealed {
+pub trait QbgObjectType: private::Sealed + Copy {
     fn as_obj() -> QbgObject;
+
+    /// Convert the value, if it can be represented.
+    #[doc(hidden)]
+    fn from_f64(value: f64) -> Option<Self>;
+
+    /// Convert the value, if it can be represented exactly.
+    #[doc(hidden)]
+    fn from_i32(value: i32) -> Option<Self>;
 }
 
 impl private::Sealed for f32 {}
@@ -27,6 +42,16 @@ impl QbgObjectType for f32 {
     fn as_obj() -> QbgObject {
         QbgObject::Float
     }
+
+    fn from_f64(value: f64) -> Option<Self> {
+        let value = value as f32;
+        value.is_finite().then_some(value)
+    }
+
+    fn from_i32(value: i32) -> Option<Self> {
+        let converted = value as f32;
+        (converted as f64 == value as f64).then_some(converted)
+    }
 }
 
 impl private::Sealed for u8 {}
@@ -34,6 +59,15 @@ impl QbgObjectType for u8 {
     fn as_obj() -> QbgObject {
         QbgObject::Uint8
     }
+
+    fn from_f64(value: f64) -> Option<Self> {
+        let in_range = value.fract() == 0.0 && (0.0..=u8::MAX as f64).contains(&value);
+        in_range.then_some(value as u8)
+    }
+
+    fn from_i32(value: i32) -> Option<Self> {
+        u8::try_from(value).ok()
+    }
 }
 
 impl private::Sealed for f16 {}
@@ -41,9 +75,20 @@ impl QbgObjectType for f16 {
     fn as_obj() -> QbgObject {
         QbgObject::Float16
     }
+
+    fn from_f64(value: f64) -> Option<Self> {
+        let value = f16::from_f64(value);
+        value.is_finite().then_some(value)
+    }
+
+    fn from_i32(value: i32) -> Option<Self> {
+        let converted = f16::from_f64(value as f64);
+        (converted.to_f64() == value as f64).then_some(converted)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum QbgDistance {
     L2 = 1,
@@ -51,13 +96,13 @@ pub enum QbgDistance {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct QbgConstructParams<T> {
-    extended_dimension: usize,
-    dimension: usize,
-    number_of_subvectors: usize,
-    number_of_blobs: usize,
-    internal_data_type: QbgObject,
-    data_type: QbgObject,
-    distance_type: QbgDistance,
+    pub(crate) extended_dimension: usize,
+    pub(crate) dimension: usize,
+    pub(crate) number_of_subvectors: usize,
+    pub(crate) number_of_blobs: usize,
+    pub(crate) internal_data_type: QbgObject,
+    pub(crate) data_type: QbgObject,
+    pub(crate) distance_type: QbgDistance,
     _marker: PhantomData<T>,
 }
 
@@ -85,12 +130,12 @@ where
         }
     }
 
-    pub fn extended_dimension(mut self, extended_dimension: usize) -> Result<Self, Error> {
+    pub fn extended_dimension(mut self, extended_dimension: usize) -> Result<Self> {
         if extended_dimension % 16 == 0 && extended_dimension >= self.dimension {
             self.extended_dimension = extended_dimension;
             Ok(self)
         } else {
-            Err(Error(format!(
+            Err(Error::from(format!(
                 "Invalid extended_dimension: {}, must be a multiple of 16 greater or equal to dimension",
                 extended_dimension
             )))
@@ -107,6 +152,38 @@ where
         self
     }
 
+    /// The dimension of the vectors.
+    pub fn get_dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// The dimension the vectors are padded to, a multiple of 16.
+    pub fn get_extended_dimension(&self) -> usize {
+        self.extended_dimension
+    }
+
+    /// The number of subvectors of the quantization.
+    pub fn get_number_of_subvectors(&self) -> usize {
+        self.number_of_subvectors
+    }
+
+    /// The number of blobs, `0` to let NGT choose it when building the index.
+    pub fn get_number_of_blobs(&self) -> usize {
+        self.number_of_blobs
+    }
+
+    /// The type the vectors are stored as by the index.
+    pub fn get_internal_data_type(&self) -> QbgObject {
+        self.internal_data_type
+    }
+
+    /// The distance function of the index.
+    pub fn get_distance_type(&self) -> QbgDistance {
+        self.distance_type
+    }
+
+    /// Set the type the vectors are stored as by the index, `T` by default.
+    /// [`Float16`](QbgObject::Float16) halves the memory of `f32` vectors.
     pub fn internal_data_type(mut self, internal_data_type: QbgObject) -> Self {
         self.internal_data_type = internal_data_type;
         self
@@ -117,6 +194,48 @@ where
         self
     }
 
+    /// Sidecar file of the index directory, NGT doesn't expose the parameters.
+    const PARAMS_FILE: &'static str = "construction";
+
+    /// Load the parameters from the index directory, indexes created by other means
+    /// than [`QbgIndex::create`](crate::qbg::QbgIndex::create) have none.
+    pub(crate) fn read(dir: &Path) -> Result<Option<Self>> {
+        let Some(params) = Params::read(dir, Self::PARAMS_FILE)? else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            extended_dimension: params.parse("extended_dimension")?,
+            dimension: params.parse("dimension")?,
+            number_of_subvectors: params.parse("number_of_subvectors")?,
+            number_of_blobs: params.parse("number_of_blobs")?,
+            internal_data_type: QbgObject::try_from(params.parse::<i32>("internal_data_type")?)?,
+            data_type: QbgObject::try_from(params.parse::<i32>("data_type")?)?,
+            distance_type: QbgDistance::try_from(params.parse::<i32>("distance_type")?)?,
+            _marker: PhantomData,
+        }))
+    }
+
+    /// Save the parameters in the index directory.
+    pub(crate) fn write(&self, dir: &Path) -> Result<()> {
+        let values = [
+            ("extended_dimension", self.extended_dimension.to_string()),
+            ("dimension", self.dimension.to_string()),
+            (
+                "number_of_subvectors",
+                self.number_of_subvectors.to_string(),
+            ),
+            ("number_of_blobs", self.number_of_blobs.to_string()),
+            (
+                "internal_data_type",
+                (self.internal_data_type as i32).to_string(),
+            ),
+            ("data_type", (self.data_type as i32).to_string()),
+            ("distance_type", (self.distance_type as i32).to_string()),
+        ];
+        Params::write(dir, Self::PARAMS_FILE, &values)
+    }
+
     pub(crate) unsafe fn into_raw(self) -> sys::QBGConstructionParameters {
         sys::QBGConstructionParameters {
             extended_dimension: self.extended_dimension,
@@ -130,11 +249,138 @@ where
     }
 }
 
+/// Parameters persisted in a sidecar file of the index directory, as `key value`
+/// lines.
+struct Params {
+    file: &'static str,
+    values: HashMap<String, String>,
+}
+
+impl Params {
+    fn read(dir: &Path, file: &'static str) -> Result<Option<Self>> {
+        let content = match fs::read_to_string(dir.join(file)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == IoErrorKind::NotFound => return Ok(None),
+            Err(e) => Err(e)?,
+        };
+
+        let values = content
+            .lines()
+            .map(|line| {
+                let (key, value) = line
+                    .split_once(' ')
+                    .ok_or_else(|| Error::from(format!("Invalid {file} params: {line:?}")))?;
+                Ok((key.to_string(), value.to_string()))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Some(Self { file, values }))
+    }
+
+    fn parse<V: FromStr>(&self, key: &str) -> Result<V> {
+        let file = self.file;
+        let value = self
+            .values
+            .get(key)
+            .ok_or_else(|| format!("Missing {key} in {file} params"))?;
+        Ok(value
+            .parse()
+            .map_err(|_| format!("Invalid {key} {value:?} in {file} params"))?)
+    }
+
+    fn write(dir: &Path, file: &str, values: &[(&str, String)]) -> Result<()> {
+        let content = values
+            .iter()
+            .map(|(key, value)| format!("{key} {value}\n"))
+            .collect::<String>();
+        fs::write(dir.join(file), content)?;
+        Ok(())
+    }
+}
+
+/// The settings of [`QbgConstructParams`][] as (de)serialized, only the dimension is
+/// required when deserializing.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ConstructParamsRepr {
+    dimension: usize,
+    extended_dimension: Option<usize>,
+    number_of_subvectors: Option<usize>,
+    number_of_blobs: Option<usize>,
+    internal_data_type: Option<QbgObject>,
+    distance_type: Option<QbgDistance>,
+}
+
+#[cfg(feature = "serde")]
+impl ConstructParamsRepr {
+    fn into_params<T: QbgObjectType>(self) -> Result<QbgConstructParams<T>> {
+        if self.dimension == 0 {
+            Err("The dimension must be greater than 0".to_string())?
+        }
+        let mut params = QbgConstructParams::dimension(self.dimension);
+        if let Some(extended_dimension) = self.extended_dimension {
+            params = params.extended_dimension(extended_dimension)?;
+        }
+        if let Some(number_of_subvectors) = self.number_of_subvectors {
+            params = params.number_of_subvectors(number_of_subvectors);
+        }
+        if params.number_of_subvectors == 0
+            || params.extended_dimension % params.number_of_subvectors != 0
+        {
+            Err(format!(
+                "Invalid number_of_subvectors: {}, must divide the extended dimension {}",
+                params.number_of_subvectors, params.extended_dimension
+            ))?
+        }
+        if let Some(number_of_blobs) = self.number_of_blobs {
+            params = params.number_of_blobs(number_of_blobs);
+        }
+        if let Some(internal_data_type) = self.internal_data_type {
+            params = params.internal_data_type(internal_data_type);
+        }
+        if let Some(distance_type) = self.distance_type {
+            params = params.distance_type(distance_type);
+        }
+        Ok(params)
+    }
+}
+
+/// The data type isn't serialized, it is determined by `T`.
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for QbgConstructParams<T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let repr = ConstructParamsRepr {
+            dimension: self.dimension,
+            extended_dimension: Some(self.extended_dimension),
+            number_of_subvectors: Some(self.number_of_subvectors),
+            number_of_blobs: Some(self.number_of_blobs),
+            internal_data_type: Some(self.internal_data_type),
+            distance_type: Some(self.distance_type),
+        };
+        serde::Serialize::serialize(&repr, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: QbgObjectType> serde::Deserialize<'de> for QbgConstructParams<T> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let repr = <ConstructParamsRepr as serde::Deserialize>::deserialize(deserializer)?;
+        repr.into_params().map_err(serde::de::Error::custom)
+    }
+}
+
 fn next_multiple_of_16(x: usize) -> usize {
     ((x + 15) / 16) * 16
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum QbgClusteringInitMode {
     Head = 0,
@@ -145,7 +391,7 @@ pub enum QbgClusteringInitMode {
     Best = 5,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct QbgBuildParams {
     // hierarchical kmeans
     hierarchical_clustering_init_mode: QbgClusteringInitMode,
@@ -261,6 +507,82 @@ impl QbgBuildParams {
         self
     }
 
+    /// Sidecar file of the index directory, NGT doesn't keep the parameters.
+    const PARAMS_FILE: &'static str = "build";
+
+    /// Load the parameters from the index directory, indexes built by other means than
+    /// [`QbgIndex::build`](crate::qbg::QbgIndex::build) have none.
+    pub(crate) fn read(dir: &Path) -> Result<Option<Self>> {
+        let Some(params) = Params::read(dir, Self::PARAMS_FILE)? else {
+            return Ok(None);
+        };
+        let init_mode = |key| -> Result<QbgClusteringInitMode> {
+            Ok(QbgClusteringInitMode::try_from(params.parse::<i32>(key)?)?)
+        };
+
+        Ok(Some(Self {
+            hierarchical_clustering_init_mode: init_mode("hierarchical_clustering_init_mode")?,
+            number_of_first_objects: params.parse("number_of_first_objects")?,
+            number_of_first_clusters: params.parse("number_of_first_clusters")?,
+            number_of_second_objects: params.parse("number_of_second_objects")?,
+            number_of_second_clusters: params.parse("number_of_second_clusters")?,
+            number_of_third_clusters: params.parse("number_of_third_clusters")?,
+            number_of_objects: params.parse("number_of_objects")?,
+            number_of_subvectors: params.parse("number_of_subvectors")?,
+            optimization_clustering_init_mode: init_mode("optimization_clustering_init_mode")?,
+            rotation_iteration: params.parse("rotation_iteration")?,
+            subvector_iteration: params.parse("subvector_iteration")?,
+            number_of_matrices: params.parse("number_of_matrices")?,
+            rotation: params.parse("rotation")?,
+            repositioning: params.parse("repositioning")?,
+        }))
+    }
+
+    /// Save the parameters in the index directory.
+    pub(crate) fn write(&self, dir: &Path) -> Result<()> {
+        let values = [
+            (
+                "hierarchical_clustering_init_mode",
+                (self.hierarchical_clustering_init_mode as i32).to_string(),
+            ),
+            (
+                "number_of_first_objects",
+                self.number_of_first_objects.to_string(),
+            ),
+            (
+                "number_of_first_clusters",
+                self.number_of_first_clusters.to_string(),
+            ),
+            (
+                "number_of_second_objects",
+                self.number_of_second_objects.to_string(),
+            ),
+            (
+                "number_of_second_clusters",
+                self.number_of_second_clusters.to_string(),
+            ),
+            (
+                "number_of_third_clusters",
+                self.number_of_third_clusters.to_string(),
+            ),
+            ("number_of_objects", self.number_of_objects.to_string()),
+            (
+                "number_of_subvectors",
+                self.number_of_subvectors.to_string(),
+            ),
+            (
+                "optimization_clustering_init_mode",
+                (self.optimization_clustering_init_mode as i32).to_string(),
+            ),
+            ("rotation_iteration", self.rotation_iteration.to_string()),
+            ("subvector_iteration", self.subvector_iteration.to_string()),
+            ("number_of_matrices", self.number_of_matrices.to_string()),
+            ("rotation", self.rotation.to_string()),
+            ("repositioning", self.repositioning.to_string()),
+        ];
+        Params::write(dir, Self::PARAMS_FILE, &values)
+    }
+
     pub(crate) unsafe fn into_raw(self) -> sys::QBGBuildParameters {
         sys::QBGBuildParameters {
             hierarchical_clustering_init_mode: self.hierarchical_clustering_init_mode as i32,
@@ -281,8 +603,124 @@ impl QbgBuildParams {
     }
 }
 
+/// The settings of [`QbgBuildParams`][] as (de)serialized, the missing ones get their
+/// default value when deserializing.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BuildParamsRepr {
+    hierarchical_clustering_init_mode: Option<QbgClusteringInitMode>,
+    number_of_first_objects: Option<usize>,
+    number_of_first_clusters: Option<usize>,
+    number_of_second_objects: Option<usize>,
+    number_of_second_clusters: Option<usize>,
+    number_of_third_clusters: Option<usize>,
+    number_of_objects: Option<usize>,
+    number_of_subvectors: Option<usize>,
+    optimization_clustering_init_mode: Option<QbgClusteringInitMode>,
+    rotation_iteration: Option<usize>,
+    subvector_iteration: Option<usize>,
+    number_of_matrices: Option<usize>,
+    rotation: Option<bool>,
+    repositioning: Option<bool>,
+}
+
+#[cfg(feature = "serde")]
+impl BuildParamsRepr {
+    fn into_params(self) -> Result<QbgBuildParams> {
+        let mut params = QbgBuildParams::default();
+        if let Some(value) = self.hierarchical_clustering_init_mode {
+            params = params.hierarchical_clustering_init_mode(value);
+        }
+        if let Some(value) = self.number_of_first_objects {
+            params = params.number_of_first_objects(value);
+        }
+        if let Some(value) = self.number_of_first_clusters {
+            params = params.number_of_first_clusters(value);
+        }
+        if let Some(value) = self.number_of_second_objects {
+            params = params.number_of_second_objects(value);
+        }
+        if let Some(value) = self.number_of_second_clusters {
+            params = params.number_of_second_clusters(value);
+        }
+        if let Some(value) = self.number_of_third_clusters {
+            params = params.number_of_third_clusters(value);
+        }
+        if let Some(value) = self.number_of_objects {
+            params = params.number_of_objects(value);
+        }
+        if let Some(value) = self.number_of_subvectors {
+            params = params.number_of_subvectors(value);
+        }
+        if let Some(value) = self.optimization_clustering_init_mode {
+            params = params.optimization_clustering_init_mode(value);
+        }
+        if let Some(value) = self.rotation_iteration {
+            params = params.rotation_iteration(value);
+        }
+        if let Some(value) = self.subvector_iteration {
+            params = params.subvector_iteration(value);
+        }
+        if let Some(value) = self.number_of_matrices {
+            params = params.number_of_matrices(value);
+        }
+        if let Some(value) = self.rotation {
+            params = params.rotation(value);
+        }
+        if let Some(value) = self.repositioning {
+            params = params.repositioning(value);
+        }
+        if params.number_of_subvectors == 0 {
+            Err("The number of subvectors must be greater than 0".to_string())?
+        }
+        Ok(params)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for QbgBuildParams {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let repr = BuildParamsRepr {
+            hierarchical_clustering_init_mode: Some(self.hierarchical_clustering_init_mode),
+            number_of_first_objects: Some(self.number_of_first_objects),
+            number_of_first_clusters: Some(self.number_of_first_clusters),
+            number_of_second_objects: Some(self.number_of_second_objects),
+            number_of_second_clusters: Some(self.number_of_second_clusters),
+            number_of_third_clusters: Some(self.number_of_third_clusters),
+            number_of_objects: Some(self.number_of_objects),
+            number_of_subvectors: Some(self.number_of_subvectors),
+            optimization_clustering_init_mode: Some(self.optimization_clustering_init_mode),
+            rotation_iteration: Some(self.rotation_iteration),
+            subvector_iteration: Some(self.subvector_iteration),
+            number_of_matrices: Some(self.number_of_matrices),
+            rotation: Some(self.rotation),
+            repositioning: Some(self.repositioning),
+        };
+        serde::Serialize::serialize(&repr, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for QbgBuildParams {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let repr = <BuildParamsRepr as serde::Deserialize>::deserialize(deserializer)?;
+        repr.into_params().map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "serde")]
+    use std::error::Error as StdError;
+    #[cfg(feature = "serde")]
+    use std::result::Result as StdResult;
+
     use super::*;
 
     #[test]
@@ -296,4 +734,36 @@ mod tests {
         let params = QbgConstructParams::<f32>::dimension(513);
         assert_eq!(params.extended_dimension, 528);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_qbg_params_serde() -> StdResult<(), Box<dyn StdError>> {
+        // Missing settings get their default value
+        let json = r#"{"dimension": 3, "number_of_subvectors": 4}"#;
+        let params: QbgConstructParams<f32> = serde_json::from_str(json)?;
+        assert_eq!(
+            params,
+            QbgConstructParams::dimension(3).number_of_subvectors(4)
+        );
+        let json = serde_json::to_string(&params)?;
+        assert_eq!(
+            serde_json::from_str::<QbgConstructParams<f32>>(&json)?,
+            params
+        );
+
+        // Invalid settings are rejected
+        let json = r#"{"dimension": 3, "extended_dimension": 8}"#;
+        assert!(serde_json::from_str::<QbgConstructParams<f32>>(json).is_err());
+        let json = r#"{"dimension": 3, "number_of_subvectors": 3}"#;
+        assert!(serde_json::from_str::<QbgConstructParams<f32>>(json).is_err());
+
+        let params: QbgBuildParams = serde_json::from_str(r#"{"rotation": false}"#)?;
+        assert_eq!(params, QbgBuildParams::default().rotation(false));
+        let json = serde_json::to_string(&params)?;
+        assert_eq!(serde_json::from_str::<QbgBuildParams>(&json)?, params);
+        let json = r#"{"number_of_subvectors": 0}"#;
+        assert!(serde_json::from_str::<QbgBuildParams>(json).is_err());
+
+        Ok(())
+    }
 }
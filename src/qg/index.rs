@@ -1,4 +1,5 @@
-use std::ffi::CString;
+use std::ffi::{CString, OsStr};
+use std::fs;
 use std::mem;
 use std::os::unix::ffi::OsStrExt;
 use std::path::Path;
@@ -9,25 +10,41 @@ use ngt_sys as sys;
 use scopeguard::defer;
 
 use super::{QgObject, QgObjectType, QgProperties, QgQuantizationParams};
-use crate::error::{make_err, Error, Result};
-use crate::ngt::NgtIndex;
+use crate::error::{make_err, Error, ErrorKind, Result};
+use crate::ngt::maintenance::{copy_dir, staging_dir, swap_in, QG_DIR};
+use crate::ngt::{distance, IndexMode, ModeRead, ModeWrite, NgtIndex, NgtObjectType, Tombstones};
 use crate::qg::QgDistance;
+use crate::query::QueryVec;
 use crate::{SearchResult, VecId};
 
+/// A quantized graph index, opened in read mode by default.
+///
+/// Vectors can only be [`appended`](QgIndex::append) or [`removed`](QgIndex::remove)
+/// in write mode, see [`into_writable`](QgIndex::into_writable).
 #[derive(Debug)]
-pub struct QgIndex<T> {
+pub struct QgIndex<T, M = ModeRead> {
+    pub(crate) path: CString,
     pub(crate) prop: QgProperties<T>,
     pub(crate) index: sys::NGTQGIndex,
+    _mode: M,
     ebuf: sys::NGTError,
+    pending: Vec<Vec<T>>,
+    tombstones: Tombstones,
+    normalize: bool,
 }
 
-impl<T> QgIndex<T>
+impl<T> QgIndex<T, ModeRead>
 where
     T: QgObjectType,
 {
-    /// Quantize an NGT index
+    /// Quantize an NGT index, the parameters are
+    /// [`validated`](QgQuantizationParams::validate) beforehand.
+    ///
+    /// NGT exposes neither a progress callback nor counters while quantizing, so the
+    /// progress of the quantization can't be reported.
     pub fn quantize(index: NgtIndex<T>, params: QgQuantizationParams) -> Result<Self> {
         QgDistance::try_from(index.prop.distance_type)?;
+        params.validate(index.prop.dimension as usize)?;
 
         unsafe {
             let ebuf = sys::ngt_create_error_object();
@@ -35,42 +52,251 @@ where
 
             let path = index.path.clone();
             drop(index); // Close the index
-            if !sys::ngtqg_quantize(path.as_ptr(), params.into_raw(), ebuf) {
+            if !sys::ngtqg_quantize(path.as_ptr(), params.clone().into_raw(), ebuf) {
                 Err(make_err(ebuf))?
             }
+            params.write(&Path::new(OsStr::from_bytes(path.as_bytes())).join(QG_DIR))?;
 
-            QgIndex::open(path.into_string()?)
+            QgIndex::open(Path::new(OsStr::from_bytes(path.as_bytes())))
         }
     }
 
-    /// Open the already existing quantized index at the specified path.
+    /// Open the already existing quantized index at the specified path, in read mode.
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with(path, ModeRead)
+    }
+
+    /// Reopen the index in write mode, to [`append`](QgIndex::append) or
+    /// [`remove`](QgIndex::remove) vectors.
+    pub fn into_writable(self) -> Result<QgIndex<T, ModeWrite>> {
+        let (path, normalize) = (self.path.clone(), self.normalize);
+        drop(self);
+        QgIndex::open_with(Path::new(OsStr::from_bytes(path.as_bytes())), ModeWrite)?
+            .normalize(normalize)
+    }
+}
+
+impl<T> QgIndex<T, ModeWrite>
+where
+    T: QgObjectType,
+{
+    /// Buffer the specified vector, to be inserted into the underlying NGT index by
+    /// the next [`requantize`](QgIndex::requantize). It isn't searchable until then.
+    ///
+    /// Buffered vectors are lost if the index is dropped before being requantized.
+    pub fn append(&mut self, vec: Vec<T>) -> Result<()>
+    where
+        T: NgtObjectType,
+    {
+        if vec.len() != self.dimension() {
+            Err(format!(
+                "Inconsistent vector dim, expected: {} got: {}",
+                self.dimension(),
+                vec.len()
+            ))?
+        }
+        let vec = if self.normalize {
+            distance::normalize(&vec)
+        } else {
+            vec
+        };
+        self.pending.push(vec);
+        Ok(())
+    }
+
+    /// The number of vectors buffered by [`append`](QgIndex::append).
+    pub fn nb_pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Insert the vectors buffered by [`append`](QgIndex::append) into the underlying
+    /// NGT index, build it with `num_threads` threads and quantize it again. Returns
+    /// the ids of the inserted vectors.
+    ///
+    /// The NGT index is copied to a fresh directory next to the index one, where it is
+    /// updated and quantized. It is then swapped with the index directory, only once
+    /// the quantization succeeded, so that the index and its buffered vectors are left
+    /// untouched on failure.
+    pub fn requantize(
+        &mut self,
+        params: QgQuantizationParams,
+        num_threads: usize,
+    ) -> Result<Vec<VecId>>
+    where
+        T: NgtObjectType,
+    {
+        params.validate(self.dimension())?;
+        self.persist()?;
+
+        let dir = self.path().to_path_buf();
+        let (_staging, work) = staging_dir(&dir, "requantize")?;
+        copy_dir(&dir, &work)?;
+        fs::remove_dir_all(work.join(QG_DIR))?;
+
+        let mut index = NgtIndex::<T>::open(&work)?;
+        let ids = self
+            .pending
+            .iter()
+            .map(|vec| index.insert(vec.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        index.build(num_threads)?;
+        index.persist()?;
+        drop(QgIndex::quantize(index, params)?);
+
+        // The previous index ends up in the staging directory, removed when dropped
+        swap_in(&work, &dir, |_| true)?;
+        *self = QgIndex::open_with(&dir, ModeWrite)?.normalize(self.normalize)?;
+        Ok(ids)
+    }
+
+    /// Remove the specified vector, it is then excluded from search results.
+    ///
+    /// The quantized structures can't be modified through NGT's C API, so the vector
+    /// is marked as removed in a sidecar file of the index directory when
+    /// [`persisting`](QgIndex::persist) it, shared with the underlying NGT index. It is
+    /// physically removed by calling [`compact`](NgtIndex::compact) on the index
+    /// returned by [`into_ngt`](QgIndex::into_ngt), before quantizing it again.
+    pub fn remove(&mut self, id: VecId) -> Result<()> {
+        if self.tombstones.contains(id) || self.get_vec(id).is_err() {
+            Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Vector {id} not found"),
+            ))?
+        }
+        self.tombstones.insert(id);
+        Ok(())
+    }
+
+    /// Persist the changes made since the index was opened to the disk.
+    ///
+    /// The quantized structures themselves are immutable, so only the
+    /// [`removals`](QgIndex::remove) are persisted. The vectors buffered by
+    /// [`append`](QgIndex::append) are only persisted by
+    /// [`requantize`](QgIndex::requantize).
+    pub fn persist(&mut self) -> Result<()> {
+        self.tombstones.save(self.path())
+    }
+
+    /// Reopen the index in read mode, the changes that haven't been
+    /// [`persisted`](QgIndex::persist) are lost.
+    pub fn into_readable(self) -> Result<QgIndex<T, ModeRead>> {
+        let (path, normalize) = (self.path.clone(), self.normalize);
+        drop(self);
+        let dir = Path::new(OsStr::from_bytes(path.as_bytes()));
+        QgIndex::open(dir)?.normalize(normalize)
+    }
+}
+
+impl<T, M> QgIndex<T, M>
+where
+    T: QgObjectType,
+    M: IndexMode,
+{
+    fn open_with<P: AsRef<Path>>(path: P, mode: M) -> Result<Self> {
         if !path.as_ref().exists() {
-            Err(Error(format!("Path {:?} does not exist", path.as_ref())))?
+            Err(format!("Path {:?} does not exist", path.as_ref()))?
         }
 
         unsafe {
             let ebuf = sys::ngt_create_error_object();
             defer! { sys::ngt_destroy_error_object(ebuf); }
 
-            let path = CString::new(path.as_ref().as_os_str().as_bytes())?;
+            let dir = path.as_ref();
+            let path = CString::new(dir.as_os_str().as_bytes())?;
 
             let index = sys::ngtqg_open_index(path.as_ptr(), ebuf);
             if index.is_null() {
                 Err(make_err(ebuf))?
             }
 
-            let prop = QgProperties::from(index)?;
+            let mut prop = QgProperties::from(index)?;
+            prop.quantization_params = QgQuantizationParams::read(&dir.join(QG_DIR))?;
+            let tombstones = Tombstones::load(dir)?;
 
             Ok(QgIndex {
+                path,
                 prop,
                 index,
+                _mode: mode,
                 ebuf: sys::ngt_create_error_object(),
+                pending: Vec::new(),
+                tombstones,
+                normalize: false,
             })
         }
     }
 
-    pub fn search(&self, query: QgQuery<T>) -> Result<Vec<SearchResult>> {
+    /// Normalize the [`appended`](QgIndex::append) vectors and the queries to a unit
+    /// norm, which is opt-in and only supported for the [`Cosine`](QgDistance::Cosine)
+    /// distance of float vectors.
+    ///
+    /// The quantization is more accurate on normalized vectors, so the vectors inserted
+    /// in the NGT index before [`quantizing`](QgIndex::quantize) it should be normalized
+    /// as well, with [`distance::normalize`](crate::distance::normalize). The setting
+    /// isn't persisted, it must be enabled again when reopening the index.
+    pub fn normalize(mut self, normalize: bool) -> Result<Self> {
+        if normalize && self.prop.distance_type != QgDistance::Cosine {
+            Err("Only vectors of Cosine indexes can be normalized".to_string())?
+        }
+        if normalize && self.prop.object_type == QgObject::Uint8 {
+            Err("Only float vectors can be normalized".to_string())?
+        }
+        self.normalize = normalize;
+        Ok(self)
+    }
+
+    /// Reopen the underlying NGT index, which is kept alongside the quantized one, so
+    /// that vectors can be inserted before [`quantizing`](QgIndex::quantize) it again.
+    ///
+    /// The changes that haven't been [`persisted`](QgIndex::persist) are lost, see
+    /// [`requantize`](QgIndex::requantize) for the whole insert, build and quantize
+    /// cycle.
+    pub fn into_ngt(self) -> Result<NgtIndex<T>>
+    where
+        T: NgtObjectType,
+    {
+        let path = self.path.clone();
+        drop(self); // Close the quantized index
+        NgtIndex::open(Path::new(OsStr::from_bytes(path.as_bytes())))
+    }
+
+    /// Search the nearest vectors to the specified query.
+    ///
+    /// With [`rerank_exact`](QgQuery::rerank_exact), the candidates found on the
+    /// quantized graph are reranked by their exact distances to the query.
+    pub fn search(&self, query: QgQuery<T>) -> Result<Vec<SearchResult>>
+    where
+        T: NgtObjectType,
+    {
+        let mut query = if self.normalize {
+            QgQuery {
+                query: QueryVec::Owned(distance::normalize(&query.query)),
+                ..query
+            }
+        } else {
+            query
+        };
+
+        // Over-fetch so that enough results remain once the removed ones are filtered
+        let size = query.size.max(query.rerank.unwrap_or_default());
+        query.size = size + self.tombstones.len();
+        let mut res = self.search_quantized(&query)?;
+        res.retain(|r| !self.tombstones.contains(r.id));
+        res.truncate(size);
+
+        if let Some(k) = query.rerank {
+            let distance_type = self.prop.distance_type.into();
+            for r in res.iter_mut() {
+                r.distance = distance::compute(distance_type, &query.query, &self.get_vec(r.id)?)?;
+            }
+            res.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+            res.truncate(k);
+        }
+
+        Ok(res)
+    }
+
+    fn search_quantized(&self, query: &QgQuery<T>) -> Result<Vec<SearchResult>> {
         unsafe {
             let results = sys::ngt_create_empty_results(self.ebuf);
             if results.is_null() {
@@ -199,7 +425,55 @@ where
     }
 }
 
-impl<T> Drop for QgIndex<T> {
+impl<T, M> QgIndex<T, M> {
+    /// The properties of the index, read back from the opened index.
+    pub fn properties(&self) -> &QgProperties<T> {
+        &self.prop
+    }
+
+    /// The path of the index directory.
+    pub fn path(&self) -> &Path {
+        Path::new(OsStr::from_bytes(self.path.as_bytes()))
+    }
+
+    /// The dimension of the vectors of the index.
+    pub fn dimension(&self) -> usize {
+        self.prop.dimension as usize
+    }
+
+    /// The distance function of the index.
+    pub fn distance_type(&self) -> QgDistance {
+        self.prop.distance_type
+    }
+
+    /// The type of the vectors of the index.
+    pub fn object_type(&self) -> QgObject {
+        self.prop.object_type
+    }
+
+    /// The number of edges of each node when creating the graph.
+    pub fn creation_edge_size(&self) -> usize {
+        self.prop.creation_edge_size as usize
+    }
+
+    /// The number of edges explored from each node when searching.
+    pub fn search_edge_size(&self) -> usize {
+        self.prop.search_edge_size as usize
+    }
+
+    /// The number of vectors inserted in the underlying NGT index (but not
+    /// necessarily indexed), excluding the [`pending`](QgIndex::nb_pending) ones.
+    pub fn nb_inserted(&self) -> usize {
+        unsafe { sys::ngt_get_number_of_objects(self.index, self.ebuf) as usize }
+    }
+
+    /// The number of indexed vectors of the underlying NGT index.
+    pub fn nb_indexed(&self) -> usize {
+        unsafe { sys::ngt_get_number_of_indexed_objects(self.index, self.ebuf) as usize }
+    }
+}
+
+impl<T, M> Drop for QgIndex<T, M> {
     fn drop(&mut self) {
         if !self.index.is_null() {
             unsafe { sys::ngtqg_close_index(self.index) };
@@ -212,13 +486,24 @@ impl<T> Drop for QgIndex<T> {
     }
 }
 
+/// A search query along with its parameters, see [`QgIndex::search`][].
+///
+/// NGT can't abort a search once started, its latency is bounded by the explored
+/// part of the graph instead, that is by [`epsilon`](QgQuery::epsilon) and
+/// [`result_expansion`](QgQuery::result_expansion).
+///
+/// Contrary to [`NgtQuery`](crate::NgtQuery), the number of edges explored from each
+/// node can't be set per query, as NGT's QG search parameters don't include it. The
+/// [`search_edge_size`](QgIndex::search_edge_size) of the index is used instead.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QgQuery<'a, T> {
-    query: &'a [T],
+    query: QueryVec<'a, T>,
     pub size: usize,
     pub epsilon: f32,
     pub result_expansion: f32,
     pub radius: f32,
+    pub rerank: Option<usize>,
 }
 
 impl<'a, T> QgQuery<'a, T>
@@ -226,12 +511,42 @@ where
     T: QgObjectType,
 {
     pub fn new(query: &'a [T]) -> Self {
+        Self::with_query(QueryVec::Borrowed(query))
+    }
+
+    /// Create a query from `f64` values, converting them to the index's object type.
+    ///
+    /// Values are rounded to the nearest float for `f32` and `f16`. Fails if a value
+    /// can't be represented by the object type, for instance a fractional or negative
+    /// value for `u8`, or an out of range value for `f16`.
+    pub fn from_f64(query: &[f64]) -> Result<QgQuery<'static, T>>
+    where
+        T: NgtObjectType,
+    {
+        let query = QueryVec::convert(query, <T as NgtObjectType>::as_obj(), T::try_from_f64)?;
+        Ok(QgQuery::with_query(query))
+    }
+
+    /// Create a query from `i32` values, converting them to the index's object type.
+    ///
+    /// Fails if a value can't be represented exactly by the object type, for instance
+    /// a negative value for `u8`, or a value above `2^24` in magnitude for `f32`.
+    pub fn from_i32(query: &[i32]) -> Result<QgQuery<'static, T>>
+    where
+        T: NgtObjectType,
+    {
+        let query = QueryVec::convert(query, <T as NgtObjectType>::as_obj(), T::try_from_i32)?;
+        Ok(QgQuery::with_query(query))
+    }
+
+    fn with_query(query: QueryVec<'a, T>) -> Self {
         Self {
             query,
             size: 20,
             epsilon: 0.03,
             result_expansion: 3.0,
             radius: f32::MAX,
+            rerank: None,
         }
     }
 
@@ -255,6 +570,17 @@ where
         self
     }
 
+    /// Rerank the [`size`](QgQuery::size) candidates of the quantized search by their
+    /// exact distances to the query, computed from the stored vectors, and keep the
+    /// `k` nearest ones.
+    ///
+    /// The size should be a few times larger than `k` to recover the accuracy lost by
+    /// the quantization, it is raised to `k` if lower.
+    pub fn rerank_exact(mut self, k: usize) -> Self {
+        self.rerank = Some(k);
+        self
+    }
+
     unsafe fn params(&self) -> sys::NGTQGQueryParameters {
         sys::NGTQGQueryParameters {
             size: self.size,
@@ -277,6 +603,267 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_qg_into_ngt() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+
+        // Create an NGT index for vectors
+        let ndims = 3;
+        let props = QgProperties::<f32>::dimension(ndims)?.distance_type(QgDistance::L2)?;
+        let mut index = NgtIndex::create(dir.path(), props.try_into()?)?;
+
+        // Insert vectors, build and persist the index
+        let nvecs = 64;
+        for i in 0..nvecs {
+            index.insert(vec![i as f32; ndims])?;
+        }
+        index.build(1)?;
+        index.persist()?;
+
+        // Quantize the index and get back the NGT index
+        let params = QgQuantizationParams {
+            dimension_of_subvector: 1.,
+            max_number_of_edges: 50,
+        };
+        let index = QgIndex::quantize(index, params.clone())?;
+        let mut index = index.into_ngt()?;
+        assert_eq!(index.nb_inserted(), nvecs);
+
+        // Insert a new vector and quantize the index again
+        let id = index.insert(vec![1000.0; ndims])?;
+        index.build(1)?;
+        index.persist()?;
+        let index = QgIndex::quantize(index, params)?;
+
+        let query = vec![1000.0; ndims];
+        let res = index.search(QgQuery::new(&query).size(1))?;
+        assert_eq!(id, res[0].id);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_qg_normalize() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+
+        // Create and quantize an NGT index of normalized vectors
+        let ndims = 2;
+        let props = QgProperties::<f32>::dimension(ndims)?.distance_type(QgDistance::Cosine)?;
+        let mut index = NgtIndex::create(dir.path(), props.try_into()?)?;
+        for i in 0..64 {
+            let angle = i as f32 / 64. * std::f32::consts::FRAC_PI_2;
+            index.insert(distance::normalize(&[angle.cos() * 10., angle.sin() * 10.]))?;
+        }
+        index.build(1)?;
+        index.persist()?;
+        let params = QgQuantizationParams {
+            dimension_of_subvector: 1.,
+            max_number_of_edges: 50,
+        };
+        let index = QgIndex::quantize(index, params.clone())?;
+        let mut index = index.normalize(true)?.into_writable()?;
+
+        // Appended vectors are normalized, and the queries as well
+        index.append(vec![0.0, 5.0])?;
+        let ids = index.requantize(params, 1)?;
+        assert_eq!(index.get_vec(ids[0])?, vec![0.0, 1.0]);
+        let res = index.search(QgQuery::new(&[0.0, 3.0]).size(1))?;
+        assert_eq!(res[0].id, ids[0]);
+
+        // Only float vectors of Cosine indexes can be normalized
+        drop(index);
+        let dir_l2 = tempdir()?;
+        let props = QgProperties::<f32>::dimension(ndims)?;
+        let mut index = NgtIndex::create(dir_l2.path(), props.try_into()?)?;
+        for i in 0..64 {
+            index.insert(vec![i as f32; ndims])?;
+        }
+        index.build(1)?;
+        index.persist()?;
+        let index = QgIndex::quantize(index, QgQuantizationParams::default())?;
+        assert!(index.normalize(true).is_err());
+
+        dir.close()?;
+        dir_l2.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_qg_rerank_exact() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+
+        // Create and quantize an NGT index
+        let ndims = 4;
+        let props = QgProperties::<f32>::dimension(ndims)?.distance_type(QgDistance::L2)?;
+        let mut index = NgtIndex::create(dir.path(), props.try_into()?)?;
+        for i in 0..100 {
+            index.insert(vec![i as f32; ndims])?;
+        }
+        index.build(1)?;
+        index.persist()?;
+        let params = QgQuantizationParams {
+            dimension_of_subvector: 2.,
+            max_number_of_edges: 50,
+        };
+        let index = QgIndex::quantize(index, params)?;
+
+        // The candidates are reranked by their exact distances
+        let query = vec![42.2; ndims];
+        let res = index.search(QgQuery::new(&query).size(20).rerank_exact(3))?;
+        assert_eq!(res.len(), 3);
+        assert!(res.windows(2).all(|w| w[0].distance <= w[1].distance));
+        let exact = distance::compute(QgDistance::L2.into(), &query, &index.get_vec(res[0].id)?)?;
+        assert_eq!(res[0].distance, exact);
+        assert_eq!(index.get_vec(res[0].id)?, vec![42.0; ndims]);
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_qg_quantization_params() -> StdResult<(), Box<dyn StdError>> {
+        // The most edges fitting the budget are chosen, then the smallest subvectors
+        let params = QgQuantizationParams::auto_for(128, 10_000)?;
+        assert_eq!(params.max_number_of_edges, 128);
+        assert_eq!(params.dimension_of_subvector, 1.);
+        params.validate(128)?;
+        let params = QgQuantizationParams::auto_for(128, 1_000)?;
+        assert_eq!(params.max_number_of_edges, 128);
+        assert_eq!(params.dimension_of_subvector, 32.);
+        assert!(QgQuantizationParams::auto_for(128, 10).is_err());
+
+        // Incompatible parameters are rejected before calling NGT
+        let invalid = |dimension_of_subvector, max_number_of_edges| QgQuantizationParams {
+            dimension_of_subvector,
+            max_number_of_edges,
+        };
+        assert!(invalid(0., 128).validate(128).is_ok());
+        assert!(invalid(3., 128).validate(128).is_err());
+        assert!(invalid(1.5, 128).validate(128).is_err());
+        assert!(invalid(-1., 128).validate(128).is_err());
+        assert!(invalid(f32::NAN, 128).validate(128).is_err());
+        assert!(invalid(2., 0).validate(128).is_err());
+
+        let dir = tempdir()?;
+        let props = QgProperties::<f32>::dimension(3)?;
+        let index = NgtIndex::create(dir.path(), props.try_into()?)?;
+        assert!(QgIndex::quantize(index, invalid(2., 128)).is_err());
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_qg_remove() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+
+        // Create and quantize an NGT index
+        let ndims = 3;
+        let props = QgProperties::<f32>::dimension(ndims)?.distance_type(QgDistance::L2)?;
+        let mut index = NgtIndex::create(dir.path(), props.try_into()?)?;
+        for i in 0..64 {
+            index.insert(vec![i as f32; ndims])?;
+        }
+        index.build(1)?;
+        index.persist()?;
+        let params = QgQuantizationParams {
+            dimension_of_subvector: 1.,
+            max_number_of_edges: 50,
+        };
+        let mut index = QgIndex::quantize(index, params)?.into_writable()?;
+
+        // Removed vectors are excluded from the results
+        let query = vec![10.0; ndims];
+        let id = index.search(QgQuery::new(&query).size(1))?[0].id;
+        index.remove(id)?;
+        assert!(index.remove(id).is_err());
+        assert!(index.remove(1000).is_err());
+        let res = index.search(QgQuery::new(&query).size(5))?;
+        assert_eq!(res.len(), 5);
+        assert!(res.iter().all(|r| r.id != id));
+
+        // The removal is kept when reopening the index, quantized or not
+        index.persist()?;
+        drop(index);
+        let index = QgIndex::<f32>::open(dir.path())?;
+        let res = index.search(QgQuery::new(&query).size(5))?;
+        assert!(res.iter().all(|r| r.id != id));
+        let index = index.into_ngt()?;
+        assert!(index.ids().all(|i| i != id));
+
+        dir.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_qg_requantize() -> StdResult<(), Box<dyn StdError>> {
+        // Get a temporary directory to store the index
+        let dir = tempdir()?;
+
+        // Create and quantize an NGT index
+        let ndims = 3;
+        let props = QgProperties::<f32>::dimension(ndims)?.distance_type(QgDistance::L2)?;
+        let mut index = NgtIndex::create(dir.path(), props.try_into()?)?;
+        for i in 0..64 {
+            index.insert(vec![i as f32; ndims])?;
+        }
+        index.build(1)?;
+        index.persist()?;
+        let params = QgQuantizationParams {
+            dimension_of_subvector: 1.,
+            max_number_of_edges: 50,
+        };
+        let mut index = QgIndex::quantize(index, params.clone())?.into_writable()?;
+
+        // A failed requantization leaves the index and its buffered vectors untouched
+        index.append(vec![1000.0; ndims])?;
+        let invalid = QgQuantizationParams {
+            max_number_of_edges: 0,
+            ..params.clone()
+        };
+        assert!(index.requantize(invalid, 1).is_err());
+        assert_eq!(index.nb_pending(), 1);
+        assert_eq!(index.nb_inserted(), 64);
+        let query = vec![0.0; ndims];
+        assert_eq!(index.search(QgQuery::new(&query).size(1))?.len(), 1);
+        let staging = format!(
+            ".{}.staging.",
+            dir.path().file_name().unwrap().to_string_lossy()
+        );
+        let entries = fs::read_dir(dir.path().parent().unwrap())?;
+        assert!(entries
+            .filter_map(|e| e.ok())
+            .all(|e| !e.file_name().to_string_lossy().starts_with(&staging)));
+
+        // Appended vectors are searchable once requantized
+        assert!(index.append(vec![1000.0; ndims + 1]).is_err());
+        assert_eq!(index.nb_pending(), 1);
+        assert_eq!(index.nb_inserted(), 64);
+        let prop = index.properties();
+        assert_eq!(prop.get_dimension(), ndims);
+        assert_eq!(prop.get_distance_type(), QgDistance::L2);
+        assert_eq!(prop.get_object_type(), QgObject::Float);
+        assert_eq!(prop.get_quantization_params(), Some(&params));
+        let ids = index.requantize(params, 1)?;
+        assert_eq!(index.nb_pending(), 0);
+        assert_eq!(index.nb_inserted(), 65);
+        assert_eq!(index.nb_indexed(), 65);
+
+        let query = vec![1000.0; ndims];
+        let res = index.search(QgQuery::new(&query).size(1))?;
+        assert_eq!(ids, vec![res[0].id]);
+        let index = index.into_readable()?;
+        assert_eq!(index.nb_inserted(), 65);
+
+        dir.close()?;
+        Ok(())
+    }
+
     #[test]
     fn test_qg_f32() -> StdResult<(), Box<dyn StdError>> {
         // Get a temporary directory to store the index
@@ -311,6 +898,9 @@ mod tests {
             max_number_of_edges: 50,
         };
         let index = QgIndex::quantize(index, params)?;
+        assert_eq!(index.path(), dir.path());
+        assert_eq!(index.dimension(), ndims);
+        assert_eq!(index.distance_type(), QgDistance::L2);
 
         // Perform a vector search (with 3 results)
         let v: Vec<f32> = (1..=ndims).into_iter().map(|x| x as f32).collect();
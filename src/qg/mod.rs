@@ -37,7 +37,7 @@
 //! let id1 = index.insert(vec1)?;
 //! let id2 = index.insert(vec2)?;
 //!
-//! // Add enough dummy vectors to build an index
+//! // Insert more vectors, the quantization codebooks are trained on them
 //! for i in 0..64 {
 //!     index.insert(vec![100. + i as f32; 3])?;
 //! }
@@ -73,3 +73,4 @@ pub use self::index::{QgIndex, QgQuery};
 pub use self::properties::{
     QgDistance, QgObject, QgObjectType, QgProperties, QgQuantizationParams,
 };
+pub use crate::ngt::{IndexMode, ModeRead, ModeWrite};
@@ -1,4 +1,7 @@
+use std::fs;
+use std::io::ErrorKind as IoErrorKind;
 use std::marker::PhantomData;
+use std::path::Path;
 use std::ptr;
 
 use half::f16;
@@ -6,11 +9,12 @@ use ngt_sys as sys;
 use num_enum::TryFromPrimitive;
 use scopeguard::defer;
 
-use crate::error::{make_err, Result};
+use crate::error::{make_err, Error, Result};
 use crate::ngt::NgtObjectType;
 use crate::{NgtDistance, NgtProperties};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum QgObject {
     Uint8 = 1,
@@ -48,6 +52,7 @@ impl QgObjectType for f16 {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum QgDistance {
     L2 = 1,
@@ -82,6 +87,7 @@ pub struct QgProperties<T> {
     pub(crate) search_edge_size: i16,
     pub(crate) object_type: QgObject,
     pub(crate) distance_type: QgDistance,
+    pub(crate) quantization_params: Option<QgQuantizationParams>,
     pub(crate) raw_prop: sys::NGTProperty,
     _marker: PhantomData<T>,
 }
@@ -121,6 +127,7 @@ where
                 search_edge_size,
                 object_type,
                 distance_type,
+                quantization_params: None,
                 raw_prop,
                 _marker: PhantomData,
             })
@@ -149,6 +156,7 @@ where
                 search_edge_size: self.search_edge_size,
                 object_type: self.object_type,
                 distance_type: self.distance_type,
+                quantization_params: self.quantization_params.clone(),
                 raw_prop,
                 _marker: PhantomData,
             })
@@ -202,6 +210,7 @@ where
                 search_edge_size,
                 object_type,
                 distance_type,
+                quantization_params: None,
                 raw_prop,
                 _marker: PhantomData,
             })
@@ -280,6 +289,51 @@ where
         Ok(())
     }
 
+    /// The dimension of the vectors.
+    pub fn get_dimension(&self) -> usize {
+        self.dimension as usize
+    }
+
+    /// The number of edges of each node when creating the graph.
+    pub fn get_creation_edge_size(&self) -> usize {
+        self.creation_edge_size as usize
+    }
+
+    /// The number of edges explored from each node when searching.
+    pub fn get_search_edge_size(&self) -> usize {
+        self.search_edge_size as usize
+    }
+
+    /// The type of the vectors, determined by `T`.
+    pub fn get_object_type(&self) -> QgObject {
+        self.object_type
+    }
+
+    /// The distance function, see [`QgDistance`][].
+    pub fn get_distance_type(&self) -> QgDistance {
+        self.distance_type
+    }
+
+    /// The parameters the index was quantized with, only known for indexes quantized
+    /// by [`QgIndex::quantize`](crate::qg::QgIndex::quantize).
+    pub fn get_quantization_params(&self) -> Option<&QgQuantizationParams> {
+        self.quantization_params.as_ref()
+    }
+
+    /// Check that the properties are coherent, which is done when converting them to
+    /// [`NgtProperties`][] to create an index.
+    ///
+    /// A dimension or a creation edge size of zero is rejected.
+    pub fn validate(&self) -> Result<()> {
+        if self.dimension == 0 {
+            Err("The dimension must be greater than 0".to_string())?
+        }
+        if self.creation_edge_size == 0 {
+            Err("The creation edge size must be greater than 0".to_string())?
+        }
+        Ok(())
+    }
+
     pub fn distance_type(mut self, distance_type: QgDistance) -> Result<Self> {
         self.distance_type = distance_type;
         unsafe { Self::set_distance_type(self.raw_prop, distance_type)? };
@@ -327,6 +381,7 @@ where
     type Error = crate::Error;
 
     fn try_from(prop: QgProperties<T>) -> Result<Self> {
+        prop.validate()?;
         NgtProperties::dimension(prop.dimension as usize)?
             .creation_edge_size(prop.creation_edge_size as usize)?
             .search_edge_size(prop.search_edge_size as usize)?
@@ -334,9 +389,24 @@ where
     }
 }
 
+/// The parameters used to quantize an NGT index, see [`QgIndex::quantize`][].
+///
+/// Each subvector of `dimension_of_subvector` dimensions is encoded on 4 bits, and the
+/// codes of up to `max_number_of_edges` neighbors are stored along with each node.
+///
+/// The quantization is parallelized by NGT with OpenMP, so its thread count is set
+/// by the `OMP_NUM_THREADS` environment variable when the process starts, all the
+/// cores being used by default. NGT's C API exposes neither the thread count nor the
+/// number of vectors sampled to train the quantizer.
+///
+/// [`QgIndex::quantize`]: crate::qg::QgIndex::quantize
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QgQuantizationParams {
+    /// The dimension of the quantized subvectors, which must divide the dimension of
+    /// the index. `0.0` lets NGT choose it from the dimension of the index.
     pub dimension_of_subvector: f32,
+    /// The maximum number of edges of each node of the quantized graph.
     pub max_number_of_edges: usize,
 }
 
@@ -350,6 +420,112 @@ impl Default for QgQuantizationParams {
 }
 
 impl QgQuantizationParams {
+    /// The numbers of edges tried by [`auto_for`](QgQuantizationParams::auto_for), in
+    /// decreasing order.
+    const AUTO_EDGES: [usize; 5] = [128, 96, 64, 32, 16];
+
+    /// Parameters for vectors of the specified dimension, such that each quantized node
+    /// fits in `memory_budget` bytes.
+    ///
+    /// The most edges are favored, then the smallest subvector dimension. A node takes
+    /// about `max_number_of_edges * (dimension / dimension_of_subvector / 2 + 4)`
+    /// bytes, the 4-bit codes of its neighbors along with their ids.
+    pub fn auto_for(dimension: usize, memory_budget: usize) -> Result<Self> {
+        if dimension == 0 {
+            Err("The dimension must be greater than 0".to_string())?
+        }
+
+        let subvector_dims = (1..=dimension).filter(|&d| dimension.is_multiple_of(d));
+        for edges in Self::AUTO_EDGES {
+            for subvector_dim in subvector_dims.clone() {
+                let code_size = (dimension / subvector_dim).div_ceil(2);
+                if edges * (code_size + 4) <= memory_budget {
+                    return Ok(Self {
+                        dimension_of_subvector: subvector_dim as f32,
+                        max_number_of_edges: edges,
+                    });
+                }
+            }
+        }
+
+        Err(format!(
+            "A memory budget of {memory_budget} bytes per vector is too low to quantize \
+             vectors of dimension {dimension}"
+        ))?
+    }
+
+    /// Check that the parameters are compatible with vectors of the specified
+    /// dimension, which is done before quantizing an index.
+    ///
+    /// The following is rejected:
+    ///   - A subvector dimension that is negative, not finite, or that isn't an integer
+    ///     dividing the dimension, unless it is `0.0`.
+    ///   - A maximum number of edges of zero.
+    pub fn validate(&self, dimension: usize) -> Result<()> {
+        let subvector_dim = self.dimension_of_subvector;
+        if !subvector_dim.is_finite() || subvector_dim < 0. {
+            Err(format!("Invalid subvector dimension {subvector_dim}"))?
+        }
+        if subvector_dim > 0.
+            && (subvector_dim.fract() != 0. || !dimension.is_multiple_of(subvector_dim as usize))
+        {
+            Err(format!(
+                "The subvector dimension {subvector_dim} doesn't divide the dimension {dimension}"
+            ))?
+        }
+        if self.max_number_of_edges == 0 {
+            Err("The maximum number of edges must be greater than 0".to_string())?
+        }
+        Ok(())
+    }
+
+    /// Sidecar file of the quantized index directory, NGT doesn't keep the parameters.
+    const PARAMS_FILE: &'static str = "params";
+
+    /// Load the parameters from the `qg` directory of a quantized index, indexes
+    /// quantized by other means than [`QgIndex::quantize`](crate::qg::QgIndex::quantize)
+    /// have none.
+    pub(crate) fn read(qg_dir: &Path) -> Result<Option<Self>> {
+        let content = match fs::read_to_string(qg_dir.join(Self::PARAMS_FILE)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == IoErrorKind::NotFound => return Ok(None),
+            Err(e) => Err(e)?,
+        };
+
+        let (mut dimension_of_subvector, mut max_number_of_edges) = (None, None);
+        for line in content.lines() {
+            let parse_err = || Error::from(format!("Invalid quantization params: {line:?}"));
+            let (key, value) = line.split_once(' ').ok_or_else(parse_err)?;
+            match key {
+                "dimension_of_subvector" => {
+                    dimension_of_subvector = Some(value.parse().map_err(|_| parse_err())?)
+                }
+                "max_number_of_edges" => {
+                    max_number_of_edges = Some(value.parse().map_err(|_| parse_err())?)
+                }
+                _ => Err(parse_err())?,
+            }
+        }
+
+        let missing = |key| Error::from(format!("Missing {key} in quantization params"));
+        Ok(Some(Self {
+            dimension_of_subvector: dimension_of_subvector
+                .ok_or_else(|| missing("dimension_of_subvector"))?,
+            max_number_of_edges: max_number_of_edges
+                .ok_or_else(|| missing("max_number_of_edges"))?,
+        }))
+    }
+
+    /// Save the parameters in the `qg` directory of a quantized index.
+    pub(crate) fn write(&self, qg_dir: &Path) -> Result<()> {
+        let content = format!(
+            "dimension_of_subvector {}\nmax_number_of_edges {}\n",
+            self.dimension_of_subvector, self.max_number_of_edges
+        );
+        fs::write(qg_dir.join(Self::PARAMS_FILE), content)?;
+        Ok(())
+    }
+
     pub(crate) fn into_raw(self) -> sys::NGTQGQuantizationParameters {
         sys::NGTQGQuantizationParameters {
             dimension_of_subvector: self.dimension_of_subvector,
@@ -0,0 +1,69 @@
+use std::fmt;
+use std::ops::Deref;
+use std::result::Result as StdResult;
+
+use crate::error::Result;
+
+/// The vector of a query, borrowed from the caller or owned when it was converted
+/// from values of another type.
+#[derive(Clone)]
+pub(crate) enum QueryVec<'a, T> {
+    Borrowed(&'a [T]),
+    Owned(Vec<T>),
+}
+
+impl<T> QueryVec<'static, T> {
+    /// Convert the values of a query with `f`, failing on the first value it can't
+    /// represent as `obj`.
+    pub(crate) fn convert<V, F>(query: &[V], obj: impl fmt::Debug, f: F) -> Result<Self>
+    where
+        V: Copy + fmt::Display,
+        F: Fn(V) -> Option<T>,
+    {
+        let query = query
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| {
+                f(v).ok_or_else(|| format!("Invalid query value {v} at position {i} for {obj:?}"))
+            })
+            .collect::<StdResult<Vec<_>, _>>()?;
+        Ok(Self::Owned(query))
+    }
+}
+
+impl<T> Deref for QueryVec<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Self::Borrowed(query) => query,
+            Self::Owned(query) => query,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for QueryVec<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: PartialEq> PartialEq for QueryVec<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for QueryVec<'_, T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for QueryVec<'_, T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> StdResult<Self, D::Error> {
+        Vec::deserialize(deserializer).map(Self::Owned)
+    }
+}